@@ -0,0 +1,53 @@
+//! Compares tracing rays one by one with tracing them in packets on the scenes of `scene1` and
+//! `scene2`, with 400 samples per pixel on 200×200 pixels.
+
+use parry3d::math::Point;
+use ray::{
+    camera::Camera,
+    objects::{RenderSettings, Scene},
+    packet::PacketWidth,
+};
+use std::{f32::consts::TAU, time::Instant};
+
+#[allow(dead_code)]
+mod scene1;
+#[allow(dead_code)]
+mod scene2;
+
+const RAYS: usize = 400;
+const RESOLUTION: u32 = 200;
+const RUNS: usize = 2;
+
+fn main() {
+    println!("Scene 1: balls and round cuboid");
+    compare(scene1::make_scene, Point::origin(), 6);
+    println!("Scene 2: Balls");
+    compare(scene2::make_scene, Point::new(4.0, 0.0, 3.0), 10);
+}
+
+/// renders the scene with every packet width
+fn compare(make_scene: fn() -> Scene, target: Point<f32>, max_reflections: usize) {
+    for packet in [
+        PacketWidth::Scalar,
+        PacketWidth::Four,
+        PacketWidth::Eight,
+        PacketWidth::Sixteen,
+    ] {
+        let scene = make_scene().with_render_settings(RenderSettings {
+            packet,
+            ..Default::default()
+        });
+        for _ in 0..RUNS {
+            let mut camera = Camera::face_towards(
+                Point::new(20.0, 20.0, 20.0),
+                target,
+                TAU / 8.0,
+                RESOLUTION,
+                RESOLUTION,
+            );
+            let now = Instant::now();
+            scene.render_par(&mut camera, RAYS, max_reflections);
+            println!("  {:?}: {:.2?}", packet, now.elapsed());
+        }
+    }
+}
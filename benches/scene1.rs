@@ -5,7 +5,7 @@ use parry3d::{
 };
 use ray::{
     camera::Camera,
-    objects::{Brdf, Color, Object, Scene, Shape},
+    objects::{Color, Object, Scene, Shape},
 };
 use std::{f32::consts::TAU, time::Instant};
 
@@ -42,69 +42,55 @@ fn main() {
     }
 }
 
-fn make_scene() -> Scene {
+pub fn make_scene() -> Scene {
     Scene::new(Color(0.0, 0.0, 0.0))
         .add_objects(vec![
-            Object {
-                shape: Shape::Ball(Ball { radius: 1.0 }),
-                isometry: Isometry::translation(0.0, 0.0, 0.0),
-                brdf: Brdf::One,
-                color: Color(0.8, 0.8, 0.8),
-                is_light_source: false,
-            },
-            Object {
-                shape: Shape::Ball(Ball { radius: 1.0 }),
-                isometry: Isometry::translation(4.0, 0.0, 0.0),
-                brdf: Brdf::One,
-                color: Color(1.0, 0.0, 0.0),
-                is_light_source: false,
-            },
-            Object {
-                shape: Shape::Ball(Ball { radius: 1.0 }),
-                isometry: Isometry::translation(0.0, 4.0, 0.0),
-                brdf: Brdf::One,
-                color: Color(0.0, 1.0, 0.0),
-                is_light_source: false,
-            },
-            Object {
-                shape: Shape::Ball(Ball { radius: 1.0 }),
-                isometry: Isometry::translation(0.0, 0.0, 4.0),
-                brdf: Brdf::One,
-                color: Color(0.0, 0.0, 1.0),
-                is_light_source: false,
-            },
-            Object {
-                shape: Shape::Ball(Ball { radius: 1.0 }),
-                isometry: Isometry::translation(0.0, 0.0, 8.0),
-                brdf: Brdf::One,
-                color: Color(0.5, 0.8, 0.2),
-                is_light_source: false,
-            },
+            Object::new(
+                Shape::Ball(Ball { radius: 1.0 }),
+                Isometry::translation(0.0, 0.0, 0.0),
+                Color(0.8, 0.8, 0.8),
+            ),
+            Object::new(
+                Shape::Ball(Ball { radius: 1.0 }),
+                Isometry::translation(4.0, 0.0, 0.0),
+                Color(1.0, 0.0, 0.0),
+            ),
+            Object::new(
+                Shape::Ball(Ball { radius: 1.0 }),
+                Isometry::translation(0.0, 4.0, 0.0),
+                Color(0.0, 1.0, 0.0),
+            ),
+            Object::new(
+                Shape::Ball(Ball { radius: 1.0 }),
+                Isometry::translation(0.0, 0.0, 4.0),
+                Color(0.0, 0.0, 1.0),
+            ),
+            Object::new(
+                Shape::Ball(Ball { radius: 1.0 }),
+                Isometry::translation(0.0, 0.0, 8.0),
+                Color(0.5, 0.8, 0.2),
+            ),
         ])
         .add_objects(vec![
-            Object {
-                shape: Shape::Ball(Ball { radius: 10.0 }),
-                isometry: Isometry::translation(10.0, 0.0, 40.0),
-                brdf: Brdf::One,
-                color: Color(20.0, 30.0, 30.0),
-                is_light_source: true,
-            },
-            Object {
-                shape: Shape::Ball(Ball { radius: 12.0 }),
-                isometry: Isometry::translation(0.0, 40.0, -30.0),
-                brdf: Brdf::One,
-                color: Color(30.0, 24.0, 20.0),
-                is_light_source: true,
-            },
+            Object::new(
+                Shape::Ball(Ball { radius: 10.0 }),
+                Isometry::translation(10.0, 0.0, 40.0),
+                Color(20.0, 30.0, 30.0),
+            )
+            .with_light_source(),
+            Object::new(
+                Shape::Ball(Ball { radius: 12.0 }),
+                Isometry::translation(0.0, 40.0, -30.0),
+                Color(30.0, 24.0, 20.0),
+            )
+            .with_light_source(),
         ])
-        .add_objects(vec![Object {
-            shape: Shape::RoundCuboid(RoundCuboid {
+        .add_objects(vec![Object::new(
+            Shape::RoundCuboid(RoundCuboid {
                 inner_shape: Cuboid::new(Vector::new(6.0, 6.0, 6.0)),
                 border_radius: 0.75,
             }),
-            isometry: Isometry::translation(0.0, 0.0, -8.0),
-            brdf: Brdf::One,
-            color: Color(0.8, 0.8, 0.8),
-            is_light_source: false,
-        }])
+            Isometry::translation(0.0, 0.0, -8.0),
+            Color(0.8, 0.8, 0.8),
+        )])
 }
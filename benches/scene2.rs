@@ -1,7 +1,7 @@
 use parry3d::math::Point;
 use parry3d::{math::Isometry, shape::Ball};
 use ray::camera::Camera;
-use ray::objects::{Brdf, Object, Scene, Shape, Color};
+use ray::objects::{Color, Object, Scene, Shape};
 use std::f32::consts::TAU;
 use std::time::Instant;
 
@@ -24,54 +24,44 @@ fn main() {
     camera.save_img("./out/scene_2.png").unwrap();
 }
 
-fn make_scene() -> Scene {
+pub fn make_scene() -> Scene {
     Scene::new(Color(0.0, 0.0, 0.0))
         // Balls
         .add_objects(vec![
-            Object {
-                shape: Shape::Ball(Ball { radius: 3.0 }),
-                isometry: Isometry::translation(0.0, 0.0, 0.0),
-                brdf: Brdf::One,
-                color: Color(0.9, 0.9, 0.9),
-                is_light_source: false,
-            },
-            Object {
-                shape: Shape::Ball(Ball { radius: 3.0 }),
-                isometry: Isometry::translation(10.0, 0.0, 0.0),
-                brdf: Brdf::One,
-                color: Color(0.1, 0.8, 0.9),
-                is_light_source: false,
-            },
-            Object {
-                shape: Shape::Ball(Ball { radius: 3.0 }),
-                isometry: Isometry::translation(5.0, 3.0, 11.0),
-                brdf: Brdf::One,
-                color: Color(0.8, 0.1, 0.8),
-                is_light_source: false,
-            },
-            Object {
-                shape: Shape::Ball(Ball { radius: 3.0 }),
-                isometry: Isometry::translation(5.0, 3.0, -8.0),
-                brdf: Brdf::One,
-                color: Color(0.8, 0.2, 0.8),
-                is_light_source: false,
-            },
+            Object::new(
+                Shape::Ball(Ball { radius: 3.0 }),
+                Isometry::translation(0.0, 0.0, 0.0),
+                Color(0.9, 0.9, 0.9),
+            ),
+            Object::new(
+                Shape::Ball(Ball { radius: 3.0 }),
+                Isometry::translation(10.0, 0.0, 0.0),
+                Color(0.1, 0.8, 0.9),
+            ),
+            Object::new(
+                Shape::Ball(Ball { radius: 3.0 }),
+                Isometry::translation(5.0, 3.0, 11.0),
+                Color(0.8, 0.1, 0.8),
+            ),
+            Object::new(
+                Shape::Ball(Ball { radius: 3.0 }),
+                Isometry::translation(5.0, 3.0, -8.0),
+                Color(0.8, 0.2, 0.8),
+            ),
         ])
         // Lights
         .add_objects(vec![
-            Object {
-                shape: Shape::Ball(Ball { radius: 10.0 }),
-                isometry: Isometry::translation(10.0, 0.0, 40.0),
-                brdf: Brdf::One,
-                color: Color(25.0, 25.0, 25.0),
-                is_light_source: true,
-            },
-            Object {
-                shape: Shape::Ball(Ball { radius: 12.0 }),
-                isometry: Isometry::translation(0.0, 40.0, -30.0),
-                brdf: Brdf::One,
-                color: Color(30.0, 24.0, 15.0),
-                is_light_source: true,
-            },
+            Object::new(
+                Shape::Ball(Ball { radius: 10.0 }),
+                Isometry::translation(10.0, 0.0, 40.0),
+                Color(25.0, 25.0, 25.0),
+            )
+            .with_light_source(),
+            Object::new(
+                Shape::Ball(Ball { radius: 12.0 }),
+                Isometry::translation(0.0, 40.0, -30.0),
+                Color(30.0, 24.0, 15.0),
+            )
+            .with_light_source(),
         ])
 }
@@ -2,7 +2,7 @@ use parry3d::math::{Point, Vector};
 use parry3d::shape::{Capsule, Cuboid, Segment};
 use parry3d::{math::Isometry, shape::Ball};
 use ray::camera::Camera;
-use ray::objects::{Brdf, Object, Scene, Shape, Color};
+use ray::objects::{Color, Object, Scene, Shape};
 use std::f32::consts::TAU;
 use std::time::Instant;
 
@@ -30,57 +30,48 @@ fn main() {
 fn make_scene() -> Scene {
     Scene::new(Color(0.0, 0.0, 0.0))
         .add_objects(vec![
-            Object {
-                shape: Shape::Cuboid(Cuboid::new(Vector::new(2.0, 10.0, 10.0))),
-                isometry: Isometry::translation(-2.0, 10.0, 10.0),
-                brdf: Brdf::One,
-                color: Color(0.3, 0.9, 0.9),
-                is_light_source: false,
-            },
-            Object {
-                shape: Shape::Cuboid(Cuboid::new(Vector::new(10.0, 2.0, 10.0))),
-                isometry: Isometry::translation(10.0, -2.0, 10.0),
-                brdf: Brdf::One,
-                color: Color(0.9, 0.3, 0.9),
-                is_light_source: false,
-            },
-            Object {
-                shape: Shape::Cuboid(Cuboid::new(Vector::new(10.0, 10.0, 2.0))),
-                isometry: Isometry::translation(10.0, 10.0, -2.0),
-                brdf: Brdf::One,
-                color: Color(0.9, 0.9, 0.3),
-                is_light_source: false,
-            },
+            Object::new(
+                Shape::Cuboid(Cuboid::new(Vector::new(2.0, 10.0, 10.0))),
+                Isometry::translation(-2.0, 10.0, 10.0),
+                Color(0.3, 0.9, 0.9),
+            ),
+            Object::new(
+                Shape::Cuboid(Cuboid::new(Vector::new(10.0, 2.0, 10.0))),
+                Isometry::translation(10.0, -2.0, 10.0),
+                Color(0.9, 0.3, 0.9),
+            ),
+            Object::new(
+                Shape::Cuboid(Cuboid::new(Vector::new(10.0, 10.0, 2.0))),
+                Isometry::translation(10.0, 10.0, -2.0),
+                Color(0.9, 0.9, 0.3),
+            ),
         ])
         .add_objects(vec![
-            Object {
-                shape: Shape::Ball(Ball::new(1.0)),
-                isometry: Isometry::translation(3.5, 6.0, 1.0),
-                brdf: Brdf::One,
-                color: Color(0.9, 0.8, 0.2),
-                is_light_source: false,
-            },
-            Object {
-                shape: Shape::Cuboid(Cuboid::new(Vector::new(3.0, 2.0, 3.5))),
-                isometry: Isometry::translation(7.0, 4.0, 0.0),
-                brdf: Brdf::One,
-                color: Color(0.9, 0.2, 0.3),
-                is_light_source: false,
-            },
+            Object::new(
+                Shape::Ball(Ball::new(1.0)),
+                Isometry::translation(3.5, 6.0, 1.0),
+                Color(0.9, 0.8, 0.2),
+            ),
+            Object::new(
+                Shape::Cuboid(Cuboid::new(Vector::new(3.0, 2.0, 3.5))),
+                Isometry::translation(7.0, 4.0, 0.0),
+                Color(0.9, 0.2, 0.3),
+            ),
         ])
-        .add_object(Object {
-            shape: Shape::Capsule(Capsule {
-                segment: Segment {
-                    a: Point::new(4.0, 0.2, 10.0),
-                    b: Point::new(11.0, 0.2, 10.0),
-                },
-                radius: 0.2,
-            }),
-            isometry: Isometry::identity(),
-            brdf: Brdf::One,
-            color: Color(1.0, 1.0, 0.5).scale(100.0),
-            is_light_source: true,
-        })
+        .add_object(
+            Object::new(
+                Shape::Capsule(Capsule {
+                    segment: Segment {
+                        a: Point::new(4.0, 0.2, 10.0),
+                        b: Point::new(11.0, 0.2, 10.0),
+                    },
+                    radius: 0.2,
+                }),
+                Isometry::identity(),
+                Color(1.0, 1.0, 0.5).scale(100.0),
+            )
+            .with_light_source(),
+        )
         // .add_object(Object {
         //     shape: Shape::Cuboid(Cuboid::new(Vector::new(5.0, 0.2, 0.2))),
         //     isometry: Isometry::translation(10.0, 3.0, 10.0),
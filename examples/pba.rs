@@ -5,9 +5,12 @@ use parry3d::{
 
 use ray::{
     camera::Camera,
-    objects::{Brdf, Color, Object, Scene, Shape},
+    elements::{AtomRadius, PeriodicTable},
+    graph::{Group, Prototype},
+    objects::{Color, Object, RenderSettings, Scene, Shape},
+    progressive::Progressive,
 };
-use std::{f32::consts::TAU, time::Instant};
+use std::f32::consts::TAU;
 
 const HALF_UNIT_CELLS: u32 = 3;
 const RESOLUTION: u32 = 1200;
@@ -23,15 +26,22 @@ fn main() {
         RESOLUTION,
         RESOLUTION,
     );
-    let now = Instant::now();
-    scene.render_par(&mut camera, 3000, 4);
-    println!("took: {:.2?}", now.elapsed());
-
-    camera.save_img("./out/pba.png").expect("failed to save");
+    // the image is refined in passes and saved after each of them
+    let settings = RenderSettings {
+        rays: 3000,
+        max_reflections: 4,
+        ..Default::default()
+    };
+    let progressive = Progressive::new(100, settings.rays).with_preview("./out/pba.png");
+    scene
+        .render_progressive(&mut camera, &settings, &progressive, |pass, _| {
+            println!("{} spp after {:.2?}", pass.rays, pass.elapsed)
+        })
+        .expect("failed to save");
 }
 
 fn make_scene() -> Scene {
-    let mut scene = Scene::new(Color(1.0, 1.0, 1.0));
+    let mut ions = Group::new("ions");
 
     for x in 0..HALF_UNIT_CELLS {
         for y in 0..HALF_UNIT_CELLS {
@@ -54,52 +64,52 @@ fn make_scene() -> Scene {
                     z as f32 * DIST_MN_MN / 2.0,
                 ]
                 .into();
-                if (x + y + z) % 2 == 0 {
-                    scene = place_ion(scene, Ion::Mn, position)
+                let ion = if (x + y + z) % 2 == 0 {
+                    Ion::Mn
                 } else {
-                    scene = place_ion(scene, Ion::Cyanocobaltate, position)
-                }
+                    Ion::Cyanocobaltate
+                };
+                ions = ions.add_instance(ion.name(), Isometry::from(position));
             }
         }
     }
 
-    scene = scene.add_objects(vec![
-        Object {
-            shape: Shape::Ball(Ball { radius: 10.0 }),
-            isometry: Isometry::translation(30.0, 30.0, 20.0),
-            brdf: Brdf::One,
-            color: Color(15.0, 15.0, 15.0),
-            is_light_source: true,
-        },
-        Object {
-            shape: Shape::Ball(Ball { radius: 10.0 }),
-            isometry: Isometry::translation(40.0, 0.0, 35.0),
-            brdf: Brdf::One,
-            color: Color(15.0, 15.0, 15.0),
-            is_light_source: true,
-        },
-    ]);
-    scene
+    let table = PeriodicTable::new();
+    let scene = Scene::new(Color(1.0, 1.0, 1.0))
+        .add_prototype(ion_prototype(&table, Ion::Mn))
+        .add_prototype(ion_prototype(&table, Ion::Cyanocobaltate))
+        .add_group(ions)
+        .expect("the ions are prototypes of the scene");
+    scene.add_objects(vec![
+        Object::new(
+            Shape::Ball(Ball { radius: 10.0 }),
+            Isometry::translation(30.0, 30.0, 20.0),
+            Color(15.0, 15.0, 15.0),
+        )
+        .with_light_source(),
+        Object::new(
+            Shape::Ball(Ball { radius: 10.0 }),
+            Isometry::translation(40.0, 0.0, 35.0),
+            Color(15.0, 15.0, 15.0),
+        )
+        .with_light_source(),
+    ])
 }
 
-const MN_RADIUS: f32 = 1.2;
-const MN_COLOR: Color = Color(0.37, 0.4, 0.82);
-const CO_RADIUS: f32 = 1.05;
-const CO_COLOR: Color = Color(0.75, 0.08, 0.77);
-const C_RADIUS: f32 = 0.76 * 0.9;
-const C_COLOR: Color = Color(0.6, 0.06, 0.06);
-const N_RADIUS: f32 = 0.71 * 0.9;
-const N_COLOR: Color = Color(0.17, 0.67, 0.24);
+/// the atoms are drawn a little smaller than their covalent radius
+const BALL_SCALE: f32 = 0.9;
 
 const DIST_MN_MN: f32 = 10.0003;
 const CO_C: f32 = 1.89;
 const CO_N: f32 = 3.03;
 
-fn place_ion(mut scene: Scene, ion: Ion, coord_armstrong: Vector<f32>) -> Scene {
-    match ion {
-        Ion::Mn => add_atom(scene, coord_armstrong, MN_COLOR, MN_RADIUS),
+/// the atoms of an ion around the origin, shared by all instances of the ion
+fn ion_prototype(table: &PeriodicTable, ion: Ion) -> Prototype {
+    let origin = Vector::zeros();
+    let atoms = match ion {
+        Ion::Mn => vec![atom(table, "Mn", origin)],
         Ion::Cyanocobaltate => {
-            scene = add_atom(scene, coord_armstrong, CO_COLOR, CO_RADIUS);
+            let mut atoms = vec![atom(table, "Co", origin)];
 
             // find nicer way to generate these unit vecs
             let all_axis: Vec<Vector<f32>> = (0..3)
@@ -111,25 +121,21 @@ fn place_ion(mut scene: Scene, ion: Ion, coord_armstrong: Vector<f32>) -> Scene
                 .collect();
             for dir in all_axis {
                 let c_offset = dir.scale(CO_C);
-                scene = add_atom(scene, coord_armstrong + c_offset, C_COLOR, C_RADIUS);
-                scene = add_atom(scene, coord_armstrong - c_offset, C_COLOR, C_RADIUS);
+                atoms.push(atom(table, "C", c_offset));
+                atoms.push(atom(table, "C", -c_offset));
                 let n_offset = dir.scale(CO_N);
-                scene = add_atom(scene, coord_armstrong + n_offset, N_COLOR, N_RADIUS);
-                scene = add_atom(scene, coord_armstrong - n_offset, N_COLOR, N_RADIUS);
+                atoms.push(atom(table, "N", n_offset));
+                atoms.push(atom(table, "N", -n_offset));
             }
-            scene
+            atoms
         }
-    }
+    };
+    Prototype::new(ion.name(), atoms)
 }
 
-fn add_atom(scene: Scene, coord_armstrong: Vector<f32>, color: Color, radius: f32) -> Scene {
-    scene.add_object(Object {
-        shape: Shape::Ball(Ball { radius }),
-        isometry: Isometry::translation(coord_armstrong.x, coord_armstrong.y, coord_armstrong.z),
-        brdf: Brdf::One,
-        color,
-        is_light_source: false,
-    })
+fn atom(table: &PeriodicTable, symbol: &str, coord_armstrong: Vector<f32>) -> Object {
+    let radius = table.radius(symbol, AtomRadius::Covalent) * BALL_SCALE;
+    table.ball(symbol, coord_armstrong.into(), radius)
 }
 
 /// A type for Ions
@@ -138,3 +144,12 @@ pub enum Ion {
     Mn,
     Cyanocobaltate,
 }
+
+impl Ion {
+    fn name(self) -> &'static str {
+        match self {
+            Ion::Mn => "Mn",
+            Ion::Cyanocobaltate => "Co(CN)6",
+        }
+    }
+}
@@ -6,98 +6,94 @@ use parry3d::{
 };
 use ray::{
     camera::Camera,
+    elements::PeriodicTable,
     objects::{Color, Object, Scene, Shape},
 };
 
 const BALL_RAD: f32 = 0.2;
 const RESOLUTION: u32 = 1200;
-const MN_COLOR: Color = Color(0.37, 0.4, 0.82);
-const CO_COLOR: Color = Color(0.75, 0.08, 0.77);
 
 fn main() {
-    let co_color = CO_COLOR;
+    let table = PeriodicTable::new();
+    let co_color = table.color("Co");
     let balls = vec![
-        Object {
-            shape: Shape::Ball(Ball { radius: BALL_RAD }),
-            isometry: Isometry::translation(0.0, 0.0, 1.0),
-            brdf: ray::objects::Brdf::One,
-            color: co_color,
-            is_light_source: false,
-        },
-        Object {
-            shape: Shape::Ball(Ball { radius: BALL_RAD }),
-            isometry: Isometry::translation(0.0, 0.0, -1.0),
-            brdf: ray::objects::Brdf::One,
-            color: co_color,
-            is_light_source: false,
-        },
-        Object {
-            shape: Shape::Ball(Ball { radius: BALL_RAD }),
-            isometry: Isometry::translation(0.0, 1.0, 0.0),
-            brdf: ray::objects::Brdf::One,
-            color: co_color,
-            is_light_source: false,
-        },
-        Object {
-            shape: Shape::Ball(Ball { radius: BALL_RAD }),
-            isometry: Isometry::translation(0.0, -1.0, 0.0),
-            brdf: ray::objects::Brdf::One,
-            color: co_color,
-            is_light_source: false,
-        },
-        Object {
-            shape: Shape::Ball(Ball { radius: BALL_RAD }),
-            isometry: Isometry::translation(1.0, 0.0, 0.0),
-            brdf: ray::objects::Brdf::One,
-            color: co_color,
-            is_light_source: false,
-        },
-        Object {
-            shape: Shape::Ball(Ball { radius: BALL_RAD }),
-            isometry: Isometry::translation(-1.0, 0.0, 0.0),
-            brdf: ray::objects::Brdf::One,
-            color: co_color,
-            is_light_source: false,
-        },
+        Object::new(
+            Shape::Ball(Ball { radius: BALL_RAD }),
+            Isometry::translation(0.0, 0.0, 1.0),
+            co_color,
+        )
+        .with_brdf(ray::objects::Brdf::One),
+        Object::new(
+            Shape::Ball(Ball { radius: BALL_RAD }),
+            Isometry::translation(0.0, 0.0, -1.0),
+            co_color,
+        )
+        .with_brdf(ray::objects::Brdf::One),
+        Object::new(
+            Shape::Ball(Ball { radius: BALL_RAD }),
+            Isometry::translation(0.0, 1.0, 0.0),
+            co_color,
+        )
+        .with_brdf(ray::objects::Brdf::One),
+        Object::new(
+            Shape::Ball(Ball { radius: BALL_RAD }),
+            Isometry::translation(0.0, -1.0, 0.0),
+            co_color,
+        )
+        .with_brdf(ray::objects::Brdf::One),
+        Object::new(
+            Shape::Ball(Ball { radius: BALL_RAD }),
+            Isometry::translation(1.0, 0.0, 0.0),
+            co_color,
+        )
+        .with_brdf(ray::objects::Brdf::One),
+        Object::new(
+            Shape::Ball(Ball { radius: BALL_RAD }),
+            Isometry::translation(-1.0, 0.0, 0.0),
+            co_color,
+        )
+        .with_brdf(ray::objects::Brdf::One),
     ];
 
     let lights = vec![
-        Object {
-            shape: Shape::Ball(Ball { radius: 5.0 }),
-            isometry: Isometry::translation(10.0, 10.0, 10.0),
-            brdf: ray::objects::Brdf::One,
-            color: Color(14.0, 14.0, 14.0),
-            is_light_source: true,
-        },
-        Object {
-            shape: Shape::Ball(Ball { radius: 5.0 }),
-            isometry: Isometry::translation(10.0, 10.0, 30.0),
-            brdf: ray::objects::Brdf::One,
-            color: Color(10.0, 9.0, 9.0),
-            is_light_source: true,
-        },
+        Object::new(
+            Shape::Ball(Ball { radius: 5.0 }),
+            Isometry::translation(10.0, 10.0, 10.0),
+            Color(14.0, 14.0, 14.0),
+        )
+        .with_brdf(ray::objects::Brdf::One)
+        .with_light_source(),
+        Object::new(
+            Shape::Ball(Ball { radius: 5.0 }),
+            Isometry::translation(10.0, 10.0, 30.0),
+            Color(10.0, 9.0, 9.0),
+        )
+        .with_brdf(ray::objects::Brdf::One)
+        .with_light_source(),
     ];
 
     let scene = Scene::new(Color(1.0, 1.0, 1.0))
-        .add_object(Object {
-            shape: Shape::Ball(Ball { radius: BALL_RAD }),
-            isometry: Isometry::identity(),
-            brdf: ray::objects::Brdf::One,
-            color: MN_COLOR,
-            is_light_source: false,
-        })
+        .add_object(
+            Object::new(
+                Shape::Ball(Ball { radius: BALL_RAD }),
+                Isometry::identity(),
+                table.color("Mn"),
+            )
+            .with_brdf(ray::objects::Brdf::One),
+        )
         .add_objects(balls)
         .add_objects(lights)
-        .add_object(Object {
-            shape: Shape::Capsule(Capsule {
-                segment: Segment::new([1.0, 0.0, 0.0].into(), [0.0, 0.0, 1.0].into()),
-                radius: 0.05,
-            }),
-            isometry: Isometry::identity(),
-            brdf: ray::objects::Brdf::One,
-            color: Color(0.8, 0.8, 0.8),
-            is_light_source: false,
-        });
+        .add_object(
+            Object::new(
+                Shape::Capsule(Capsule {
+                    segment: Segment::new([1.0, 0.0, 0.0].into(), [0.0, 0.0, 1.0].into()),
+                    radius: 0.05,
+                }),
+                Isometry::identity(),
+                Color(0.8, 0.8, 0.8),
+            )
+            .with_brdf(ray::objects::Brdf::One),
+        );
 
     let mut camera = Camera::face_towards(
         Point::new(4.5, 5.9, 2.6),
@@ -4,9 +4,13 @@ use parry3d::{
 };
 use ray::{
     camera::Camera,
-    objects::{Brdf, Color, Object, Scene, Shape},
+    crystal::Lattice,
+    defects::{LatticeBuilder, MotifAtom, MotifSite},
+    elements::{AtomRadius, PeriodicTable},
+    objects::{Color, Object, RenderSettings, Scene, Shape},
+    progressive::{Adaptive, Progressive},
 };
-use std::{f32::consts::TAU, time::Instant};
+use std::f32::consts::TAU;
 
 const HALF_UNIT_CELLS: u32 = 4;
 const RESOLUTION: u32 = 1200;
@@ -23,122 +27,126 @@ fn main() {
         RESOLUTION,
         RESOLUTION,
     );
-    let now = Instant::now();
-    scene.render_par(&mut camera, RAYS, 4);
-    println!("took: {:.2?}", now.elapsed());
-
-    camera
-        .save_img("./out/fefe_vac.png")
+    // the white background stops getting samples after the warm-up
+    let settings = RenderSettings {
+        rays: RAYS,
+        max_reflections: 4,
+        ..Default::default()
+    };
+    let progressive = Progressive::new(50, RAYS)
+        .with_adaptive(Adaptive::new(0.02))
+        .with_preview("./out/fefe_vac.png")
+        .with_heatmap("./out/fefe_vac_samples.png");
+    let pass = scene
+        .render_progressive(&mut camera, &settings, &progressive, |pass, _| {
+            println!(
+                "{:.0} spp on average, {} pixels left after {:.2?}",
+                pass.mean_rays, pass.active, pass.elapsed
+            )
+        })
         .expect("failed to save");
+    println!("took: {:.2?}", pass.elapsed);
 }
 
 fn make_scene() -> Scene {
+    // the hexacyanocobaltate between two manganese along a is missing in every unit cell
+    let cells = HALF_UNIT_CELLS as usize / 2;
+    let mut builder = prussian_blue_analogue();
+    for x in 0..cells {
+        for y in 0..cells {
+            for z in 0..cells {
+                builder = builder.with_vacancy("Co1", [x, y, z]);
+            }
+        }
+    }
+    let structure = builder.build();
+
+    let table = PeriodicTable::new();
+    let radius = |species: &str| table.radius(species, AtomRadius::Covalent) * BALL_SCALE;
     let mut scene = Scene::new(Color(1.0, 1.0, 1.0));
+    for (species, position) in structure.species.iter().zip(&structure.positions) {
+        scene = scene.add_object(table.ball(species, *position, radius(species)));
+    }
+    for vacancy in builder.vacancies() {
+        scene = add_atom(
+            scene,
+            vacancy.coords,
+            Color(0.6, 0.6, 0.6),
+            (DIST_MN_MN / 2.0 - radius("Mn")) * 0.9,
+        );
+    }
 
-    for x in 0..HALF_UNIT_CELLS {
-        for y in 0..HALF_UNIT_CELLS {
-            for z in 0..HALF_UNIT_CELLS {
-                let position: Vector<f32> = [
-                    x as f32 * DIST_MN_MN / 2.0,
-                    y as f32 * DIST_MN_MN / 2.0,
-                    z as f32 * DIST_MN_MN / 2.0,
-                ]
-                .into();
-                if (x + y + z) % 2 == 0 {
-                    scene = place_ion(scene, Ion::Mn, position)
-                } else {
-                    if z == 0 || z == 2 {
-                        if x == 1 || x == 3 {
-                            scene = place_ion(scene, Ion::Vacancy, position);
-                            continue;
-                        }
-                    }
+    scene.add_objects(vec![
+        Object::new(
+            Shape::Ball(Ball { radius: 10.0 }),
+            Isometry::translation(45.0, 30.0, 20.0),
+            Color(15.0, 12.0, 12.0),
+        )
+        .with_light_source(),
+        Object::new(
+            Shape::Ball(Ball { radius: 10.0 }),
+            Isometry::translation(40.0, 0.0, 35.0),
+            Color(15.0, 14.0, 12.0),
+        )
+        .with_light_source(),
+    ])
+}
 
-                    scene = place_ion(scene, Ion::Cyanocobaltate, position)
-                }
-            }
-        }
+/// manganese and hexacyanocobaltate alternating like in rock salt, the four cobalt sites of
+/// the unit cell are labeled `Co1` to `Co4`
+fn prussian_blue_analogue() -> LatticeBuilder {
+    let lattice = Lattice::new(
+        Vector::x() * DIST_MN_MN,
+        Vector::y() * DIST_MN_MN,
+        Vector::z() * DIST_MN_MN,
+    );
+    let cells = HALF_UNIT_CELLS as usize / 2;
+    let mut builder = LatticeBuilder::new(lattice, [cells; 3]);
+    for fract in [
+        [0.0, 0.0, 0.0],
+        [0.5, 0.5, 0.0],
+        [0.5, 0.0, 0.5],
+        [0.0, 0.5, 0.5],
+    ] {
+        builder = builder.with_site(MotifSite::atom("Mn", "Mn", fract.into()));
+    }
+    for (i, fract) in [
+        [0.5, 0.0, 0.0],
+        [0.0, 0.5, 0.0],
+        [0.0, 0.0, 0.5],
+        [0.5, 0.5, 0.5],
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let label = format!("Co{}", i + 1);
+        builder = builder.with_site(MotifSite::group(&label, fract.into(), hexacyanocobaltate()));
     }
+    builder
+}
 
-    scene = scene.add_objects(vec![
-        Object {
-            shape: Shape::Ball(Ball { radius: 10.0 }),
-            isometry: Isometry::translation(45.0, 30.0, 20.0),
-            brdf: Brdf::One,
-            color: Color(15.0, 12.0, 12.0),
-            is_light_source: true,
-        },
-        Object {
-            shape: Shape::Ball(Ball { radius: 10.0 }),
-            isometry: Isometry::translation(40.0, 0.0, 35.0),
-            brdf: Brdf::One,
-            color: Color(15.0, 14.0, 12.0),
-            is_light_source: true,
-        },
-    ]);
-    scene
+fn hexacyanocobaltate() -> Vec<MotifAtom> {
+    let mut atoms = vec![MotifAtom::new("Co", Vector::zeros())];
+    for axis in [Vector::x(), Vector::y(), Vector::z()] {
+        for direction in [axis, -axis] {
+            atoms.push(MotifAtom::new("C", direction * CO_C));
+            atoms.push(MotifAtom::new("N", direction * CO_N));
+        }
+    }
+    atoms
 }
 
-const MN_RADIUS: f32 = 1.2;
-const MN_COLOR: Color = Color(0.37, 0.4, 0.82);
-const CO_RADIUS: f32 = 1.05;
-const CO_COLOR: Color = Color(0.75, 0.08, 0.77);
-const C_RADIUS: f32 = 0.76 * 0.9;
-const C_COLOR: Color = Color(0.6, 0.06, 0.06);
-const N_RADIUS: f32 = 0.71 * 0.9;
-const N_COLOR: Color = Color(0.17, 0.67, 0.24);
+/// the atoms are drawn a little smaller than their covalent radius
+const BALL_SCALE: f32 = 0.9;
 
 const DIST_MN_MN: f32 = 10.0003;
 const CO_C: f32 = 1.89;
 const CO_N: f32 = 3.03;
 
-fn place_ion(mut scene: Scene, ion: Ion, coord_armstrong: Vector<f32>) -> Scene {
-    match ion {
-        Ion::Mn => add_atom(scene, coord_armstrong, MN_COLOR, MN_RADIUS),
-        Ion::Cyanocobaltate => {
-            scene = add_atom(scene, coord_armstrong, CO_COLOR, CO_RADIUS);
-
-            // find nicer way to generate these unit vecs
-            let all_axis: Vec<Vector<f32>> = (0..3)
-                .map(|i| {
-                    let mut e = [0.0, 0.0, 0.0];
-                    e[i] = 1.0;
-                    e.into()
-                })
-                .collect();
-            for dir in all_axis {
-                let c_offset = dir.scale(CO_C);
-                scene = add_atom(scene, coord_armstrong + c_offset, C_COLOR, C_RADIUS);
-                scene = add_atom(scene, coord_armstrong - c_offset, C_COLOR, C_RADIUS);
-                let n_offset = dir.scale(CO_N);
-                scene = add_atom(scene, coord_armstrong + n_offset, N_COLOR, N_RADIUS);
-                scene = add_atom(scene, coord_armstrong - n_offset, N_COLOR, N_RADIUS);
-            }
-            scene
-        }
-        Ion::Vacancy => add_atom(
-            scene,
-            coord_armstrong,
-            Color(0.6, 0.6, 0.6),
-            (DIST_MN_MN / 2.0 - MN_RADIUS) * 0.9,
-        ),
-    }
-}
-
 fn add_atom(scene: Scene, coord_armstrong: Vector<f32>, color: Color, radius: f32) -> Scene {
-    scene.add_object(Object {
-        shape: Shape::Ball(Ball { radius }),
-        isometry: Isometry::translation(coord_armstrong.x, coord_armstrong.y, coord_armstrong.z),
-        brdf: Brdf::One,
+    scene.add_object(Object::new(
+        Shape::Ball(Ball { radius }),
+        Isometry::translation(coord_armstrong.x, coord_armstrong.y, coord_armstrong.z),
         color,
-        is_light_source: false,
-    })
-}
-
-/// A type for Ions
-#[derive(Clone, Copy)]
-pub enum Ion {
-    Mn,
-    Cyanocobaltate,
-    Vacancy,
+    ))
 }
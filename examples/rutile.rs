@@ -0,0 +1,47 @@
+use ray::{
+    cell::AxisTriad,
+    crystal::Crystal,
+    elements::PeriodicTable,
+    objects::Color,
+    polyhedra::{CoordinationPolyhedra, PolyhedronStyle},
+    structure::Representation,
+};
+use std::time::Instant;
+
+fn main() {
+    println!("Rutile");
+    let crystal = Crystal::open_cif("./examples/data/rutile.cif").expect("failed to read cif");
+    let structure = crystal.structure([3, 3, 4]);
+    let table = PeriodicTable::default();
+    let octahedra = CoordinationPolyhedra::new().with_centre(
+        "Ti",
+        PolyhedronStyle {
+            ligands: vec!["O".into()],
+            ..Default::default()
+        },
+    );
+    let representation = Representation::BallAndStick {
+        ball_scale: 0.4,
+        bond_radius: 0.1,
+    };
+    let scene = structure
+        .scene(&representation, &table)
+        .add_objects(structure.polyhedra(&octahedra, &table))
+        .add_objects(
+            crystal
+                .lattice
+                .outline([3, 3, 4], 0.03, Color(0.2, 0.2, 0.2)),
+        );
+    let mut camera = scene.camera_settings().build();
+    let now = Instant::now();
+    scene.render_par(&mut camera, 300, 4);
+    println!("took: {:.2?}", now.elapsed());
+    AxisTriad::default().render(
+        &crystal.lattice,
+        scene.camera_settings(),
+        Color(1.0, 1.0, 1.0),
+        &mut camera,
+    );
+
+    camera.save_img("./out/rutile.png").expect("failed to save");
+}
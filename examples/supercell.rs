@@ -0,0 +1,94 @@
+use parry3d::math::{Isometry, Point, Vector};
+use ray::{
+    camera::Camera,
+    elements::{AtomRadius, PeriodicTable},
+    graph::{Group, Prototype},
+    objects::{Color, Object, Scene},
+};
+use std::{f32::consts::TAU, time::Instant};
+
+const CELLS: u32 = 50;
+const RESOLUTION: u32 = 600;
+
+/// the atoms are drawn a little smaller than their covalent radius
+const BALL_SCALE: f32 = 0.9;
+
+const DIST_MN_MN: f32 = 10.0003;
+const CO_C: f32 = 1.89;
+const CO_N: f32 = 3.03;
+
+/// A 50×50×50 supercell of a Prussian blue analogue with 7 million atoms, every unit cell is an
+/// instance of one prototype with its 56 atoms.
+fn main() {
+    println!("Prussian Blue Analog supercell");
+    let now = Instant::now();
+    let scene = make_scene();
+    println!(
+        "{} instances of {} atoms, built in {:.2?}",
+        scene.instances().len(),
+        scene.prototypes()[0].objects().len(),
+        now.elapsed()
+    );
+    let size = CELLS as f32 * DIST_MN_MN;
+    let mut camera = Camera::face_towards(
+        Point::new(2.8 * size, 2.0 * size, 2.4 * size),
+        Point::new(size / 2.0, size / 2.0, size / 2.0),
+        TAU / 10.0,
+        RESOLUTION,
+        RESOLUTION,
+    );
+    let now = Instant::now();
+    scene.render_par_with_progress(&mut camera, 16, 3);
+    println!("took: {:.2?}", now.elapsed());
+
+    camera
+        .save_img("./out/supercell.png")
+        .expect("failed to save");
+}
+
+fn make_scene() -> Scene {
+    let mut cells = Group::new("cells");
+    for x in 0..CELLS {
+        for y in 0..CELLS {
+            for z in 0..CELLS {
+                let position = Vector::new(x as f32, y as f32, z as f32) * DIST_MN_MN;
+                cells = cells.add_instance("unit cell", Isometry::from(position));
+            }
+        }
+    }
+    let table = PeriodicTable::new();
+    Scene::new(Color(1.0, 1.0, 1.0))
+        .add_prototype(Prototype::new("unit cell", unit_cell(&table)))
+        .add_group(cells)
+        .expect("the cells are instances of the unit cell")
+}
+
+/// the ions on the corners of the eight octants of the face centred cubic cell
+fn unit_cell(table: &PeriodicTable) -> Vec<Object> {
+    let mut atoms = Vec::new();
+    for x in 0..2 {
+        for y in 0..2 {
+            for z in 0..2 {
+                let centre = Vector::new(x as f32, y as f32, z as f32) * DIST_MN_MN / 2.0;
+                if (x + y + z) % 2 == 0 {
+                    atoms.push(atom(table, "Mn", centre));
+                    continue;
+                }
+                atoms.push(atom(table, "Co", centre));
+                for axis in 0..3 {
+                    let dir = Vector::ith(axis, 1.0);
+                    for sign in [1.0, -1.0] {
+                        atoms.push(atom(table, "C", centre + dir * sign * CO_C));
+                        atoms.push(atom(table, "N", centre + dir * sign * CO_N));
+                    }
+                }
+            }
+        }
+    }
+    atoms
+}
+
+fn atom(table: &PeriodicTable, symbol: &str, centre: Vector<f32>) -> Object {
+    let radius = table.radius(symbol, AtomRadius::Covalent) * BALL_SCALE;
+    table.ball(symbol, centre.into(), radius)
+}
@@ -0,0 +1,37 @@
+use parry3d::math::Vector;
+use ray::{
+    animation::{Animation, CameraAnimation},
+    crystal::Crystal,
+    elements::PeriodicTable,
+    structure::Representation,
+};
+use std::{fs, time::Instant};
+
+const FRAMES: usize = 48;
+const SECONDS: f32 = 4.0;
+
+fn main() {
+    println!("Rutile turntable");
+    let crystal = Crystal::open_cif("./examples/data/rutile.cif").expect("failed to read cif");
+    let structure = crystal.structure([2, 2, 2]);
+    let table = PeriodicTable::default();
+    let representation = Representation::BallAndStick {
+        ball_scale: 0.4,
+        bond_radius: 0.1,
+    };
+    let mut scene = structure.scene(&representation, &table);
+    let turntable = CameraAnimation::turntable(scene.camera_settings(), Vector::z(), SECONDS);
+    scene = scene.with_animation(Animation::new().with_camera(turntable));
+
+    fs::create_dir_all("./out/turntable").expect("failed to create the output directory");
+    let now = Instant::now();
+    for frame in 0..FRAMES {
+        scene.at_time(frame as f32 / FRAMES as f32 * SECONDS);
+        let mut camera = scene.camera_settings().build();
+        scene.render_par(&mut camera, 100, 4);
+        camera
+            .save_img(format!("./out/turntable/frame_{:05}.png", frame))
+            .expect("failed to save");
+    }
+    println!("took: {:.2?}", now.elapsed());
+}
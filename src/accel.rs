@@ -0,0 +1,200 @@
+//! The two-level acceleration structure of a scene.
+//!
+//! Every prototype has a bottom-level hierarchy (BLAS) over its objects in their local space and
+//! the objects placed directly in the scene have one more. The top-level hierarchy (TLAS) indexes
+//! the instances of these by their bounds in world space, swept over their motion while the
+//! shutter is open. Rays are transformed into the space of an instance before they descend into
+//! its BLAS, so moving instances only rebuilds the TLAS and a prototype is stored once no matter
+//! how often it is instanced.
+
+use std::collections::HashMap;
+
+use parry3d::{
+    bounding_volume::Aabb,
+    math::Real,
+    query::{Ray, RayCast, RayIntersection},
+};
+
+use crate::{
+    bvh::{self, Bvh},
+    graph::{Instance, Prototype},
+    objects::Object,
+    packet::{RayPacket, Spheres},
+};
+
+/// A hierarchy over objects in their local space.
+#[derive(Debug, Clone, Default)]
+pub struct Blas {
+    bvh: Bvh,
+    /// the bounds of the objects, a ray has to pass through them before it is cast against
+    /// the shape of the object
+    bounds: Vec<Option<Aabb>>,
+    /// the spheres among the objects, which are intersected without going through parry
+    spheres: Spheres,
+}
+
+impl Blas {
+    pub fn new(objects: &[Object]) -> Self {
+        let bounds: Vec<Option<Aabb>> = objects.iter().map(Object::aabb).collect();
+        Self {
+            bvh: Bvh::new(&bounds),
+            bounds,
+            spheres: Spheres::new(objects),
+        }
+    }
+
+    /// updates the bounds after the objects moved, keeping the hierarchy, which gets slower to
+    /// traverse the farther they move, it is rebuilt if objects were added or removed
+    pub fn refit(&mut self, objects: &[Object]) {
+        let bounds: Vec<Option<Aabb>> = objects.iter().map(Object::aabb).collect();
+        let same_objects = bounds.len() == self.bounds.len()
+            && bounds
+                .iter()
+                .zip(&self.bounds)
+                .all(|(new, old)| new.is_some() == old.is_some());
+        if !same_objects {
+            *self = Self::new(objects);
+            return;
+        }
+        self.bvh.refit(&bounds);
+        self.bounds = bounds;
+        self.spheres = Spheres::new(objects);
+    }
+
+    /// the bounds of the objects over the frame interval, `None` if one of them is unbounded
+    pub fn aabb(&self) -> Option<Aabb> {
+        self.bvh.aabb()
+    }
+
+    /// the closest of the objects the hierarchy was built over hit by a ray at `time`,
+    /// with the index of the object
+    pub fn closest(
+        &self,
+        objects: &[Object],
+        ray: &Ray,
+        time: f32,
+    ) -> Option<(Real, (usize, RayIntersection))> {
+        self.bvh.closest(ray, |i| {
+            if self.spheres.contains(i) {
+                let toi = self.spheres.cast(i, ray)?;
+                return Some((toi, (i, self.spheres.intersection(i, ray, toi))));
+            }
+            if let Some(aabb) = &self.bounds[i] {
+                aabb.cast_local_ray(ray, Real::MAX, true)?;
+            }
+            let intersection = objects[i]
+                .get_intersection(ray, time)
+                .filter(|inter| inter.toi > 0.0)?;
+            Some((intersection.toi, (i, intersection)))
+        })
+    }
+
+    /// the objects hit by the lanes of a packet before their closest hit so far, which is
+    /// shortened to the new hits
+    pub fn closest_packet<const N: usize>(
+        &self,
+        objects: &[Object],
+        packet: &mut RayPacket<N>,
+    ) -> [Option<(usize, RayIntersection)>; N] {
+        // the normals of spheres are only computed for the closest hits
+        let mut hits: [Option<(usize, Option<RayIntersection>)>; N] = [None; N];
+        self.bvh.traverse_packet(packet, |packet, i| {
+            if self.spheres.contains(i) {
+                let hit = self.spheres.cast_packet(i, packet);
+                for (lane, _) in hit.iter().enumerate().filter(|(_, hit)| **hit) {
+                    hits[lane] = Some((i, None));
+                }
+                return;
+            }
+            let inside = self.bounds[i].map_or([true; N], |aabb| packet.cast_aabb(&aabb));
+            for lane in (0..packet.len()).filter(|lane| inside[*lane]) {
+                let Some(intersection) = objects[i]
+                    .get_intersection(&packet.ray(lane), packet.time[lane])
+                    .filter(|inter| inter.toi > 0.0 && inter.toi < packet.toi[lane])
+                else {
+                    continue;
+                };
+                packet.toi[lane] = intersection.toi;
+                hits[lane] = Some((i, Some(intersection)));
+            }
+        });
+        std::array::from_fn(|lane| {
+            let (i, intersection) = hits[lane]?;
+            Some((
+                i,
+                intersection.unwrap_or_else(|| {
+                    self.spheres
+                        .intersection(i, &packet.ray(lane), packet.toi[lane])
+                }),
+            ))
+        })
+    }
+}
+
+/// What the top-level hierarchy indexes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlasEntry {
+    /// the objects placed directly in the scene
+    Objects,
+    /// an instance with the index of its prototype
+    Instance { instance: usize, prototype: usize },
+}
+
+/// A hierarchy over the instances of the bottom-level hierarchies in world space.
+#[derive(Debug, Clone, Default)]
+pub struct Tlas {
+    bvh: Bvh,
+    entries: Vec<TlasEntry>,
+}
+
+impl Tlas {
+    /// the prototypes of the instances have to be among `prototypes`, which `Scene` checks when
+    /// they are added
+    pub fn new(objects: &Blas, instances: &[Instance], prototypes: &[Prototype]) -> Self {
+        let indices: HashMap<&str, usize> = prototypes
+            .iter()
+            .enumerate()
+            .map(|(i, prototype)| (prototype.name.as_str(), i))
+            .collect();
+        let mut entries = vec![TlasEntry::Objects];
+        let mut bounds = vec![objects.aabb()];
+        for (i, instance) in instances.iter().enumerate() {
+            let Some(&prototype) = indices.get(instance.prototype.as_str()) else {
+                panic!(
+                    "unknown prototype `{}` of instance {}",
+                    instance.prototype, i
+                );
+            };
+            entries.push(TlasEntry::Instance {
+                instance: i,
+                prototype,
+            });
+            bounds.push(prototypes[prototype].blas().aabb().map(|aabb| {
+                bvh::swept_aabb(&aabb, &instance.isometry, instance.end_isometry.as_ref())
+            }));
+        }
+        Self {
+            bvh: Bvh::new(&bounds),
+            entries,
+        }
+    }
+
+    /// the closest hit of the ray, `intersect` casts it against the BLAS of an entry
+    pub fn closest<T>(
+        &self,
+        ray: &Ray,
+        mut intersect: impl FnMut(TlasEntry) -> Option<(Real, T)>,
+    ) -> Option<(Real, T)> {
+        self.bvh.closest(ray, |i| intersect(self.entries[i]))
+    }
+
+    /// visits the entries any ray of the packet passes through, like `Bvh::traverse_packet`
+    pub fn traverse_packet<const N: usize>(
+        &self,
+        packet: &mut RayPacket<N>,
+        mut intersect: impl FnMut(&mut RayPacket<N>, TlasEntry),
+    ) {
+        self.bvh
+            .traverse_packet(packet, |packet, i| intersect(packet, self.entries[i]))
+    }
+}
@@ -0,0 +1,417 @@
+//! Keyframe animation of the camera and the objects of a scene.
+//!
+//! Every animated property has a track of keyframes sorted by time, the interpolation of a
+//! keyframe shapes the segment up to the next one. Rotations always follow the shortest arc,
+//! Bezier easing still changes their speed.
+
+use std::f32::consts::TAU;
+
+use nalgebra::{Translation, Unit, UnitQuaternion};
+use parry3d::math::{Isometry, Point, Vector};
+
+use crate::{
+    camera::CameraSettings,
+    objects::{Color, Object},
+};
+
+/// A value that can be keyframed.
+pub trait Animatable: Copy {
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+
+    /// the uniform Catmull-Rom spline between `points[1]` and `points[2]`
+    fn catmull_rom(points: [&Self; 4], t: f32) -> Self;
+}
+
+fn catmull_rom([p0, p1, p2, p3]: [f32; 4], t: f32) -> f32 {
+    let (t2, t3) = (t * t, t * t * t);
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+impl Animatable for f32 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+
+    fn catmull_rom(points: [&Self; 4], t: f32) -> Self {
+        catmull_rom(points.map(|p| *p), t)
+    }
+}
+
+impl Animatable for Vector<f32> {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Vector::lerp(self, other, t)
+    }
+
+    fn catmull_rom(points: [&Self; 4], t: f32) -> Self {
+        Vector::from_fn(|i, _| catmull_rom(points.map(|p| p[i]), t))
+    }
+}
+
+impl Animatable for Point<f32> {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self.coords.lerp(&other.coords, t).into()
+    }
+
+    fn catmull_rom(points: [&Self; 4], t: f32) -> Self {
+        Vector::catmull_rom(points.map(|p| &p.coords), t).into()
+    }
+}
+
+impl Animatable for Color {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Color::lerp(self, other, t)
+    }
+
+    fn catmull_rom(points: [&Self; 4], t: f32) -> Self {
+        Color(
+            catmull_rom(points.map(|p| p.0), t),
+            catmull_rom(points.map(|p| p.1), t),
+            catmull_rom(points.map(|p| p.2), t),
+        )
+    }
+}
+
+impl Animatable for UnitQuaternion<f32> {
+    /// spherical linear interpolation
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        // opposite rotations have no unique arc between them, `try_slerp` fails on those
+        self.try_slerp(other, t, 1e-6)
+            .unwrap_or_else(|| self.nlerp(other, t))
+    }
+
+    /// slerps between the middle rotations
+    fn catmull_rom(points: [&Self; 4], t: f32) -> Self {
+        Animatable::lerp(points[1], points[2], t)
+    }
+}
+
+/// interpolates the translation linearly and the rotation spherically
+impl Animatable for Isometry<f32> {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Isometry::from_parts(
+            Translation::from(Animatable::lerp(
+                &self.translation.vector,
+                &other.translation.vector,
+                t,
+            )),
+            Animatable::lerp(&self.rotation, &other.rotation, t),
+        )
+    }
+
+    fn catmull_rom(points: [&Self; 4], t: f32) -> Self {
+        Isometry::from_parts(
+            Translation::from(Vector::catmull_rom(
+                points.map(|p| &p.translation.vector),
+                t,
+            )),
+            UnitQuaternion::catmull_rom(points.map(|p| &p.rotation), t),
+        )
+    }
+}
+
+/// How a track moves from a keyframe to the next one.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    /// a smooth curve through the keyframes, shaped by the neighbouring ones
+    CatmullRom,
+    /// a linear interpolation eased by the cubic Bezier curve from (0, 0) to (1, 1) with the
+    /// control points (x1, y1) and (x2, y2), like `cubic-bezier` in CSS
+    Bezier { x1: f32, y1: f32, x2: f32, y2: f32 },
+}
+
+impl Interpolation {
+    pub const EASE_IN_OUT: Self = Self::Bezier {
+        x1: 0.42,
+        y1: 0.0,
+        x2: 0.58,
+        y2: 1.0,
+    };
+}
+
+/// the y of the cubic Bezier timing curve at `x`
+fn ease(x1: f32, y1: f32, x2: f32, y2: f32, x: f32) -> f32 {
+    let bezier = |a: f32, b: f32, s: f32| {
+        let r = 1.0 - s;
+        3.0 * r * r * s * a + 3.0 * r * s * s * b + s * s * s
+    };
+    // x grows with s for control points inside the unit square
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..32 {
+        let s = (low + high) / 2.0;
+        if bezier(x1, x2, s) < x {
+            low = s;
+        } else {
+            high = s;
+        }
+    }
+    bezier(y1, y2, (low + high) / 2.0)
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Keyframe<T> {
+    pub time: f32,
+    pub value: T,
+    /// the interpolation up to the next keyframe
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub interpolation: Interpolation,
+}
+
+/// The keyframes of one property, sorted by time. Before the first and after the last
+/// keyframe the property keeps their values.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Track<T> {
+    keyframes: Vec<Keyframe<T>>,
+}
+
+impl<T> Default for Track<T> {
+    fn default() -> Self {
+        Self {
+            keyframes: Vec::new(),
+        }
+    }
+}
+
+impl<T: Animatable> Track<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// adds a keyframe, keeping the keyframes sorted
+    pub fn with_key(mut self, time: f32, value: T, interpolation: Interpolation) -> Self {
+        let index = self.keyframes.partition_point(|key| key.time <= time);
+        self.keyframes.insert(
+            index,
+            Keyframe {
+                time,
+                value,
+                interpolation,
+            },
+        );
+        self
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe<T>] {
+        &self.keyframes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keyframes.is_empty()
+    }
+
+    /// the time of the last keyframe
+    pub fn end(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |key| key.time)
+    }
+
+    /// the value at `time`, `None` without keyframes
+    pub fn sample(&self, time: f32) -> Option<T> {
+        let keys = &self.keyframes;
+        let next = keys.partition_point(|key| key.time <= time);
+        if next == 0 || next == keys.len() {
+            return keys.get(next.saturating_sub(1)).map(|key| key.value);
+        }
+        let (from, to) = (&keys[next - 1], &keys[next]);
+        let t = (time - from.time) / (to.time - from.time);
+        Some(match from.interpolation {
+            Interpolation::Linear => from.value.lerp(&to.value, t),
+            Interpolation::CatmullRom => {
+                let before = next.checked_sub(2).map_or(from, |i| &keys[i]);
+                let after = keys.get(next + 1).unwrap_or(to);
+                T::catmull_rom([&before.value, &from.value, &to.value, &after.value], t)
+            }
+            Interpolation::Bezier { x1, y1, x2, y2 } => {
+                from.value.lerp(&to.value, ease(x1, y1, x2, y2, t))
+            }
+        })
+    }
+}
+
+/// The animated properties of the camera, empty tracks leave the camera unchanged.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct CameraAnimation {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Track::is_empty"))]
+    pub eye: Track<Point<f32>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Track::is_empty"))]
+    pub target: Track<Point<f32>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Track::is_empty"))]
+    pub fov: Track<f32>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Track::is_empty"))]
+    pub aperture: Track<f32>,
+}
+
+impl CameraAnimation {
+    /// one turn of the eye around the axis through the target in `duration`
+    pub fn turntable(camera: &CameraSettings, axis: Vector<f32>, duration: f32) -> Self {
+        let steps = 24;
+        let mut eye = Track::new();
+        for step in 0..=steps {
+            let fraction = step as f32 / steps as f32;
+            let rotation =
+                UnitQuaternion::from_axis_angle(&Unit::new_normalize(axis), TAU * fraction);
+            let position = camera.target + rotation * (camera.eye - camera.target);
+            eye = eye.with_key(duration * fraction, position, Interpolation::CatmullRom);
+        }
+        Self {
+            eye,
+            ..Default::default()
+        }
+    }
+
+    pub fn apply(&self, camera: &mut CameraSettings, time: f32) {
+        if let Some(eye) = self.eye.sample(time) {
+            camera.eye = eye;
+        }
+        if let Some(target) = self.target.sample(time) {
+            camera.target = target;
+        }
+        if let Some(fov) = self.fov.sample(time) {
+            camera.fov = fov;
+        }
+        if let Some(aperture) = self.aperture.sample(time) {
+            camera.aperture = aperture;
+        }
+    }
+
+    fn end(&self) -> f32 {
+        [
+            self.eye.end(),
+            self.target.end(),
+            self.fov.end(),
+            self.aperture.end(),
+        ]
+        .into_iter()
+        .fold(0.0, f32::max)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.eye.is_empty()
+            && self.target.is_empty()
+            && self.fov.is_empty()
+            && self.aperture.is_empty()
+    }
+}
+
+/// The animated properties of an object, empty tracks leave the object unchanged.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct ObjectAnimation {
+    /// the index of the object in the scene
+    pub object: usize,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Track::is_empty"))]
+    pub translation: Track<Vector<f32>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Track::is_empty"))]
+    pub rotation: Track<UnitQuaternion<f32>>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Track::is_empty"))]
+    pub color: Track<Color>,
+    /// the light the surface emits, see `Object::emission`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Track::is_empty"))]
+    pub emission: Track<Color>,
+}
+
+impl ObjectAnimation {
+    pub fn new(object: usize) -> Self {
+        Self {
+            object,
+            ..Default::default()
+        }
+    }
+
+    /// adds a keyframe to the translation and the rotation
+    pub fn with_isometry_key(
+        mut self,
+        time: f32,
+        isometry: Isometry<f32>,
+        interpolation: Interpolation,
+    ) -> Self {
+        self.translation =
+            self.translation
+                .with_key(time, isometry.translation.vector, interpolation);
+        self.rotation = self
+            .rotation
+            .with_key(time, isometry.rotation, interpolation);
+        self
+    }
+
+    pub fn apply(&self, object: &mut Object, time: f32) {
+        object.isometry = self.isometry_at(&object.isometry, time);
+        if let Some(color) = self.color.sample(time) {
+            object.color = color;
+        }
+        if let Some(emission) = self.emission.sample(time) {
+            object.emission = emission;
+        }
+    }
+
+    /// `isometry` with the animated translation and rotation at `time`
+    pub fn isometry_at(&self, isometry: &Isometry<f32>, time: f32) -> Isometry<f32> {
+        let mut isometry = *isometry;
+        if let Some(translation) = self.translation.sample(time) {
+            isometry.translation = Translation::from(translation);
+        }
+        if let Some(rotation) = self.rotation.sample(time) {
+            isometry.rotation = rotation;
+        }
+        isometry
+    }
+
+    fn end(&self) -> f32 {
+        [
+            self.translation.end(),
+            self.rotation.end(),
+            self.color.end(),
+            self.emission.end(),
+        ]
+        .into_iter()
+        .fold(0.0, f32::max)
+    }
+}
+
+/// The animation of a scene, evaluated by `Scene::at_time`.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Animation {
+    pub camera: CameraAnimation,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub objects: Vec<ObjectAnimation>,
+}
+
+impl Animation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_camera(mut self, camera: CameraAnimation) -> Self {
+        self.camera = camera;
+        self
+    }
+
+    pub fn with_object(mut self, object: ObjectAnimation) -> Self {
+        self.objects.push(object);
+        self
+    }
+
+    /// the time of the last keyframe
+    pub fn duration(&self) -> f32 {
+        self.objects
+            .iter()
+            .map(ObjectAnimation::end)
+            .fold(self.camera.end(), f32::max)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.camera.is_empty() && self.objects.is_empty()
+    }
+}
@@ -0,0 +1,351 @@
+//! Renders, inspects and converts scene files.
+//!
+//! Needs the `cli` feature, `cargo run --release --features cli -- render scene.ron`.
+
+use std::{collections::BTreeMap, path::PathBuf, process::ExitCode, str::FromStr, time::Duration};
+
+use clap::{Parser, Subcommand};
+use indicatif::{ProgressBar, ProgressStyle};
+use ray::{
+    camera::Camera,
+    environment::Background,
+    light::Light,
+    objects::{RenderSettings, Scene, Shape},
+    progressive::{Adaptive, Progressive},
+};
+
+#[derive(Parser)]
+#[command(version, about = "A path tracer for scene files")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// render a scene to an image
+    Render {
+        scene: PathBuf,
+        #[arg(short, long, default_value = "out.png")]
+        output: PathBuf,
+        /// samples per pixel, overrides the scene
+        #[arg(long)]
+        spp: Option<usize>,
+        /// maximum number of reflections, overrides the scene
+        #[arg(long)]
+        max_depth: Option<usize>,
+        /// number of threads, defaults to one per core
+        #[arg(long)]
+        threads: Option<usize>,
+        /// image size as WIDTHxHEIGHT, overrides the scene
+        #[arg(long)]
+        resolution: Option<Resolution>,
+        /// seed for reproducible renders
+        #[arg(long)]
+        seed: Option<u64>,
+        /// render progressively in passes of this many samples per pixel,
+        /// saving the image after every pass
+        #[arg(long)]
+        pass: Option<usize>,
+        /// stop a progressive render after the pass that exceeds this many seconds
+        #[arg(long, requires = "pass")]
+        time_limit: Option<f64>,
+        /// stop a progressive render once the mean relative error of the pixels is below this
+        #[arg(long, requires = "pass")]
+        noise: Option<f32>,
+        /// only sample the pixels of a progressive render whose relative error is above this
+        #[arg(long, requires = "pass")]
+        adaptive: Option<f32>,
+        /// passes of an adaptive render in which every pixel is sampled
+        #[arg(long, requires = "adaptive")]
+        warm_up: Option<usize>,
+        /// save a heat map of the samples per pixel of a progressive render
+        #[arg(long, requires = "pass")]
+        heatmap: Option<PathBuf>,
+        /// hide the progress bar
+        #[arg(short, long)]
+        quiet: bool,
+    },
+    /// print a summary of a scene
+    Info { scene: PathBuf },
+    /// convert a scene between RON, JSON and TOML
+    Convert { input: PathBuf, output: PathBuf },
+}
+
+#[derive(Clone, Copy)]
+struct Resolution(u32, u32);
+
+impl FromStr for Resolution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = || {
+            let (width, height) = s.split_once(['x', 'X'])?;
+            let width = width.trim().parse().ok().filter(|&w| w > 0)?;
+            let height = height.trim().parse().ok().filter(|&h| h > 0)?;
+            Some(Resolution(width, height))
+        };
+        parse().ok_or_else(|| format!("expected WIDTHxHEIGHT, got `{}`", s))
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse().command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(command: Command) -> Result<(), String> {
+    match command {
+        Command::Render {
+            scene,
+            output,
+            spp,
+            max_depth,
+            threads,
+            resolution,
+            seed,
+            pass,
+            time_limit,
+            noise,
+            adaptive,
+            warm_up,
+            heatmap,
+            quiet,
+        } => {
+            if let Some(threads) = threads {
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build_global()
+                    .map_err(|err| err.to_string())?;
+            }
+            let scene = Scene::load(&scene).map_err(|err| err.to_string())?;
+
+            let mut camera_settings = *scene.camera_settings();
+            if let Some(Resolution(width, height)) = resolution {
+                camera_settings.width = width;
+                camera_settings.height = height;
+            }
+            let mut settings = *scene.render_settings();
+            settings.rays = spp.unwrap_or(settings.rays);
+            settings.max_reflections = max_depth.unwrap_or(settings.max_reflections);
+            settings.seed = seed.or(settings.seed);
+
+            let mut camera = camera_settings.build();
+            if let Some(pass) = pass {
+                let mut progressive = Progressive::new(pass, settings.rays).with_preview(&output);
+                if let Some(seconds) = time_limit {
+                    progressive = progressive.with_time_budget(Duration::from_secs_f64(seconds));
+                }
+                if let Some(noise) = noise {
+                    progressive = progressive.with_noise_threshold(noise);
+                }
+                if let Some(threshold) = adaptive {
+                    let mut adaptive = Adaptive::new(threshold);
+                    if let Some(passes) = warm_up {
+                        adaptive = adaptive.with_warm_up(passes);
+                    }
+                    progressive = progressive.with_adaptive(adaptive);
+                }
+                if let Some(heatmap) = heatmap {
+                    progressive = progressive.with_heatmap(heatmap);
+                }
+                return render_progressive(&scene, &mut camera, &settings, &progressive, quiet);
+            }
+            let progress = if quiet {
+                ProgressBar::hidden()
+            } else {
+                ProgressBar::new(camera.width() as u64 * camera.height() as u64).with_style(
+                    ProgressStyle::with_template(
+                        "{elapsed_precise} [{wide_bar}] {percent}% eta {eta}",
+                    )
+                    .unwrap(),
+                )
+            };
+            scene.render_with_settings(&mut camera, &settings, progress.clone());
+            progress.finish_and_clear();
+
+            camera
+                .save_img(&output)
+                .map_err(|err| format!("{}: {}", output.display(), err))
+        }
+        Command::Info { scene: path } => {
+            let scene = Scene::load(&path).map_err(|err| err.to_string())?;
+            print_info(&path, &scene);
+            Ok(())
+        }
+        Command::Convert { input, output } => {
+            let scene = Scene::load(&input).map_err(|err| err.to_string())?;
+            scene.save(&output).map_err(|err| err.to_string())
+        }
+    }
+}
+
+fn render_progressive(
+    scene: &Scene,
+    camera: &mut Camera,
+    settings: &RenderSettings,
+    progressive: &Progressive,
+    quiet: bool,
+) -> Result<(), String> {
+    let progress = if quiet {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(progressive.target_rays as u64).with_style(
+            ProgressStyle::with_template("{elapsed_precise} [{wide_bar}] {pos}/{len} spp {msg}")
+                .unwrap(),
+        )
+    };
+    let result = scene.render_progressive(camera, settings, progressive, |pass, _| {
+        progress.set_position(pass.rays as u64);
+        let mut message = format!("{:.1} spp on average", pass.mean_rays);
+        if let Some(noise) = pass.noise {
+            message += &format!(", noise {:.4}", noise);
+        }
+        progress.set_message(message);
+    });
+    progress.finish_and_clear();
+    let output = progressive
+        .preview
+        .as_ref()
+        .expect("saved after every pass");
+    result
+        .map(|_| ())
+        .map_err(|err| format!("{}: {}", output.display(), err))
+}
+
+fn print_info(path: &std::path::Path, scene: &Scene) {
+    println!("{}", path.display());
+
+    let objects = scene.objects();
+    let mut shapes = BTreeMap::new();
+    for object in objects {
+        let name = match object.shape {
+            Shape::Ball { .. } => "ball",
+            Shape::Cuboid { .. } => "cuboid",
+            Shape::Capsule { .. } => "capsule",
+            Shape::Cylinder { .. } => "cylinder",
+            Shape::ConvexPolyhedron { .. } => "convex polyhedron",
+            Shape::Ellipsoid { .. } => "ellipsoid",
+            Shape::RoundCuboid { .. } => "round cuboid",
+            Shape::HalfSpace { .. } => "half space",
+        };
+        *shapes.entry(name).or_insert(0) += 1;
+    }
+    println!("objects: {}", objects.len());
+    for (name, count) in shapes {
+        println!("  {}: {}", name, count);
+    }
+    let count = |f: fn(&ray::objects::Object) -> bool| objects.iter().filter(|o| f(o)).count();
+    println!("  light sources: {}", count(|o| o.is_light_source));
+    println!(
+        "  emissive: {}",
+        count(|o| o.emission != ray::objects::Color::default())
+    );
+    println!("  media: {}", count(|o| o.medium.is_some()));
+    println!(
+        "  textured: {}",
+        count(|o| o.texture.is_some() || o.roughness_map.is_some() || o.emission_map.is_some())
+    );
+    println!("  normal mapped: {}", count(|o| o.normal_map.is_some()));
+    if !scene.instances().is_empty() {
+        println!(
+            "instances: {} of {} prototypes",
+            scene.instances().len(),
+            scene.prototypes().len()
+        );
+        for prototype in scene.prototypes() {
+            let instances = scene
+                .instances()
+                .iter()
+                .filter(|instance| instance.prototype == prototype.name)
+                .count();
+            println!(
+                "  {}: {} objects, {} instances",
+                prototype.name,
+                prototype.objects().len(),
+                instances
+            );
+        }
+    }
+
+    let mut lights = BTreeMap::new();
+    for light in scene.lights() {
+        let name = match light {
+            Light::Point { .. } => "point",
+            Light::Spot { .. } => "spot",
+            Light::Directional { .. } => "directional",
+            Light::Rectangle { .. } => "rectangle",
+            Light::Disk { .. } => "disk",
+            Light::Sphere { .. } => "sphere",
+        };
+        *lights.entry(name).or_insert(0) += 1;
+    }
+    println!("lights: {}", scene.lights().len());
+    for (name, count) in lights {
+        println!("  {}: {}", name, count);
+    }
+
+    match scene.background() {
+        Background::Color(color) => {
+            println!("background: color ({}, {}, {})", color.0, color.1, color.2)
+        }
+        Background::Map(map) => match map.path() {
+            Some(path) => println!("background: environment map {}", path.display()),
+            None => println!("background: environment map"),
+        },
+        Background::Sky(sky) => println!(
+            "background: sky, sun at {:.1}° elevation, turbidity {}",
+            sky.elevation().to_degrees(),
+            sky.turbidity()
+        ),
+    }
+    println!(
+        "atmosphere: {}",
+        if scene.atmosphere().is_some() {
+            "yes"
+        } else {
+            "no"
+        }
+    );
+
+    let camera = scene.camera_settings();
+    println!(
+        "camera: {}x{}, eye ({}, {}, {}), target ({}, {}, {}), fov {:.1}°",
+        camera.width,
+        camera.height,
+        camera.eye.x,
+        camera.eye.y,
+        camera.eye.z,
+        camera.target.x,
+        camera.target.y,
+        camera.target.z,
+        camera.fov.to_degrees()
+    );
+    if camera.aperture > 0.0 {
+        print!("  aperture {}", camera.aperture);
+        match camera.focus_distance {
+            Some(distance) => println!(", focus distance {}", distance),
+            None => println!(", focused on the target"),
+        }
+    }
+    if !camera.shutter.is_instant() {
+        println!(
+            "  shutter open from {} to {} of the frame interval, {:?}",
+            camera.shutter.open, camera.shutter.close, camera.shutter.curve
+        );
+    }
+    let render = scene.render_settings();
+    print!(
+        "render: {} spp, max depth {}, {} px tiles in {:?} order",
+        render.rays, render.max_reflections, render.tiles.size, render.tiles.order
+    );
+    match render.seed {
+        Some(seed) => println!(", seed {}", seed),
+        None => println!(),
+    }
+}
@@ -0,0 +1,103 @@
+//! Finding bonds from the distances between atoms.
+
+use std::collections::HashMap;
+
+use parry3d::math::{Point, Vector};
+
+use crate::{
+    elements::{AtomRadius, PeriodicTable},
+    structure::Structure,
+};
+
+/// how much longer than the sum of the covalent radii a bond may be in Å
+pub const DEFAULT_BOND_TOLERANCE: f32 = 0.45;
+/// atoms closer than this in Å are overlapping sites rather than bonded
+const MIN_BOND_LENGTH: f32 = 0.4;
+
+/// A bond between two atoms of a structure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Bond {
+    pub a: usize,
+    pub b: usize,
+    /// the lattice translation of `b`, nonzero for bonds across the cell boundary
+    pub image: [i32; 3],
+}
+
+impl Bond {
+    pub fn new(a: usize, b: usize) -> Self {
+        Self {
+            a,
+            b,
+            image: [0; 3],
+        }
+    }
+}
+
+impl Structure {
+    /// the position of the periodic image of an atom
+    pub fn image_position(&self, atom: usize, image: [i32; 3]) -> Point<f32> {
+        let position = self.positions[atom];
+        match &self.lattice {
+            Some(lattice) => position + lattice.matrix() * Vector::from(image.map(|i| i as f32)),
+            None => position,
+        }
+    }
+
+    /// the lattice translations to the neighbouring cells, only the origin if the structure is
+    /// not periodic, so bonds have to be shorter than the lattice vectors
+    pub(crate) fn images(&self) -> Vec<[i32; 3]> {
+        match self.lattice {
+            Some(_) => (-1..=1)
+                .flat_map(|x| (-1..=1).flat_map(move |y| (-1..=1).map(move |z| [x, y, z])))
+                .collect(),
+            None => vec![[0; 3]],
+        }
+    }
+
+    /// bonds between atoms closer than the sum of their covalent radii plus `tolerance`,
+    /// periodic structures are also bonded across the cell boundary
+    pub fn detect_bonds(&self, table: &PeriodicTable, tolerance: f32) -> Vec<Bond> {
+        let radii: Vec<f32> = self
+            .species
+            .iter()
+            .map(|element| table.radius(element, AtomRadius::Covalent))
+            .collect();
+        let max_radius = radii.iter().copied().fold(0.0, f32::max);
+        let cell_size = (2.0 * max_radius + tolerance).max(MIN_BOND_LENGTH);
+
+        // sort the atoms into a grid of cells at least as large as the longest bond
+        let cell = |point: &Point<f32>| point.coords.map(|x| (x / cell_size).floor() as i32);
+        let mut grid: HashMap<_, Vec<usize>> = HashMap::new();
+        for (i, position) in self.positions.iter().enumerate() {
+            grid.entry(cell(position)).or_default().push(i);
+        }
+
+        let mut bonds = Vec::new();
+        for image in self.images() {
+            for i in 0..self.len() {
+                let position = self.image_position(i, image);
+                let center = cell(&position);
+                for offset in (-1..=1).flat_map(|x| {
+                    (-1..=1).flat_map(move |y| (-1..=1).map(move |z| Vector::new(x, y, z)))
+                }) {
+                    let Some(atoms) = grid.get(&(center + offset)) else {
+                        continue;
+                    };
+                    for &j in atoms {
+                        // every bond is found from both of its atoms, only the one starting
+                        // at the lower index is kept
+                        if j > i || (j == i && image <= [0; 3]) {
+                            continue;
+                        }
+                        let length = (self.positions[j] - position).norm();
+                        if length > MIN_BOND_LENGTH && length <= radii[i] + radii[j] + tolerance {
+                            bonds.push(Bond { a: j, b: i, image });
+                        }
+                    }
+                }
+            }
+        }
+        bonds.sort_unstable();
+        bonds
+    }
+}
@@ -0,0 +1,307 @@
+//! A bounding volume hierarchy over the objects of a scene, so rays are only cast against the
+//! objects whose bounds they pass through.
+//!
+//! The bounds of moving objects enclose them over the whole frame interval, so rays at any time
+//! of the shutter interval find them.
+
+use parry3d::{
+    bounding_volume::{Aabb, BoundingVolume},
+    math::{Isometry, Real, Vector},
+    query::{Ray, RayCast},
+};
+
+use crate::packet::RayPacket;
+
+/// the most objects in a leaf
+const LEAF_SIZE: usize = 4;
+
+/// deep enough for the balanced trees built by splitting at the median
+const STACK_SIZE: usize = 64;
+
+#[derive(Debug, Clone, Default)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    /// the bounded objects in the order of the leaves
+    indices: Vec<usize>,
+    /// objects without bounds, like half spaces, every ray is cast against them
+    unbounded: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    aabb: Aabb,
+    /// the first object of a leaf or the first of the two children of an inner node
+    start: usize,
+    /// the number of objects of a leaf, zero for inner nodes
+    count: usize,
+}
+
+impl Bvh {
+    /// builds the hierarchy over the bounds of the objects, `None` for unbounded ones
+    pub fn new(bounds: &[Option<Aabb>]) -> Self {
+        let (mut indices, unbounded): (Vec<usize>, Vec<usize>) =
+            (0..bounds.len()).partition(|&i| bounds[i].is_some());
+        let aabbs: Vec<Aabb> = bounds
+            .iter()
+            .map(|aabb| aabb.unwrap_or_else(Aabb::new_invalid))
+            .collect();
+        let mut nodes = Vec::new();
+        if !indices.is_empty() {
+            nodes.push(Node {
+                aabb: Aabb::new_invalid(),
+                start: 0,
+                count: 0,
+            });
+            build(&mut nodes, 0, &mut indices, 0, &aabbs);
+        }
+        Self {
+            nodes,
+            indices,
+            unbounded,
+        }
+    }
+
+    /// updates the bounds of the nodes to new bounds of the same objects, keeping the tree, the
+    /// objects have to stay bounded or unbounded
+    pub fn refit(&mut self, bounds: &[Option<Aabb>]) {
+        // the children of a node come after it
+        for index in (0..self.nodes.len()).rev() {
+            let node = self.nodes[index];
+            self.nodes[index].aabb = if node.count > 0 {
+                self.indices[node.start..node.start + node.count]
+                    .iter()
+                    .fold(Aabb::new_invalid(), |aabb, &i| {
+                        aabb.merged(&bounds[i].expect("bounded objects stay bounded"))
+                    })
+            } else {
+                self.nodes[node.start]
+                    .aabb
+                    .merged(&self.nodes[node.start + 1].aabb)
+            };
+        }
+    }
+
+    /// the bounds of all objects, `None` if one of them is unbounded
+    pub fn aabb(&self) -> Option<Aabb> {
+        if !self.unbounded.is_empty() {
+            return None;
+        }
+        Some(
+            self.nodes
+                .first()
+                .map_or_else(Aabb::new_invalid, |root| root.aabb),
+        )
+    }
+
+    /// the closest hit of the ray, `intersect` casts it against the object with an index and
+    /// returns the distance of the hit with anything the caller needs to know about it
+    pub fn closest<T>(
+        &self,
+        ray: &Ray,
+        mut intersect: impl FnMut(usize) -> Option<(Real, T)>,
+    ) -> Option<(Real, T)> {
+        let mut closest: Option<(Real, T)> = None;
+        let mut test = |i: usize, closest: &mut Option<(Real, T)>| {
+            if let Some(hit) = intersect(i) {
+                if closest.as_ref().is_none_or(|best| hit.0 < best.0) {
+                    *closest = Some(hit);
+                }
+            }
+        };
+        for &i in &self.unbounded {
+            test(i, &mut closest);
+        }
+        let max_toi =
+            |closest: &Option<(Real, T)>| closest.as_ref().map_or(Real::MAX, |(toi, _)| *toi);
+        let Some(root) = self.nodes.first() else {
+            return closest;
+        };
+        let Some(entry) = root.aabb.cast_local_ray(ray, max_toi(&closest), true) else {
+            return closest;
+        };
+        // the nodes still to visit with the distance at which the ray enters them
+        let mut stack = [(0, 0.0); STACK_SIZE];
+        stack[0] = (0, entry);
+        let mut len = 1;
+        while len > 0 {
+            len -= 1;
+            let (index, entry) = stack[len];
+            if entry > max_toi(&closest) {
+                continue;
+            }
+            let node = &self.nodes[index];
+            if node.count > 0 {
+                for &i in &self.indices[node.start..node.start + node.count] {
+                    test(i, &mut closest);
+                }
+                continue;
+            }
+            let max = max_toi(&closest);
+            let hits = [node.start, node.start + 1]
+                .map(|child| (child, self.nodes[child].aabb.cast_local_ray(ray, max, true)));
+            // the nearer child is pushed last to be visited first
+            let [near, far] = match (hits[0].1, hits[1].1) {
+                (Some(a), Some(b)) if b < a => [hits[1], hits[0]],
+                _ => hits,
+            };
+            for (child, entry) in [far, near] {
+                if let Some(entry) = entry {
+                    stack[len] = (child, entry);
+                    len += 1;
+                }
+            }
+        }
+        closest
+    }
+
+    /// visits the objects whose bounds any ray of the packet passes through before its closest
+    /// hit so far, `intersect` casts the packet against the object with an index and shortens
+    /// `packet.toi` for the lanes that hit it
+    pub fn traverse_packet<const N: usize>(
+        &self,
+        packet: &mut RayPacket<N>,
+        mut intersect: impl FnMut(&mut RayPacket<N>, usize),
+    ) {
+        for &i in &self.unbounded {
+            intersect(packet, i);
+        }
+        if self.nodes.is_empty() {
+            return;
+        }
+        let direction = packet.direction();
+        let mut stack = [0; STACK_SIZE];
+        let mut len = 1;
+        while len > 0 {
+            len -= 1;
+            let node = &self.nodes[stack[len]];
+            if !packet.intersects_aabb(&node.aabb) {
+                continue;
+            }
+            if node.count > 0 {
+                for &i in &self.indices[node.start..node.start + node.count] {
+                    intersect(packet, i);
+                }
+                continue;
+            }
+            // the child nearer along the direction of the packet is pushed last to be visited first
+            let (a, b) = (node.start, node.start + 1);
+            let offset = self.nodes[b].aabb.center() - self.nodes[a].aabb.center();
+            let (near, far) = if direction.dot(&offset) < 0.0 {
+                (b, a)
+            } else {
+                (a, b)
+            };
+            stack[len] = far;
+            stack[len + 1] = near;
+            len += 2;
+        }
+    }
+}
+
+/// the bounds of a shape with the bounds `local` in its local space moving from `start` to
+/// `end` over the frame interval
+pub fn swept_aabb(local: &Aabb, start: &Isometry<f32>, end: Option<&Isometry<f32>>) -> Aabb {
+    let mut aabb = local.transform_by(start);
+    let Some(end) = end else {
+        return aabb;
+    };
+    aabb.merge(&local.transform_by(end));
+    if start.rotation.angle_to(&end.rotation) > 0.0 {
+        // the translation moves along a line while the shape turns around it,
+        // staying within the distance of the farthest point of its bounds
+        let sphere = local.bounding_sphere();
+        let radius = sphere.center.coords.norm() + sphere.radius;
+        for translation in [start.translation, end.translation] {
+            aabb.merge(&Aabb::from_half_extents(
+                translation.vector.into(),
+                Vector::repeat(radius),
+            ));
+        }
+    }
+    aabb
+}
+
+/// fills in `node` for the objects in `indices`, which start at `offset` in the leaf order,
+/// splitting them at the median along the axis where their centers spread the most
+fn build(nodes: &mut Vec<Node>, node: usize, indices: &mut [usize], offset: usize, aabbs: &[Aabb]) {
+    let aabb = indices
+        .iter()
+        .fold(Aabb::new_invalid(), |aabb, &i| aabb.merged(&aabbs[i]));
+    if indices.len() <= LEAF_SIZE {
+        nodes[node] = Node {
+            aabb,
+            start: offset,
+            count: indices.len(),
+        };
+        return;
+    }
+    let mut centers = Aabb::new_invalid();
+    for &i in indices.iter() {
+        centers.take_point(aabbs[i].center());
+    }
+    let axis = centers.extents().imax();
+    let middle = indices.len() / 2;
+    indices.select_nth_unstable_by(middle, |&a, &b| {
+        aabbs[a].center()[axis].total_cmp(&aabbs[b].center()[axis])
+    });
+    let children = nodes.len();
+    let placeholder = Node {
+        aabb,
+        start: 0,
+        count: 0,
+    };
+    nodes.extend([placeholder; 2]);
+    nodes[node] = Node {
+        aabb,
+        start: children,
+        count: 0,
+    };
+    let (left, right) = indices.split_at_mut(middle);
+    build(nodes, children, left, offset, aabbs);
+    build(nodes, children + 1, right, offset + middle, aabbs);
+}
+
+#[cfg(test)]
+mod tests {
+    use parry3d::math::Point;
+
+    use super::*;
+    use crate::utils;
+
+    fn boxes(offset: Vector<f32>) -> Vec<Option<Aabb>> {
+        (0..100)
+            .map(|i| {
+                let center = Point::new((i % 10) as f32, (i / 10) as f32, (i % 7) as f32);
+                Some(Aabb::from_half_extents(
+                    center + offset * i as f32,
+                    Vector::repeat(0.3),
+                ))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn refitted_hierarchies_find_the_closest_object() {
+        let _seeded = utils::seed_rng(5);
+        let mut bvh = Bvh::new(&boxes(Vector::zeros()));
+        let moved = boxes(Vector::new(0.05, -0.1, 0.02));
+        bvh.refit(&moved);
+        let cast = |i: usize, ray: &Ray| {
+            let toi = moved[i].unwrap().cast_local_ray(ray, Real::MAX, true)?;
+            Some((toi, i))
+        };
+        for _ in 0..500 {
+            let origin = Point::new(-5.0, 5.0, 3.0 + utils::random::<f32>() * 3.0);
+            let target = Point::new(
+                utils::random::<f32>() * 10.0,
+                utils::random::<f32>() * 5.0,
+                utils::random::<f32>() * 7.0,
+            );
+            let ray = Ray::new(origin, (target - origin).normalize());
+            let expected = (0..moved.len())
+                .filter_map(|i| cast(i, &ray))
+                .min_by(|a, b| a.0.total_cmp(&b.0));
+            assert_eq!(bvh.closest(&ray, |i| cast(i, &ray)), expected);
+        }
+    }
+}
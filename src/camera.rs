@@ -1,16 +1,127 @@
 use std::{f32::consts::TAU, path::Path};
 
-use image::{ImageResult, Rgb, RgbImage, imageops::blur};
-use itertools::Itertools;
+use image::{
+    ImageResult, Rgb, Rgb32FImage, RgbImage,
+    imageops::{blur, replace},
+};
 use parry3d::{
     math::{Isometry, Point, Vector},
     query::Ray,
 };
 
+use crate::{objects::Color, tiles::Tile, utils};
+
 pub struct Camera {
     fov: f32,
     isometry: Isometry<f32>,
     image: RgbImage,
+    /// the linear colors of the pixels before they are clamped to 8 bits
+    radiance: Rgb32FImage,
+    aperture: f32,
+    focus_distance: f32,
+    shutter: Shutter,
+}
+
+/// The thin lens of a camera with depth of field.
+#[derive(Debug, Clone, Copy)]
+pub struct Lens {
+    forward: Vector<f32>,
+    /// spanning the lens, with the length of its radius
+    axes: [Vector<f32>; 2],
+    focus_distance: f32,
+}
+
+impl Lens {
+    /// a ray from a random point on the lens meeting `ray`, which starts at the center of the
+    /// lens, on the plane in focus
+    pub fn sample(&self, ray: &Ray) -> Ray {
+        let focus = ray.point_at(self.focus_distance / ray.dir.dot(&self.forward));
+        let radius = utils::random::<f32>().sqrt();
+        let angle = TAU * utils::random::<f32>();
+        let origin =
+            ray.origin + (self.axes[0] * angle.cos() + self.axes[1] * angle.sin()) * radius;
+        Ray::new(origin, (focus - origin).normalize())
+    }
+}
+
+/// When the shutter is open, as fractions of the interval from one frame to the next, over which
+/// moving objects go from their `isometry` to their `end_isometry`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Shutter {
+    pub open: f32,
+    pub close: f32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub curve: ShutterCurve,
+}
+
+/// How much light passes the shutter while it opens and closes.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
+pub enum ShutterCurve {
+    /// fully open at once
+    #[default]
+    Box,
+    /// opening until the middle of the interval and closing after it
+    Triangle,
+    /// opening and closing during the given fraction of the interval at each end
+    Trapezoid { ramp: f32 },
+}
+
+impl Shutter {
+    pub fn new(open: f32, close: f32) -> Self {
+        Self {
+            open,
+            close,
+            curve: ShutterCurve::Box,
+        }
+    }
+
+    pub fn with_curve(mut self, curve: ShutterCurve) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    /// a shutter that opens and closes at once, without motion blur
+    pub fn is_instant(&self) -> bool {
+        self.close <= self.open
+    }
+
+    /// a random time at which light passes the shutter
+    pub fn sample(&self) -> f32 {
+        if self.is_instant() {
+            return self.open;
+        }
+        self.open + (self.close - self.open) * self.curve.sample()
+    }
+}
+
+impl ShutterCurve {
+    /// the fraction of light passing at time `t` between opening and closing
+    pub fn weight(&self, t: f32) -> f32 {
+        match *self {
+            ShutterCurve::Box => 1.0,
+            ShutterCurve::Triangle => 1.0 - (2.0 * t - 1.0).abs(),
+            ShutterCurve::Trapezoid { ramp } if ramp > 0.0 => (t.min(1.0 - t) / ramp).min(1.0),
+            ShutterCurve::Trapezoid { .. } => 1.0,
+        }
+    }
+
+    /// a random time between opening and closing, distributed like the weight
+    fn sample(&self) -> f32 {
+        match self {
+            ShutterCurve::Box => utils::random(),
+            // the sum of two uniform numbers is distributed like a triangle
+            ShutterCurve::Triangle => (utils::random::<f32>() + utils::random::<f32>()) / 2.0,
+            ShutterCurve::Trapezoid { .. } => loop {
+                let t = utils::random();
+                if utils::random::<f32>() < self.weight(t) {
+                    return t;
+                }
+            },
+        }
+    }
 }
 
 impl Camera {
@@ -26,36 +137,96 @@ impl Camera {
             fov,
             isometry,
             image: RgbImage::new(width, height),
+            radiance: Rgb32FImage::new(width, height),
+            aperture: 0.0,
+            focus_distance: (target - eye).norm(),
+            shutter: Shutter::default(),
         }
     }
 
-    pub fn get_rays(&mut self) -> Vec<(Ray, &mut Rgb<u8>)> {
+    /// gives the camera a lens with the diameter `aperture`, which keeps objects at
+    /// `focus_distance` sharp
+    pub fn with_lens(mut self, aperture: f32, focus_distance: f32) -> Self {
+        self.aperture = aperture;
+        self.focus_distance = focus_distance;
+        self
+    }
+
+    /// the lens, `None` for a pinhole camera
+    pub fn lens(&self) -> Option<Lens> {
+        (self.aperture > 0.0).then(|| Lens {
+            forward: self.isometry * Vector::z(),
+            axes: [Vector::x(), Vector::y()].map(|axis| self.isometry * axis * self.aperture / 2.0),
+            focus_distance: self.focus_distance,
+        })
+    }
+
+    pub fn with_shutter(mut self, shutter: Shutter) -> Self {
+        self.shutter = shutter;
+        self
+    }
+
+    pub fn shutter(&self) -> &Shutter {
+        &self.shutter
+    }
+
+    /// the rays through the center of the lens
+    pub fn rays(&self) -> PixelRays {
         let width = self.image.width();
         let height = self.image.height();
+        let pixel_length = 2.0 / width as f32 * (self.fov / 2.0).tan();
+        PixelRays {
+            eye: self.isometry * Point::new(0.0, 0.0, 0.0),
+            direction: self.isometry * Vector::new(0.0, 0.0, 1.0),
+            img_x: self.isometry * Vector::new(-pixel_length, 0.0, 0.0),
+            img_y: self.isometry * Vector::new(0.0, pixel_length, 0.0),
+            centre: (width as f32 / 2.0, height as f32 / 2.0),
+        }
+    }
 
-        let eye = self.isometry * Point::new(0.0, 0.0, 0.0);
-        let direction = self.isometry * Vector::new(0.0, 0.0, 1.0);
+    /// writes the rendered colors of a tile, given row by row, into the image
+    pub fn put_tile(&mut self, tile: &Tile, colors: &[Color]) {
+        for ((x, y), color) in tile.pixels().zip(colors) {
+            self.image.put_pixel(x, y, (*color).into());
+            self.radiance
+                .put_pixel(x, y, Rgb([color.0, color.1, color.2]));
+        }
+    }
 
-        let pixel_length = 2.0 / width as f32 * (self.fov / 2.0).tan();
-        let img_x = self.isometry * Vector::new(-pixel_length, 0.0, 0.0);
-        let img_y = self.isometry * Vector::new(0.0, pixel_length, 0.0);
+    pub fn image(&self) -> &RgbImage {
+        &self.image
+    }
 
-        let xs = (0..width).map(|val| val as f32 - width as f32 / 2.0);
-        let ys = (0..height).map(|val| height as f32 / 2.0 - val as f32);
-        ys.cartesian_product(xs)
-            .map(|(y, x)| Ray::new(eye, (direction + img_x * x + img_y * y).normalize()))
-            .zip(self.image.pixels_mut())
-            .collect()
+    /// the linear colors of the pixels, which `image` clamps to 8 bits
+    pub fn radiance(&self) -> &Rgb32FImage {
+        &self.radiance
     }
 
     pub fn apply_blur(&mut self, sigma: f32) {
-        self.image = blur(&self.image, sigma)
+        self.image = blur(&self.image, sigma);
+        self.radiance = blur(&self.radiance, sigma);
+    }
+
+    /// replaces the pixels under the image of `inset` with its top left corner at `x`, `y`
+    pub fn paste(&mut self, inset: &Camera, x: u32, y: u32) {
+        replace(&mut self.image, &inset.image, x as i64, y as i64);
+        replace(&mut self.radiance, &inset.radiance, x as i64, y as i64);
     }
 }
 
 impl Camera {
+    /// saves the image, the format is chosen by the extension, OpenEXR files get the linear
+    /// radiance
     pub fn save_img(&self, path: impl AsRef<Path>) -> ImageResult<()> {
-        self.image.save(path)
+        let path = path.as_ref();
+        let exr = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("exr"));
+        if exr {
+            self.radiance.save(path)
+        } else {
+            self.image.save(path)
+        }
     }
 
     pub fn width(&self) -> u32 {
@@ -67,14 +238,85 @@ impl Camera {
     }
 }
 
+/// The ray through the center of the lens and a pixel for every pixel of the image.
+#[derive(Debug, Clone, Copy)]
+pub struct PixelRays {
+    eye: Point<f32>,
+    direction: Vector<f32>,
+    /// the step from a pixel to the next one in the row and the one in the row above
+    img_x: Vector<f32>,
+    img_y: Vector<f32>,
+    centre: (f32, f32),
+}
+
+impl PixelRays {
+    pub fn ray(&self, x: u32, y: u32) -> Ray {
+        let x = x as f32 - self.centre.0;
+        let y = self.centre.1 - y as f32;
+        Ray::new(
+            self.eye,
+            (self.direction + self.img_x * x + self.img_y * y).normalize(),
+        )
+    }
+}
+
 impl Default for Camera {
     fn default() -> Self {
-        Self::face_towards(
-            Point::new(10.0, 5.0, 8.0),
-            Point::new(0.0, 0.0, 0.0),
-            TAU / 6.0,
-            100,
-            100,
-        )
+        CameraSettings::default().build()
+    }
+}
+
+/// The parameters of a camera without its image, as stored in scene files.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CameraSettings {
+    pub eye: Point<f32>,
+    pub target: Point<f32>,
+    pub fov: f32,
+    pub width: u32,
+    pub height: u32,
+    /// the diameter of the lens, zero for a pinhole camera where everything is sharp
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "crate::scene_file::is_zero")
+    )]
+    pub aperture: f32,
+    /// the distance of the plane in focus, the distance to the target by default
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub focus_distance: Option<f32>,
+    /// open for a part of the frame interval to blur moving objects
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "crate::scene_file::is_default")
+    )]
+    pub shutter: Shutter,
+}
+
+impl CameraSettings {
+    pub fn build(&self) -> Camera {
+        let focus_distance = self
+            .focus_distance
+            .unwrap_or_else(|| (self.target - self.eye).norm());
+        Camera::face_towards(self.eye, self.target, self.fov, self.width, self.height)
+            .with_lens(self.aperture, focus_distance)
+            .with_shutter(self.shutter)
+    }
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            eye: Point::new(10.0, 5.0, 8.0),
+            target: Point::new(0.0, 0.0, 0.0),
+            fov: TAU / 6.0,
+            width: 100,
+            height: 100,
+            aperture: 0.0,
+            focus_distance: None,
+            shutter: Shutter::default(),
+        }
     }
 }
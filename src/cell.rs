@@ -0,0 +1,274 @@
+//! Drawing the lattice of a crystal: the outline of the cell, lattice planes and an axis triad
+//! in a corner of the image.
+
+use std::f32::consts::TAU;
+
+use parry3d::{
+    math::{Isometry, Point, Vector},
+    shape::{Ball, ConvexPolyhedron},
+};
+
+use crate::{
+    camera::{Camera, CameraSettings},
+    crystal::Lattice,
+    objects::{Brdf, Color, Object, Scene, Shape},
+    structure,
+};
+
+/// How the slabs of lattice planes are drawn.
+#[derive(Debug, Clone, Copy)]
+pub struct PlaneStyle {
+    /// in Å
+    pub thickness: f32,
+    pub color: Color,
+    pub opacity: f32,
+}
+
+impl Default for PlaneStyle {
+    fn default() -> Self {
+        Self {
+            thickness: 0.05,
+            color: Color(0.9, 0.7, 0.2),
+            opacity: 0.4,
+        }
+    }
+}
+
+impl Lattice {
+    /// the corner of the cell spanned by `cells` unit cells, `corner` selects the far side of
+    /// the cell along the vectors whose bit is set
+    fn corner(&self, cells: [usize; 3], corner: usize) -> Point<f32> {
+        let fract = Vector::from_fn(|i, _| ((corner >> i) & 1) as f32 * cells[i] as f32);
+        self.to_cartesian(&fract)
+    }
+
+    /// cylinders along the twelve edges of the cell spanned by `cells` unit cells, with balls
+    /// rounding the corners
+    pub fn outline(&self, cells: [usize; 3], radius: f32, color: Color) -> Vec<Object> {
+        let mut objects = Vec::new();
+        for corner in 0..8 {
+            let from = self.corner(cells, corner);
+            // every edge starts at the corner without the bit of its direction
+            for axis in (0..3).filter(|axis| corner & (1 << axis) == 0) {
+                let to = self.corner(cells, corner | (1 << axis));
+                objects.extend(structure::cylinder(from, to, radius, color));
+            }
+            objects.push(ball(from, radius, color));
+        }
+        objects
+    }
+
+    /// translucent slabs on the lattice planes with the Miller indices `hkl` that pass through
+    /// lattice points, clipped to the cell spanned by `cells` unit cells
+    pub fn miller_planes(
+        &self,
+        hkl: [i32; 3],
+        cells: [usize; 3],
+        style: &PlaneStyle,
+    ) -> Vec<Object> {
+        let hkl = Vector::from(hkl.map(|i| i as f32));
+        // the planes are where the fractional coordinates dotted with `hkl` are integers
+        let index = |point: &Point<f32>| self.to_fractional(point).dot(&hkl);
+        let normal = self
+            .matrix()
+            .try_inverse()
+            .expect("lattice vectors are linearly dependent")
+            .transpose()
+            * hkl;
+        if normal.norm() == 0.0 {
+            return Vec::new();
+        }
+        let normal = normal.normalize();
+
+        let corners: Vec<Point<f32>> = (0..8).map(|corner| self.corner(cells, corner)).collect();
+        let edges: Vec<(Point<f32>, Point<f32>)> = (0..8)
+            .flat_map(|corner| {
+                (0..3)
+                    .filter(move |axis| corner & (1 << axis) == 0)
+                    .map(move |axis| (corner, corner | (1 << axis)))
+            })
+            .map(|(from, to)| (corners[from], corners[to]))
+            .collect();
+        let indices: Vec<f32> = corners.iter().map(index).collect();
+        let lowest = indices.iter().copied().fold(f32::MAX, f32::min).ceil() as i32;
+        let highest = indices.iter().copied().fold(f32::MIN, f32::max).floor() as i32;
+
+        let mut objects = Vec::new();
+        for plane in lowest..=highest {
+            let plane = plane as f32;
+            // the polygon where the plane cuts the cell, corners are shared by several edges
+            let side = |point: &Point<f32>| match index(point) - plane {
+                side if side.abs() < 1e-4 => 0.0,
+                side => side,
+            };
+            let mut points: Vec<Point<f32>> = Vec::new();
+            for (from, to) in &edges {
+                let (a, b) = (side(from), side(to));
+                let crossings = [
+                    (a == 0.0).then_some(*from),
+                    (b == 0.0).then_some(*to),
+                    (a * b < 0.0).then(|| from + (to - from) * (a / (a - b))),
+                ];
+                for point in crossings.into_iter().flatten() {
+                    if points.iter().all(|other| (other - point).norm() > 1e-4) {
+                        points.push(point);
+                    }
+                }
+            }
+            // planes touching only an edge or a corner of the cell
+            let Some(&first) = points.first() else {
+                continue;
+            };
+            let has_area = points.iter().any(|a| {
+                points
+                    .iter()
+                    .any(|b| (a - first).cross(&(b - first)).norm() > 1e-6)
+            });
+            if !has_area {
+                continue;
+            }
+            let center = Point::from(
+                points.iter().map(|point| point.coords).sum::<Vector<f32>>() / points.len() as f32,
+            );
+            let offset = normal * style.thickness / 2.0;
+            let slab: Vec<Point<f32>> = points
+                .iter()
+                .flat_map(|point| [point - center + offset, point - center - offset])
+                .map(Point::from)
+                .collect();
+            let Some(slab) = ConvexPolyhedron::from_convex_hull(&slab) else {
+                continue;
+            };
+            objects.push(
+                Object::new(
+                    Shape::ConvexPolyhedron(slab),
+                    Isometry::translation(center.x, center.y, center.z),
+                    style.color,
+                )
+                .with_opacity(style.opacity),
+            );
+        }
+        objects
+    }
+}
+
+/// A corner of the image.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    #[default]
+    BottomLeft,
+    BottomRight,
+}
+
+/// Arrows along the directions of the lattice vectors in a corner of the image, rendered with
+/// their own camera looking in the same direction as the camera of the scene.
+#[derive(Debug, Clone, Copy)]
+pub struct AxisTriad {
+    /// the width and height of the inset in pixels
+    pub size: u32,
+    pub corner: Corner,
+    /// the distance to the border of the image in pixels
+    pub margin: u32,
+    /// the colors of the arrows along a, b and c, red, green and blue by default
+    pub colors: [Color; 3],
+    pub rays: usize,
+}
+
+impl Default for AxisTriad {
+    fn default() -> Self {
+        Self {
+            size: 120,
+            corner: Corner::default(),
+            margin: 10,
+            colors: [
+                Color(0.95, 0.05, 0.05),
+                Color(0.05, 0.85, 0.05),
+                Color(0.05, 0.1, 0.95),
+            ],
+            rays: 50,
+        }
+    }
+}
+
+impl AxisTriad {
+    /// the arrows in front of the background, which also lights them, and the camera of the
+    /// inset looking in the direction of `camera`
+    pub fn scene(&self, lattice: &Lattice, camera: &CameraSettings, background: Color) -> Scene {
+        let mut objects = vec![ball(Point::origin(), 0.1, Color(0.5, 0.5, 0.5))];
+        for (vector, color) in lattice.vectors.iter().zip(self.colors) {
+            let direction = vector.normalize();
+            let head = Point::from(direction * 0.7);
+            objects.extend(structure::cylinder(Point::origin(), head, 0.05, color));
+            objects.extend(cone(head, direction * 0.3, 0.12, color));
+        }
+        // lit by the background only, the default brdf leaves the colors too dark to tell apart
+        let objects = objects
+            .into_iter()
+            .map(|object| object.with_brdf(Brdf::Diffuse))
+            .collect();
+
+        // far away with a narrow field of view, so the arrows are hardly distorted
+        let distance = 20.0;
+        let fov = 2.0 * (1.3_f32 / distance).asin();
+        Scene::new(background)
+            .add_objects(objects)
+            .with_camera(CameraSettings {
+                eye: Point::from((camera.eye - camera.target).normalize() * distance),
+                target: Point::origin(),
+                fov,
+                width: self.size,
+                height: self.size,
+                ..Default::default()
+            })
+    }
+
+    /// renders the triad and pastes it into the corner of the image of `camera`
+    pub fn render(
+        &self,
+        lattice: &Lattice,
+        settings: &CameraSettings,
+        background: Color,
+        camera: &mut Camera,
+    ) {
+        let scene = self.scene(lattice, settings, background);
+        let mut inset = scene.camera_settings().build();
+        scene.render_par(&mut inset, self.rays, 2);
+        let far = |length: u32| length.saturating_sub(self.size + self.margin);
+        let (x, y) = match self.corner {
+            Corner::TopLeft => (self.margin, self.margin),
+            Corner::TopRight => (far(camera.width()), self.margin),
+            Corner::BottomLeft => (self.margin, far(camera.height())),
+            Corner::BottomRight => (far(camera.width()), far(camera.height())),
+        };
+        camera.paste(&inset, x, y);
+    }
+}
+
+fn ball(center: Point<f32>, radius: f32, color: Color) -> Object {
+    Object::new(
+        Shape::Ball(Ball::new(radius)),
+        Isometry::translation(center.x, center.y, center.z),
+        color,
+    )
+}
+
+/// a cone from the center of its base along `axis`, approximated by a pyramid
+fn cone(base: Point<f32>, axis: Vector<f32>, radius: f32, color: Color) -> Option<Object> {
+    let tangent = axis.cross(&Vector::x()).try_normalize(1e-6);
+    let tangent = tangent.unwrap_or_else(|| axis.cross(&Vector::y()).normalize());
+    let bitangent = axis.normalize().cross(&tangent);
+    let mut points: Vec<Point<f32>> = (0..24)
+        .map(|i| {
+            let angle = i as f32 / 24.0 * TAU;
+            Point::from((tangent * angle.cos() + bitangent * angle.sin()) * radius)
+        })
+        .collect();
+    points.push(Point::from(axis));
+    Some(Object::new(
+        Shape::ConvexPolyhedron(ConvexPolyhedron::from_convex_hull(&points)?),
+        Isometry::translation(base.x, base.y, base.z),
+        color,
+    ))
+}
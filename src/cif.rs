@@ -0,0 +1,545 @@
+//! Reading crystal structures from Crystallographic Information Files.
+//!
+//! Tags are matched case-insensitively and the DDLm form `_atom_site.fract_x` is treated like
+//! `_atom_site_fract_x`. Only the first data block containing a unit cell is read.
+//!
+//! Displacement parameters are read from `_atom_site_U_iso_or_equiv`, `_atom_site_B_iso_or_equiv`
+//! and the `_atom_site_aniso_` loop.
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    f32::consts::PI,
+    fmt::{self, Display},
+    fs, io,
+    path::Path,
+};
+
+use nalgebra::Matrix3;
+use parry3d::math::Vector;
+
+use crate::{
+    crystal::{Crystal, Lattice, Site, SymmetryOp},
+    elements,
+};
+
+#[derive(Debug)]
+pub enum CifError {
+    Io(io::Error),
+    /// malformed input, `line` starts at one
+    Syntax {
+        line: usize,
+        message: String,
+    },
+    MissingTag(String),
+    InvalidValue {
+        tag: String,
+        value: String,
+    },
+}
+
+impl Display for CifError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CifError::Io(err) => write!(f, "{}", err),
+            CifError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            CifError::MissingTag(tag) => write!(f, "missing {}", tag),
+            CifError::InvalidValue { tag, value } => {
+                write!(f, "invalid value `{}` for {}", value, tag)
+            }
+        }
+    }
+}
+
+impl Error for CifError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CifError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// A loop of values, given row by row.
+#[derive(Debug, Clone, Default)]
+pub struct Loop {
+    pub tags: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Loop {
+    /// the values of a column, `None` if the loop does not contain the tag
+    pub fn column(&self, tag: &str) -> Option<impl Iterator<Item = &str>> {
+        let tag = normalize_tag(tag);
+        let index = self.tags.iter().position(|t| *t == tag)?;
+        Some(self.rows.iter().map(move |row| row[index].as_str()))
+    }
+}
+
+/// The tags and loops of one `data_` block.
+#[derive(Debug, Clone, Default)]
+pub struct DataBlock {
+    pub name: String,
+    pub values: HashMap<String, String>,
+    pub loops: Vec<Loop>,
+}
+
+impl DataBlock {
+    pub fn value(&self, tag: &str) -> Option<&str> {
+        self.values.get(&normalize_tag(tag)).map(String::as_str)
+    }
+
+    /// the loop containing the tag
+    pub fn find_loop(&self, tag: &str) -> Option<&Loop> {
+        let tag = normalize_tag(tag);
+        self.loops.iter().find(|l| l.tags.contains(&tag))
+    }
+
+    /// a numeric value, ignoring the standard uncertainty in parentheses
+    fn number(&self, tag: &str) -> Result<f32, CifError> {
+        let value = self
+            .value(tag)
+            .ok_or_else(|| CifError::MissingTag(tag.into()))?;
+        number(value).ok_or_else(|| CifError::InvalidValue {
+            tag: tag.into(),
+            value: value.into(),
+        })
+    }
+}
+
+/// parses all data blocks of a file
+pub fn parse(source: &str) -> Result<Vec<DataBlock>, CifError> {
+    let mut blocks: Vec<DataBlock> = Vec::new();
+    let mut tokens = tokenize(source)?.into_iter().peekable();
+    while let Some((line, token)) = tokens.next() {
+        if let Token::Data(name) = token {
+            blocks.push(DataBlock {
+                name,
+                ..Default::default()
+            });
+            continue;
+        }
+        let Some(block) = blocks.last_mut() else {
+            return Err(CifError::Syntax {
+                line,
+                message: "expected a data block".into(),
+            });
+        };
+        match token {
+            Token::Tag(tag) => match tokens.next() {
+                Some((_, Token::Value(value))) => {
+                    block.values.insert(tag, value);
+                }
+                _ => {
+                    return Err(CifError::Syntax {
+                        line,
+                        message: format!("missing value for _{}", tag),
+                    });
+                }
+            },
+            Token::Loop => {
+                let mut l = Loop::default();
+                while let Some((_, Token::Tag(tag))) = tokens.peek() {
+                    l.tags.push(tag.clone());
+                    tokens.next();
+                }
+                let mut values = Vec::new();
+                while let Some((_, Token::Value(value))) = tokens.peek() {
+                    values.push(value.clone());
+                    tokens.next();
+                }
+                if l.tags.is_empty() || values.len() % l.tags.len() != 0 {
+                    return Err(CifError::Syntax {
+                        line,
+                        message: "the number of values does not match the tags of the loop".into(),
+                    });
+                }
+                l.rows = values.chunks(l.tags.len()).map(<[_]>::to_vec).collect();
+                block.loops.push(l);
+            }
+            Token::Value(value) => {
+                return Err(CifError::Syntax {
+                    line,
+                    message: format!("unexpected value `{}`", value),
+                });
+            }
+            Token::Data(_) => unreachable!(),
+        }
+    }
+    Ok(blocks)
+}
+
+impl Crystal {
+    pub fn open_cif(path: impl AsRef<Path>) -> Result<Self, CifError> {
+        Self::from_cif(&fs::read_to_string(path).map_err(CifError::Io)?)
+    }
+
+    /// reads the cell parameters, the atom sites and the symmetry operations,
+    /// a file without symmetry operations only contains the identity
+    pub fn from_cif(source: &str) -> Result<Self, CifError> {
+        let blocks = parse(source)?;
+        let block = blocks
+            .iter()
+            .find(|block| block.value("_cell_length_a").is_some())
+            .ok_or_else(|| CifError::MissingTag("_cell_length_a".into()))?;
+
+        let lengths = [
+            block.number("_cell_length_a")?,
+            block.number("_cell_length_b")?,
+            block.number("_cell_length_c")?,
+        ];
+        let angles = [
+            block.number("_cell_angle_alpha")?,
+            block.number("_cell_angle_beta")?,
+            block.number("_cell_angle_gamma")?,
+        ];
+        let lattice = Lattice::from_parameters(lengths, angles.map(f32::to_radians));
+
+        let atoms = block
+            .find_loop("_atom_site_fract_x")
+            .ok_or_else(|| CifError::MissingTag("_atom_site_fract_x".into()))?;
+        let column = |tag: &str| {
+            atoms
+                .column(tag)
+                .ok_or_else(|| CifError::MissingTag(tag.into()))
+        };
+        let labels: Vec<&str> = column("_atom_site_label")?.collect();
+        let elements: Vec<&str> = match atoms.column("_atom_site_type_symbol") {
+            Some(symbols) => symbols.collect(),
+            None => labels.clone(),
+        };
+        let occupancies: Vec<&str> = match atoms.column("_atom_site_occupancy") {
+            Some(occupancies) => occupancies.collect(),
+            None => vec!["1"; labels.len()],
+        };
+        let fract: Vec<Vec<&str>> = [
+            "_atom_site_fract_x",
+            "_atom_site_fract_y",
+            "_atom_site_fract_z",
+        ]
+        .iter()
+        .map(|tag| Ok(column(tag)?.collect()))
+        .collect::<Result<_, CifError>>()?;
+        let parse = |tag: &str, value: &str| {
+            number(value).ok_or_else(|| CifError::InvalidValue {
+                tag: tag.into(),
+                value: value.into(),
+            })
+        };
+        let mut sites: Vec<Site> = (0..labels.len())
+            .map(|i| {
+                Ok(Site {
+                    label: labels[i].into(),
+                    element: elements::parse_symbol(elements[i]),
+                    fract: Vector::new(
+                        parse("_atom_site_fract_x", fract[0][i])?,
+                        parse("_atom_site_fract_y", fract[1][i])?,
+                        parse("_atom_site_fract_z", fract[2][i])?,
+                    ),
+                    occupancy: match occupancies[i] {
+                        "?" | "." => 1.0,
+                        value => parse("_atom_site_occupancy", value)?,
+                    },
+                    displacement: None,
+                })
+            })
+            .collect::<Result<_, CifError>>()?;
+        read_displacements(block, atoms, &lattice, &mut sites)?;
+
+        let symmetry = [
+            "_space_group_symop_operation_xyz",
+            "_symmetry_equiv_pos_as_xyz",
+        ]
+        .iter()
+        .find_map(|tag| match block.find_loop(tag) {
+            Some(ops) => Some(ops.column(tag)?.collect()),
+            None => block.value(tag).map(|op| vec![op]),
+        })
+        .unwrap_or_default()
+        .into_iter()
+        .map(|op| {
+            op.parse::<SymmetryOp>()
+                .map_err(|_| CifError::InvalidValue {
+                    tag: "_space_group_symop_operation_xyz".into(),
+                    value: op.into(),
+                })
+        })
+        .collect::<Result<_, CifError>>()?;
+
+        Ok(Crystal {
+            lattice,
+            sites,
+            symmetry,
+        })
+    }
+}
+
+/// reads the isotropic displacements of the sites and replaces them by the anisotropic ones where
+/// given, sites with unknown values are left without displacement
+fn read_displacements(
+    block: &DataBlock,
+    atoms: &Loop,
+    lattice: &Lattice,
+    sites: &mut [Site],
+) -> Result<(), CifError> {
+    // B = 8 π² U
+    let b_to_u = 1.0 / (8.0 * PI * PI);
+    for (tag, factor) in [
+        ("_atom_site_U_iso_or_equiv", 1.0),
+        ("_atom_site_B_iso_or_equiv", b_to_u),
+    ] {
+        let Some(values) = atoms.column(tag) else {
+            continue;
+        };
+        for (site, value) in sites.iter_mut().zip(values) {
+            if let Some(u) = number(value) {
+                site.displacement = Some(Matrix3::from_diagonal_element(u * factor));
+            }
+        }
+    }
+
+    let Some(aniso) = block.find_loop("_atom_site_aniso_label") else {
+        return Ok(());
+    };
+    let (prefix, factor) = match aniso.column("_atom_site_aniso_U_11") {
+        Some(_) => ("_atom_site_aniso_U_", 1.0),
+        None => ("_atom_site_aniso_B_", b_to_u),
+    };
+    let components: Vec<Vec<&str>> = ["11", "22", "33", "12", "13", "23"]
+        .iter()
+        .map(|ij| {
+            let tag = format!("{}{}", prefix, ij);
+            match aniso.column(&tag) {
+                Some(values) => Ok(values.collect()),
+                None => Err(CifError::MissingTag(tag)),
+            }
+        })
+        .collect::<Result<_, CifError>>()?;
+    let labels = aniso.column("_atom_site_aniso_label").into_iter().flatten();
+    for (row, label) in labels.enumerate() {
+        let Some(u) = components
+            .iter()
+            .map(|values| number(values[row]))
+            .collect::<Option<Vec<f32>>>()
+        else {
+            continue;
+        };
+        let u = Matrix3::new(u[0], u[3], u[4], u[3], u[1], u[5], u[4], u[5], u[2]) * factor;
+        let u = lattice.displacement_to_cartesian(&u);
+        for site in sites.iter_mut().filter(|site| site.label == label) {
+            site.displacement = Some(u);
+        }
+    }
+    Ok(())
+}
+
+/// parses a number like `5.4307(2)`, `?` and `.` stand for unknown values
+pub fn number(value: &str) -> Option<f32> {
+    let value = match value.find('(') {
+        Some(idx) => &value[..idx],
+        None => value,
+    };
+    value.parse().ok()
+}
+
+/// lowercase without the leading underscore, with `.` replaced by `_`
+fn normalize_tag(tag: &str) -> String {
+    tag.trim_start_matches('_').to_lowercase().replace('.', "_")
+}
+
+enum Token {
+    Data(String),
+    Loop,
+    Tag(String),
+    Value(String),
+}
+
+/// splits the file into tokens with their line numbers
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, CifError> {
+    let mut tokens = Vec::new();
+    let mut lines = source.lines().enumerate().map(|(i, line)| (i + 1, line));
+    while let Some((number, line)) = lines.next() {
+        // text fields span the lines between two lines starting with a semicolon
+        if let Some(first) = line.strip_prefix(';') {
+            let mut text = vec![first];
+            loop {
+                match lines.next() {
+                    Some((_, line)) if line.starts_with(';') => break,
+                    Some((_, line)) => text.push(line),
+                    None => {
+                        return Err(CifError::Syntax {
+                            line: number,
+                            message: "unterminated text field".into(),
+                        });
+                    }
+                }
+            }
+            tokens.push((number, Token::Value(text.join("\n").trim().into())));
+            continue;
+        }
+
+        let mut rest = line.trim_start();
+        while !rest.is_empty() {
+            if rest.starts_with('#') {
+                break;
+            }
+            let (token, remainder) = if let Some(quote @ ('\'' | '"')) = rest.chars().next() {
+                // a quote only ends the value if it is followed by whitespace
+                let end = rest[1..]
+                    .char_indices()
+                    .find(|&(idx, c)| {
+                        c == quote
+                            && rest[idx + 2..]
+                                .chars()
+                                .next()
+                                .is_none_or(char::is_whitespace)
+                    })
+                    .map(|(idx, _)| idx + 1)
+                    .ok_or_else(|| CifError::Syntax {
+                        line: number,
+                        message: "unterminated quoted value".into(),
+                    })?;
+                (Token::Value(rest[1..end].into()), &rest[end + 1..])
+            } else {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                let word = &rest[..end];
+                let lower = word.to_lowercase();
+                let token = if lower == "loop_" {
+                    Token::Loop
+                } else if lower.starts_with("data_") {
+                    Token::Data(word[5..].into())
+                } else if word.starts_with('_') {
+                    Token::Tag(normalize_tag(word))
+                } else {
+                    Token::Value(word.into())
+                };
+                (token, &rest[end..])
+            };
+            tokens.push((number, token));
+            rest = remainder.trim_start();
+        }
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SILICON: &str = "\
+data_Si
+_cell_length_a 5.4307(2)
+_cell_length_b 5.4307(2)
+_cell_length_c 5.4307(2)
+_cell_angle_alpha 90
+_cell_angle_beta 90.0
+_cell_angle_gamma 90.(1)
+_symmetry_equiv_pos_as_xyz 'x, y, z'
+loop_
+_atom_site_label
+_atom_site_type_symbol
+_atom_site_fract_x
+_atom_site_fract_y
+_atom_site_fract_z
+_atom_site_U_iso_or_equiv
+Si1 Si 0 0 0 0.0050(3)
+";
+
+    #[test]
+    fn numbers_ignore_uncertainties() {
+        assert_eq!(number("5.4307(2)"), Some(5.4307));
+        assert_eq!(number("-0.125(14)"), Some(-0.125));
+        assert_eq!(number("90"), Some(90.0));
+        assert_eq!(number("?"), None);
+        assert_eq!(number("."), None);
+    }
+
+    #[test]
+    fn reads_cell_with_uncertainties() {
+        let crystal = Crystal::from_cif(SILICON).unwrap();
+        let [a, b, c] = crystal.lattice.vectors;
+        assert!((a.norm() - 5.4307).abs() < 1e-4);
+        assert!((b.norm() - 5.4307).abs() < 1e-4);
+        assert!((c.norm() - 5.4307).abs() < 1e-4);
+        assert!(a.dot(&b).abs() < 1e-3 && a.dot(&c).abs() < 1e-3 && b.dot(&c).abs() < 1e-3);
+        let site = &crystal.sites[0];
+        assert_eq!((site.label.as_str(), site.element.as_str()), ("Si1", "Si"));
+        assert_eq!(site.occupancy, 1.0);
+        let u = site.displacement.unwrap();
+        assert!((u[(0, 0)] - 0.005).abs() < 1e-6 && u[(0, 1)] == 0.0);
+    }
+
+    #[test]
+    fn reads_single_symmetry_operation() {
+        let crystal = Crystal::from_cif(SILICON).unwrap();
+        assert_eq!(crystal.symmetry, vec![SymmetryOp::identity()]);
+    }
+
+    #[test]
+    fn reads_looped_symmetry_operations() {
+        let source = SILICON.replace(
+            "_symmetry_equiv_pos_as_xyz 'x, y, z'\n",
+            "loop_\n_space_group_symop.operation_xyz\n'x, y, z'\n'x+1/2, y+1/2, z'\n\
+             'x+1/2, y, z+1/2'\n'x, y+1/2, z+1/2'\n",
+        );
+        let crystal = Crystal::from_cif(&source).unwrap();
+        assert_eq!(crystal.symmetry.len(), 4);
+        assert_eq!(crystal.symmetry[1].translation, Vector::new(0.5, 0.5, 0.0));
+        // a face centred cubic cell
+        assert_eq!(crystal.unit_cell().len(), 4);
+    }
+
+    #[test]
+    fn missing_symmetry_is_identity() {
+        let source = SILICON.replace("_symmetry_equiv_pos_as_xyz 'x, y, z'\n", "");
+        let crystal = Crystal::from_cif(&source).unwrap();
+        assert!(crystal.symmetry.is_empty());
+        assert_eq!(crystal.unit_cell().len(), 1);
+    }
+
+    #[test]
+    fn reads_anisotropic_displacements() {
+        let source = format!(
+            "{}loop_\n_atom_site_aniso_label\n_atom_site_aniso_U_11\n_atom_site_aniso_U_22\n\
+             _atom_site_aniso_U_33\n_atom_site_aniso_U_12\n_atom_site_aniso_U_13\n\
+             _atom_site_aniso_U_23\nSi1 0.01 0.02 0.03(1) 0 0 0\n",
+            SILICON
+        );
+        let crystal = Crystal::from_cif(&source).unwrap();
+        let u = crystal.sites[0].displacement.unwrap();
+        let diagonal = [u[(0, 0)], u[(1, 1)], u[(2, 2)]];
+        for (value, expected) in diagonal.into_iter().zip([0.01, 0.02, 0.03]) {
+            assert!((value - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn parses_text_fields_and_quotes() {
+        let blocks = parse(
+            "data_a\n_title\n;first line\nsecond line\n;\n_name 'O'Brien'\n\
+             _DDLm.Tag \"x\" # a comment\n",
+        )
+        .unwrap();
+        let block = &blocks[0];
+        assert_eq!(block.name, "a");
+        assert_eq!(block.value("_title"), Some("first line\nsecond line"));
+        assert_eq!(block.value("_name"), Some("O'Brien"));
+        assert_eq!(block.value("_ddlm_tag"), Some("x"));
+    }
+
+    #[test]
+    fn reports_syntax_errors_with_lines() {
+        match parse("data_a\n_tag\n;never closed\n") {
+            Err(CifError::Syntax { line, .. }) => assert_eq!(line, 3),
+            other => panic!("expected a syntax error, got {:?}", other),
+        }
+        match parse("_tag value\n") {
+            Err(CifError::Syntax { line, .. }) => assert_eq!(line, 1),
+            other => panic!("expected a syntax error, got {:?}", other),
+        }
+        assert!(matches!(
+            Crystal::from_cif("data_a\n_cell_length_a 1\n"),
+            Err(CifError::MissingTag(_))
+        ));
+    }
+}
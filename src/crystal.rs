@@ -0,0 +1,392 @@
+//! Periodic crystal structures given by a lattice, the atoms of the asymmetric unit and the
+//! symmetry operations of the space group.
+
+use std::str::FromStr;
+
+use nalgebra::Matrix3;
+use parry3d::math::{Isometry, Point, Vector};
+
+use crate::{
+    elements::PeriodicTable,
+    graph::{Group, Prototype},
+    objects::{Color, Object, Scene},
+    structure::{Representation, Structure},
+};
+
+/// two sites closer than this in Å are considered the same after applying the symmetry
+const SITE_TOLERANCE: f32 = 1e-2;
+
+/// The three lattice vectors of a unit cell in Å.
+#[derive(Debug, Clone, Copy)]
+pub struct Lattice {
+    pub vectors: [Vector<f32>; 3],
+}
+
+impl Lattice {
+    pub fn new(a: Vector<f32>, b: Vector<f32>, c: Vector<f32>) -> Self {
+        Self { vectors: [a, b, c] }
+    }
+
+    /// builds the lattice from the cell lengths and angles in radians,
+    /// `a` points along the x axis and `b` lies in the xy plane
+    pub fn from_parameters(lengths: [f32; 3], angles: [f32; 3]) -> Self {
+        let [a, b, c] = lengths;
+        let [alpha, beta, gamma] = angles.map(f32::cos);
+        let sin_gamma = angles[2].sin();
+        let cy = (alpha - beta * gamma) / sin_gamma;
+        let cz = (1.0 - beta * beta - cy * cy).max(0.0).sqrt();
+        Self::new(
+            Vector::new(a, 0.0, 0.0),
+            Vector::new(b * gamma, b * sin_gamma, 0.0),
+            Vector::new(c * beta, c * cy, c * cz),
+        )
+    }
+
+    /// the lattice vectors as columns
+    pub fn matrix(&self) -> Matrix3<f32> {
+        Matrix3::from_columns(&self.vectors)
+    }
+
+    pub fn to_cartesian(&self, fract: &Vector<f32>) -> Point<f32> {
+        (self.matrix() * fract).into()
+    }
+
+    pub fn to_fractional(&self, point: &Point<f32>) -> Vector<f32> {
+        self.matrix()
+            .try_inverse()
+            .expect("lattice vectors are linearly dependent")
+            * point.coords
+    }
+
+    /// converts a displacement tensor given along the reciprocal lattice vectors, like the
+    /// `U_ij` of CIF files, to the cartesian axes
+    pub fn displacement_to_cartesian(&self, u: &Matrix3<f32>) -> Matrix3<f32> {
+        let matrix = self.matrix();
+        let reciprocal = matrix
+            .try_inverse()
+            .expect("lattice vectors are linearly dependent");
+        let lengths = Matrix3::from_diagonal(&Vector::from_fn(|i, _| reciprocal.row(i).norm()));
+        matrix * lengths * u * lengths * matrix.transpose()
+    }
+
+    /// the distance between two fractional positions, using the closest periodic image
+    pub fn periodic_distance(&self, a: &Vector<f32>, b: &Vector<f32>) -> f32 {
+        let d = (a - b).map(|x| x - x.round());
+        (self.matrix() * d).norm()
+    }
+
+    /// the shortest cartesian vector equivalent to `d` under the lattice translations
+    pub fn minimum_image(&self, d: &Vector<f32>) -> Vector<f32> {
+        let fract = self.to_fractional(&Point::from(*d)).map(|x| x - x.round());
+        self.matrix() * fract
+    }
+}
+
+/// A symmetry operation acting on fractional coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SymmetryOp {
+    pub rotation: Matrix3<f32>,
+    pub translation: Vector<f32>,
+}
+
+impl SymmetryOp {
+    pub fn identity() -> Self {
+        Self {
+            rotation: Matrix3::identity(),
+            translation: Vector::zeros(),
+        }
+    }
+
+    pub fn apply(&self, fract: &Vector<f32>) -> Vector<f32> {
+        self.rotation * fract + self.translation
+    }
+}
+
+impl FromStr for SymmetryOp {
+    type Err = String;
+
+    /// parses operations in the Jones faithful notation, like `-y+1/2, x, z+0.25`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("invalid symmetry operation `{}`", s);
+        let rows: Vec<&str> = s.split(',').map(str::trim).collect();
+        if rows.len() != 3 {
+            return Err(error());
+        }
+        let mut op = Self {
+            rotation: Matrix3::zeros(),
+            translation: Vector::zeros(),
+        };
+        for (i, row) in rows.iter().enumerate() {
+            let row: String = row.chars().filter(|c| !c.is_whitespace()).collect();
+            if row.is_empty() {
+                return Err(error());
+            }
+            // split before every sign, keeping the sign with its term
+            let mut terms = Vec::new();
+            let mut start = 0;
+            for (idx, c) in row.char_indices().skip(1) {
+                if c == '+' || c == '-' {
+                    terms.push(&row[start..idx]);
+                    start = idx;
+                }
+            }
+            terms.push(&row[start..]);
+
+            for term in terms {
+                let (sign, term) = match term.as_bytes().first() {
+                    Some(b'-') => (-1.0, &term[1..]),
+                    Some(b'+') => (1.0, &term[1..]),
+                    _ => (1.0, term),
+                };
+                let axis = match term.chars().last() {
+                    Some('x' | 'X') => Some(0),
+                    Some('y' | 'Y') => Some(1),
+                    Some('z' | 'Z') => Some(2),
+                    _ => None,
+                };
+                let number = match axis {
+                    Some(_) => term[..term.len() - 1].trim_end_matches('*'),
+                    None => term,
+                };
+                let value = if number.is_empty() {
+                    1.0
+                } else if let Some((numerator, denominator)) = number.split_once('/') {
+                    let numerator: f32 = numerator.parse().map_err(|_| error())?;
+                    let denominator: f32 = denominator.parse().map_err(|_| error())?;
+                    numerator / denominator
+                } else {
+                    number.parse().map_err(|_| error())?
+                };
+                match axis {
+                    Some(axis) => op.rotation[(i, axis)] += sign * value,
+                    None if !number.is_empty() => op.translation[i] += sign * value,
+                    None => return Err(error()),
+                }
+            }
+        }
+        Ok(op)
+    }
+}
+
+/// An atom position in fractional coordinates.
+#[derive(Debug, Clone)]
+pub struct Site {
+    pub label: String,
+    /// the element symbol, like `Fe`
+    pub element: String,
+    pub fract: Vector<f32>,
+    /// the probability of the site being occupied
+    pub occupancy: f32,
+    /// the displacement tensor U in Å² along the cartesian axes
+    pub displacement: Option<Matrix3<f32>>,
+}
+
+/// A crystal structure, the symmetry operations map the asymmetric unit onto the whole unit cell.
+#[derive(Debug, Clone)]
+pub struct Crystal {
+    pub lattice: Lattice,
+    pub sites: Vec<Site>,
+    pub symmetry: Vec<SymmetryOp>,
+}
+
+impl Crystal {
+    /// all sites of one unit cell, wrapped into `[0, 1)`
+    pub fn unit_cell(&self) -> Vec<Site> {
+        let identity = [SymmetryOp::identity()];
+        let symmetry = if self.symmetry.is_empty() {
+            &identity[..]
+        } else {
+            &self.symmetry[..]
+        };
+        let mut sites: Vec<Site> = Vec::new();
+        for site in &self.sites {
+            for op in symmetry {
+                // the rotation of the operation in cartesian coordinates
+                let rotation = self.lattice.matrix()
+                    * op.rotation
+                    * self
+                        .lattice
+                        .matrix()
+                        .try_inverse()
+                        .expect("lattice vectors are linearly dependent");
+                let fract = op.apply(&site.fract).map(|x| {
+                    let x = x.rem_euclid(1.0);
+                    // tiny negative values round up to one
+                    if x >= 1.0 { 0.0 } else { x }
+                });
+                let duplicate = sites.iter().any(|other| {
+                    other.element == site.element
+                        && self.lattice.periodic_distance(&other.fract, &fract) < SITE_TOLERANCE
+                });
+                if !duplicate {
+                    sites.push(Site {
+                        fract,
+                        displacement: site
+                            .displacement
+                            .map(|u| rotation * u * rotation.transpose()),
+                        ..site.clone()
+                    });
+                }
+            }
+        }
+        sites
+    }
+
+    /// all sites of a block of `cells` unit cells, the fractional coordinates are relative to
+    /// the unit cell and range up to `cells`
+    pub fn supercell(&self, cells: [usize; 3]) -> Vec<Site> {
+        let unit_cell = self.unit_cell();
+        let mut sites = Vec::with_capacity(unit_cell.len() * cells.iter().product::<usize>());
+        for x in 0..cells[0] {
+            for y in 0..cells[1] {
+                for z in 0..cells[2] {
+                    let offset = Vector::new(x as f32, y as f32, z as f32);
+                    sites.extend(unit_cell.iter().map(|site| Site {
+                        fract: site.fract + offset,
+                        ..site.clone()
+                    }));
+                }
+            }
+        }
+        sites
+    }
+
+    /// the atoms of the supercell, partially occupied sites are included like full ones
+    pub fn structure(&self, cells: [usize; 3]) -> Structure {
+        let sites = self.supercell(cells);
+        let [a, b, c] = self.lattice.vectors;
+        Structure {
+            species: sites.iter().map(|site| site.element.clone()).collect(),
+            positions: sites
+                .iter()
+                .map(|site| self.lattice.to_cartesian(&site.fract))
+                .collect(),
+            lattice: Some(Lattice::new(
+                a * cells[0] as f32,
+                b * cells[1] as f32,
+                c * cells[2] as f32,
+            )),
+            bonds: None,
+            displacements: sites.iter().map(|site| site.displacement).collect(),
+        }
+    }
+
+    /// one ball per atom of the supercell
+    pub fn objects(&self, cells: [usize; 3]) -> Vec<Object> {
+        self.structure(cells)
+            .objects(&Representation::default(), &PeriodicTable::default())
+    }
+
+    /// a scene with the supercell in front of a white background, see [`Structure::scene`]
+    pub fn scene(&self, cells: [usize; 3]) -> Scene {
+        self.structure(cells)
+            .scene(&Representation::default(), &PeriodicTable::default())
+    }
+
+    /// like `scene`, but the supercell is a group named `cells` of instances of one unit cell,
+    /// so supercells with millions of atoms fit into memory, the bonds across the cell
+    /// boundary are drawn in halves from both sides and stick out of the outermost cells
+    pub fn instanced_scene(
+        &self,
+        cells: [usize; 3],
+        representation: &Representation,
+        table: &PeriodicTable,
+    ) -> Scene {
+        let unit_cell = self.structure([1, 1, 1]);
+        let mut group = Group::new("cells");
+        for x in 0..cells[0] {
+            for y in 0..cells[1] {
+                for z in 0..cells[2] {
+                    let offset = Vector::new(x as f32, y as f32, z as f32);
+                    let translation = self.lattice.to_cartesian(&offset).coords;
+                    group = group.add_instance("unit cell", Isometry::from(translation));
+                }
+            }
+        }
+        let [a, b, c] = self.lattice.vectors;
+        // only the corners of the supercell place the camera
+        let outline = Structure {
+            lattice: Some(Lattice::new(
+                a * cells[0] as f32,
+                b * cells[1] as f32,
+                c * cells[2] as f32,
+            )),
+            ..Default::default()
+        };
+        Scene::new(Color(1.0, 1.0, 1.0))
+            .add_prototype(Prototype::new(
+                "unit cell",
+                unit_cell.objects(representation, table),
+            ))
+            .add_group(group)
+            .expect("the cells are instances of the unit cell")
+            .with_camera(outline.camera())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn op(s: &str) -> SymmetryOp {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_jones_faithful_notation() {
+        let parsed = op("-y+1/2, x, z+0.25");
+        assert_eq!(
+            parsed.rotation,
+            Matrix3::new(0.0, -1.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0)
+        );
+        assert_eq!(parsed.translation, Vector::new(0.5, 0.0, 0.25));
+        assert_eq!(op("x,y,z"), SymmetryOp::identity());
+    }
+
+    #[test]
+    fn parses_combined_axes_and_leading_translations() {
+        let parsed = op("1/2+x-y, -X, 2*z - 1/3");
+        assert_eq!(
+            parsed.rotation,
+            Matrix3::new(1.0, -1.0, 0.0, -1.0, 0.0, 0.0, 0.0, 0.0, 2.0)
+        );
+        assert!((parsed.translation - Vector::new(0.5, 0.0, -1.0 / 3.0)).norm() < 1e-6);
+    }
+
+    #[test]
+    fn rejects_malformed_operations() {
+        for s in [
+            "x, y",
+            "x, y, z, x",
+            "x, , z",
+            "x, y, q",
+            "x, y, 1/0.5.1",
+            "x, y, +",
+        ] {
+            assert!(s.parse::<SymmetryOp>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn unit_cell_wraps_and_merges_sites() {
+        let crystal = Crystal {
+            lattice: Lattice::from_parameters([4.0; 3], [std::f32::consts::FRAC_PI_2; 3]),
+            sites: vec![Site {
+                label: "Na1".into(),
+                element: "Na".into(),
+                fract: Vector::new(0.0, 0.0, 0.0),
+                occupancy: 1.0,
+                displacement: None,
+            }],
+            symmetry: vec![op("x, y, z"), op("-x, -y, -z"), op("x+1/2, y+1/2, z")],
+        };
+        let sites = crystal.unit_cell();
+        // the inversion maps the origin onto itself
+        assert_eq!(sites.len(), 2);
+        assert!(
+            sites
+                .iter()
+                .all(|site| site.fract.iter().all(|x| (0.0..1.0).contains(x)))
+        );
+    }
+}
@@ -0,0 +1,318 @@
+//! Building supercells from a motif with vacancies, substitutions, interstitials and partially
+//! occupied sites.
+//!
+//! Random occupancies are drawn from a seeded generator, so the same builder always gives the
+//! same structure.
+
+use nalgebra::Matrix3;
+use parry3d::math::{Point, Vector};
+use rand::{Rng, SeedableRng, rngs::SmallRng};
+
+use crate::{
+    crystal::{Crystal, Lattice},
+    structure::Structure,
+};
+
+/// An atom of a motif site, `offset` is cartesian in Å from the site.
+#[derive(Debug, Clone)]
+pub struct MotifAtom {
+    /// the element symbol, like `Fe`
+    pub element: String,
+    pub offset: Vector<f32>,
+    /// the displacement tensor U in Å² along the cartesian axes
+    pub displacement: Option<Matrix3<f32>>,
+}
+
+impl MotifAtom {
+    pub fn new(element: &str, offset: Vector<f32>) -> Self {
+        Self {
+            element: element.into(),
+            offset,
+            displacement: None,
+        }
+    }
+
+    pub fn with_displacement(mut self, displacement: Matrix3<f32>) -> Self {
+        self.displacement = Some(displacement);
+        self
+    }
+}
+
+/// A site of the motif, holding a single atom or a group that is placed and removed as a whole,
+/// like the hexacyanometallate of a Prussian blue analogue.
+#[derive(Debug, Clone)]
+pub struct MotifSite {
+    pub label: String,
+    pub fract: Vector<f32>,
+    pub atoms: Vec<MotifAtom>,
+}
+
+impl MotifSite {
+    /// a site with a single atom
+    pub fn atom(label: &str, element: &str, fract: Vector<f32>) -> Self {
+        Self::group(label, fract, vec![MotifAtom::new(element, Vector::zeros())])
+    }
+
+    pub fn group(label: &str, fract: Vector<f32>, atoms: Vec<MotifAtom>) -> Self {
+        Self {
+            label: label.into(),
+            fract,
+            atoms,
+        }
+    }
+}
+
+/// A change to the sites of the supercell, applied in the order they were added.
+#[derive(Debug, Clone)]
+pub enum Defect {
+    /// removes the sites with the label in the unit cell `cell`
+    Vacancy { label: String, cell: [usize; 3] },
+    /// replaces the atoms of the sites with the label in the unit cell `cell`
+    Substitution {
+        label: String,
+        cell: [usize; 3],
+        atoms: Vec<MotifAtom>,
+    },
+    /// adds atoms at a fractional position of the unit cell `cell`
+    Interstitial {
+        cell: [usize; 3],
+        fract: Vector<f32>,
+        atoms: Vec<MotifAtom>,
+    },
+    /// keeps every occupied site with the label with the probability `occupancy`
+    Occupancy { label: String, occupancy: f32 },
+}
+
+/// A supercell of a motif with defects.
+#[derive(Debug, Clone)]
+pub struct LatticeBuilder {
+    lattice: Lattice,
+    cells: [usize; 3],
+    motif: Vec<MotifSite>,
+    defects: Vec<Defect>,
+    seed: u64,
+}
+
+/// a site of the supercell, `atoms` is `None` for vacancies
+struct Placement {
+    label: String,
+    cell: [usize; 3],
+    /// relative to the unit cell, ranging up to `cells`
+    fract: Vector<f32>,
+    atoms: Option<Vec<MotifAtom>>,
+    interstitial: bool,
+}
+
+impl LatticeBuilder {
+    pub fn new(lattice: Lattice, cells: [usize; 3]) -> Self {
+        Self {
+            lattice,
+            cells,
+            motif: Vec::new(),
+            defects: Vec::new(),
+            seed: 0,
+        }
+    }
+
+    pub fn with_site(mut self, site: MotifSite) -> Self {
+        self.motif.push(site);
+        self
+    }
+
+    pub fn with_defect(mut self, defect: Defect) -> Self {
+        self.defects.push(defect);
+        self
+    }
+
+    pub fn with_vacancy(self, label: &str, cell: [usize; 3]) -> Self {
+        self.with_defect(Defect::Vacancy {
+            label: label.into(),
+            cell,
+        })
+    }
+
+    pub fn with_substitution(self, label: &str, cell: [usize; 3], atoms: Vec<MotifAtom>) -> Self {
+        self.with_defect(Defect::Substitution {
+            label: label.into(),
+            cell,
+            atoms,
+        })
+    }
+
+    pub fn with_interstitial(
+        self,
+        cell: [usize; 3],
+        fract: Vector<f32>,
+        atoms: Vec<MotifAtom>,
+    ) -> Self {
+        self.with_defect(Defect::Interstitial { cell, fract, atoms })
+    }
+
+    pub fn with_occupancy(self, label: &str, occupancy: f32) -> Self {
+        self.with_defect(Defect::Occupancy {
+            label: label.into(),
+            occupancy,
+        })
+    }
+
+    /// the seed of the random occupancies, zero by default
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    fn place(&self) -> Vec<Placement> {
+        let mut sites = Vec::new();
+        for x in 0..self.cells[0] {
+            for y in 0..self.cells[1] {
+                for z in 0..self.cells[2] {
+                    let offset = Vector::new(x as f32, y as f32, z as f32);
+                    sites.extend(self.motif.iter().map(|site| Placement {
+                        label: site.label.clone(),
+                        cell: [x, y, z],
+                        fract: site.fract + offset,
+                        atoms: Some(site.atoms.clone()),
+                        interstitial: false,
+                    }));
+                }
+            }
+        }
+
+        let mut rng = SmallRng::seed_from_u64(self.seed);
+        for defect in &self.defects {
+            match defect {
+                Defect::Vacancy { label, cell } => sites
+                    .iter_mut()
+                    .filter(|site| site.label == *label && site.cell == *cell)
+                    .for_each(|site| site.atoms = None),
+                Defect::Substitution { label, cell, atoms } => sites
+                    .iter_mut()
+                    .filter(|site| site.label == *label && site.cell == *cell)
+                    .for_each(|site| site.atoms = Some(atoms.clone())),
+                Defect::Interstitial { cell, fract, atoms } => sites.push(Placement {
+                    label: String::new(),
+                    cell: *cell,
+                    fract: fract + Vector::from(cell.map(|i| i as f32)),
+                    atoms: Some(atoms.clone()),
+                    interstitial: true,
+                }),
+                Defect::Occupancy { label, occupancy } => {
+                    for site in &mut sites {
+                        if site.label == *label
+                            && site.atoms.is_some()
+                            && rng.r#gen::<f32>() >= *occupancy
+                        {
+                            site.atoms = None;
+                        }
+                    }
+                }
+            }
+        }
+        sites
+    }
+
+    /// the atoms of the supercell
+    pub fn build(&self) -> Structure {
+        let mut structure = Structure {
+            lattice: Some(Lattice {
+                vectors: [0, 1, 2].map(|i| self.lattice.vectors[i] * self.cells[i] as f32),
+            }),
+            ..Default::default()
+        };
+        for site in self.place() {
+            let position = self.lattice.to_cartesian(&site.fract);
+            for atom in site.atoms.iter().flatten() {
+                structure.species.push(atom.element.clone());
+                structure.positions.push(position + atom.offset);
+                structure.displacements.push(atom.displacement);
+            }
+        }
+        structure
+    }
+
+    /// the positions of the motif sites left empty
+    pub fn vacancies(&self) -> Vec<Point<f32>> {
+        self.place()
+            .iter()
+            .filter(|site| site.atoms.is_none() && !site.interstitial)
+            .map(|site| self.lattice.to_cartesian(&site.fract))
+            .collect()
+    }
+}
+
+impl Crystal {
+    /// a builder for a supercell of the unit cell, partially occupied sites get their
+    /// occupancy drawn at random
+    pub fn builder(&self, cells: [usize; 3]) -> LatticeBuilder {
+        let mut builder = LatticeBuilder::new(self.lattice, cells);
+        let mut partial: Vec<(String, f32)> = Vec::new();
+        for site in self.unit_cell() {
+            if site.occupancy < 1.0 && !partial.iter().any(|(label, _)| *label == site.label) {
+                partial.push((site.label.clone(), site.occupancy));
+            }
+            let mut atom = MotifAtom::new(&site.element, Vector::zeros());
+            atom.displacement = site.displacement;
+            builder = builder.with_site(MotifSite::group(&site.label, site.fract, vec![atom]));
+        }
+        for (label, occupancy) in partial {
+            builder = builder.with_occupancy(&label, occupancy);
+        }
+        builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builder() -> LatticeBuilder {
+        let lattice = Lattice::new(
+            Vector::new(10.0, 0.0, 0.0),
+            Vector::new(0.0, 10.0, 0.0),
+            Vector::new(0.0, 0.0, 10.0),
+        );
+        LatticeBuilder::new(lattice, [2, 2, 2])
+            .with_site(MotifSite::atom("A", "Na", Vector::zeros()))
+            .with_site(MotifSite::atom("B", "Cl", Vector::new(0.5, 0.5, 0.5)))
+    }
+
+    fn atoms(structure: &Structure) -> Vec<(String, Point<f32>)> {
+        structure
+            .species
+            .iter()
+            .cloned()
+            .zip(structure.positions.iter().copied())
+            .collect()
+    }
+
+    #[test]
+    fn occupancies_depend_only_on_the_seed() {
+        let partial = builder().with_occupancy("A", 0.5);
+        let first = partial.clone().with_seed(7).build();
+        let again = partial.clone().with_seed(7).build();
+        let other = partial.with_seed(8).build();
+        assert_eq!(atoms(&first), atoms(&again));
+        assert_ne!(atoms(&first), atoms(&other));
+        assert!(first.species.iter().filter(|s| *s == "Na").count() < 8);
+    }
+
+    #[test]
+    fn defects_land_in_their_cell() {
+        let structure = builder()
+            .with_vacancy("A", [1, 0, 0])
+            .with_substitution("B", [0, 1, 0], vec![MotifAtom::new("K", Vector::zeros())])
+            .with_interstitial(
+                [1, 1, 1],
+                Vector::new(0.25, 0.25, 0.25),
+                vec![MotifAtom::new("H", Vector::new(0.0, 0.0, 1.0))],
+            );
+        assert_eq!(structure.vacancies(), vec![Point::new(10.0, 0.0, 0.0)]);
+        let atoms = atoms(&structure.build());
+        assert_eq!(atoms.len(), 16);
+        assert!(!atoms.iter().any(|(_, p)| *p == Point::new(10.0, 0.0, 0.0)));
+        assert!(atoms.contains(&("Na".into(), Point::new(0.0, 0.0, 0.0))));
+        assert!(atoms.contains(&("K".into(), Point::new(5.0, 15.0, 5.0))));
+        assert!(atoms.contains(&("Cl".into(), Point::new(5.0, 5.0, 5.0))));
+        assert!(atoms.contains(&("H".into(), Point::new(12.5, 12.5, 13.5))));
+    }
+}
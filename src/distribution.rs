@@ -0,0 +1,100 @@
+/// A piecewise-constant distribution on `[0, 1)`.
+pub struct Distribution1D {
+    func: Vec<f32>,
+    cdf: Vec<f32>,
+    integral: f32,
+}
+
+impl Distribution1D {
+    pub fn new(func: Vec<f32>) -> Self {
+        let n = func.len() as f32;
+        let mut cdf = Vec::with_capacity(func.len() + 1);
+        cdf.push(0.0);
+        for (i, value) in func.iter().enumerate() {
+            cdf.push(cdf[i] + value.abs() / n);
+        }
+        let integral = cdf[func.len()];
+        if integral == 0.0 {
+            // fall back to a uniform distribution
+            for (i, value) in cdf.iter_mut().enumerate() {
+                *value = i as f32 / n;
+            }
+        } else {
+            for value in cdf.iter_mut() {
+                *value /= integral;
+            }
+        }
+        Self {
+            func,
+            cdf,
+            integral,
+        }
+    }
+
+    pub fn integral(&self) -> f32 {
+        self.integral
+    }
+
+    /// maps a uniform random number to `(x, pdf, segment)`
+    pub fn sample(&self, u: f32) -> (f32, f32, usize) {
+        let segment = self
+            .cdf
+            .partition_point(|&c| c <= u)
+            .saturating_sub(1)
+            .min(self.func.len() - 1);
+        let width = self.cdf[segment + 1] - self.cdf[segment];
+        let offset = if width > 0.0 {
+            (u - self.cdf[segment]) / width
+        } else {
+            0.0
+        };
+        let x = (segment as f32 + offset) / self.func.len() as f32;
+        (x, self.pdf_segment(segment), segment)
+    }
+
+    pub fn pdf(&self, x: f32) -> f32 {
+        let segment = ((x * self.func.len() as f32) as usize).min(self.func.len() - 1);
+        self.pdf_segment(segment)
+    }
+
+    fn pdf_segment(&self, segment: usize) -> f32 {
+        if self.integral == 0.0 {
+            1.0
+        } else {
+            self.func[segment].abs() / self.integral
+        }
+    }
+}
+
+/// A piecewise-constant distribution on `[0, 1)²`, `func` is given row by row.
+pub struct Distribution2D {
+    conditional: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    pub fn new(func: &[f32], width: usize, height: usize) -> Self {
+        let conditional: Vec<_> = func
+            .chunks_exact(width)
+            .take(height)
+            .map(|row| Distribution1D::new(row.to_vec()))
+            .collect();
+        let marginal = Distribution1D::new(conditional.iter().map(|d| d.integral()).collect());
+        Self {
+            conditional,
+            marginal,
+        }
+    }
+
+    /// maps two uniform random numbers to `((u, v), pdf)`
+    pub fn sample(&self, u: f32, v: f32) -> ((f32, f32), f32) {
+        let (y, pdf_y, row) = self.marginal.sample(v);
+        let (x, pdf_x, _) = self.conditional[row].sample(u);
+        ((x, y), pdf_x * pdf_y)
+    }
+
+    pub fn pdf(&self, u: f32, v: f32) -> f32 {
+        let row = ((v * self.conditional.len() as f32) as usize).min(self.conditional.len() - 1);
+        self.marginal.pdf(v) * self.conditional[row].pdf(u)
+    }
+}
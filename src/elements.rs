@@ -0,0 +1,902 @@
+//! Data of all elements for drawing atoms.
+//!
+//! Covalent radii are from Cordero et al. 2008 and from Pyykkö and Atsumi 2009 beyond curium,
+//! van der Waals radii from Bondi 1964, Mantina et al. 2009 and Alvarez 2013, where elements
+//! without a known radius use 2 Å. Ionic radii are the effective radii of Shannon 1976.
+
+use std::collections::HashMap;
+
+use parry3d::{
+    math::{Isometry, Point},
+    shape::Ball,
+};
+
+use crate::objects::{Color, Object, Shape};
+
+/// the color of elements missing from a color scheme
+const UNKNOWN_COLOR: u32 = 0xFF1493;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorScheme {
+    #[default]
+    Jmol,
+    /// the scheme of RasMol, which only colors common elements
+    Cpk,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Spin {
+    High,
+    Low,
+}
+
+/// The radius of an ion in Å for an oxidation state and coordination number.
+#[derive(Debug, Clone, Copy)]
+pub struct IonicRadius {
+    element: u8,
+    pub charge: i8,
+    pub coordination: u8,
+    /// the spin state, if the radius depends on it
+    pub spin: Option<Spin>,
+    pub radius: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Element {
+    pub number: u8,
+    pub symbol: &'static str,
+    pub name: &'static str,
+    /// the standard atomic weight, or the mass number of the most stable isotope
+    pub mass: f32,
+    pub jmol_color: Color,
+    pub cpk_color: Color,
+    /// in Å
+    pub covalent_radius: f32,
+    /// in Å
+    pub vdw_radius: f32,
+}
+
+impl Element {
+    pub fn color(&self, scheme: ColorScheme) -> Color {
+        match scheme {
+            ColorScheme::Jmol => self.jmol_color,
+            ColorScheme::Cpk => self.cpk_color,
+        }
+    }
+
+    pub fn ionic_radii(&self) -> impl Iterator<Item = &'static IonicRadius> {
+        let number = self.number;
+        IONIC_RADII.iter().filter(move |ion| ion.element == number)
+    }
+
+    /// the ionic radius for an oxidation state and coordination number, where the spin
+    /// state matters and none is given, the high spin radius is used
+    pub fn ionic_radius(&self, charge: i8, coordination: u8, spin: Option<Spin>) -> Option<f32> {
+        let ions: Vec<_> = self
+            .ionic_radii()
+            .filter(|ion| ion.charge == charge && ion.coordination == coordination)
+            .collect();
+        let spin = spin.unwrap_or(Spin::High);
+        ions.iter()
+            .find(|ion| ion.spin == Some(spin))
+            .or_else(|| ions.iter().find(|ion| ion.spin.is_none()))
+            .map(|ion| ion.radius)
+    }
+}
+
+/// the element with the given symbol, like `Fe`
+pub fn get(symbol: &str) -> Option<&'static Element> {
+    ELEMENTS.iter().find(|element| element.symbol == symbol)
+}
+
+pub fn by_number(number: usize) -> Option<&'static Element> {
+    ELEMENTS.get(number.checked_sub(1)?)
+}
+
+/// all elements ordered by atomic number
+pub fn all() -> &'static [Element] {
+    &ELEMENTS
+}
+
+/// the element symbol of a name like `Fe3+`, `Fe1` or `FE`, which starts with the symbol
+pub fn parse_symbol(name: &str) -> String {
+    let letters: Vec<char> = name
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .take(2)
+        .collect();
+    let mut symbol = String::new();
+    if let Some(first) = letters.first() {
+        symbol.push(first.to_ascii_uppercase());
+    }
+    if let Some(second) = letters.get(1) {
+        let second = second.to_ascii_lowercase();
+        if get(&format!("{}{}", symbol, second)).is_some() {
+            symbol.push(second);
+        }
+    }
+    symbol
+}
+
+/// Which radius the atoms are drawn with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AtomRadius {
+    Covalent,
+    VanDerWaals,
+    /// falls back to the covalent radius for ions without data and for the negative radii Shannon
+    /// gives to hydrogen ions
+    Ionic {
+        charge: i8,
+        coordination: u8,
+        spin: Option<Spin>,
+    },
+    Fixed(f32),
+}
+
+/// The element data used for a scene, starting from the built-in data with entries that can be
+/// overridden.
+#[derive(Debug, Clone, Default)]
+pub struct PeriodicTable {
+    pub scheme: ColorScheme,
+    elements: HashMap<String, Element>,
+    colors: HashMap<String, Color>,
+}
+
+impl PeriodicTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_scheme(mut self, scheme: ColorScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// overrides the color of an element regardless of the color scheme
+    pub fn with_color(mut self, symbol: &str, color: Color) -> Self {
+        self.colors.insert(symbol.into(), color);
+        self
+    }
+
+    /// replaces the data of the element with the same symbol
+    pub fn with_element(mut self, element: Element) -> Self {
+        self.elements.insert(element.symbol.into(), element);
+        self
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&Element> {
+        self.elements.get(symbol).or_else(|| get(symbol))
+    }
+
+    /// the data of an element for overriding single values
+    pub fn get_mut(&mut self, symbol: &str) -> Option<&mut Element> {
+        if !self.elements.contains_key(symbol) {
+            self.elements.insert(symbol.into(), *get(symbol)?);
+        }
+        self.elements.get_mut(symbol)
+    }
+
+    pub fn color(&self, symbol: &str) -> Color {
+        match (self.colors.get(symbol), self.get(symbol)) {
+            (Some(color), _) => *color,
+            (None, Some(element)) => element.color(self.scheme),
+            (None, None) => hex_color(UNKNOWN_COLOR),
+        }
+    }
+
+    /// the radius in Å, unknown elements use 1.5 Å
+    pub fn radius(&self, symbol: &str, radius: AtomRadius) -> f32 {
+        let Some(element) = self.get(symbol) else {
+            return match radius {
+                AtomRadius::Fixed(radius) => radius,
+                _ => 1.5,
+            };
+        };
+        match radius {
+            AtomRadius::Covalent => element.covalent_radius,
+            AtomRadius::VanDerWaals => element.vdw_radius,
+            AtomRadius::Ionic {
+                charge,
+                coordination,
+                spin,
+            } => element
+                .ionic_radius(charge, coordination, spin)
+                .filter(|&radius| radius > 0.0)
+                .unwrap_or(element.covalent_radius),
+            AtomRadius::Fixed(radius) => radius,
+        }
+    }
+
+    /// an atom as a ball colored by its element
+    pub fn ball(&self, symbol: &str, position: Point<f32>, radius: f32) -> Object {
+        Object::new(
+            Shape::Ball(Ball { radius }),
+            Isometry::translation(position.x, position.y, position.z),
+            self.color(symbol),
+        )
+    }
+}
+
+const fn hex_color(hex: u32) -> Color {
+    Color(
+        ((hex >> 16) & 0xFF) as f32 / 255.0,
+        ((hex >> 8) & 0xFF) as f32 / 255.0,
+        (hex & 0xFF) as f32 / 255.0,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+const fn element(
+    number: u8,
+    symbol: &'static str,
+    name: &'static str,
+    mass: f32,
+    jmol: u32,
+    cpk: u32,
+    covalent_radius: f32,
+    vdw_radius: f32,
+) -> Element {
+    Element {
+        number,
+        symbol,
+        name,
+        mass,
+        jmol_color: hex_color(jmol),
+        cpk_color: hex_color(cpk),
+        covalent_radius,
+        vdw_radius,
+    }
+}
+
+const fn ion(
+    element: u8,
+    charge: i8,
+    coordination: u8,
+    spin: Option<Spin>,
+    radius: f32,
+) -> IonicRadius {
+    IonicRadius {
+        element,
+        charge,
+        coordination,
+        spin,
+        radius,
+    }
+}
+
+#[rustfmt::skip]
+static ELEMENTS: [Element; 118] = [
+    element(1, "H", "Hydrogen", 1.008, 0xFFFFFF, 0xFFFFFF, 0.31, 1.20),
+    element(2, "He", "Helium", 4.0026, 0xD9FFFF, 0xFFC0CB, 0.28, 1.40),
+    element(3, "Li", "Lithium", 6.94, 0xCC80FF, 0xB22222, 1.28, 1.82),
+    element(4, "Be", "Beryllium", 9.0122, 0xC2FF00, 0xFF1493, 0.96, 1.53),
+    element(5, "B", "Boron", 10.81, 0xFFB5B5, 0x00FF00, 0.84, 1.92),
+    element(6, "C", "Carbon", 12.011, 0x909090, 0xC8C8C8, 0.76, 1.70),
+    element(7, "N", "Nitrogen", 14.007, 0x3050F8, 0x8F8FFF, 0.71, 1.55),
+    element(8, "O", "Oxygen", 15.999, 0xFF0D0D, 0xF00000, 0.66, 1.52),
+    element(9, "F", "Fluorine", 18.998, 0x90E050, 0xDAA520, 0.57, 1.47),
+    element(10, "Ne", "Neon", 20.180, 0xB3E3F5, 0xFF1493, 0.58, 1.54),
+    element(11, "Na", "Sodium", 22.990, 0xAB5CF2, 0x0000FF, 1.66, 2.27),
+    element(12, "Mg", "Magnesium", 24.305, 0x8AFF00, 0x228B22, 1.41, 1.73),
+    element(13, "Al", "Aluminium", 26.982, 0xBFA6A6, 0x808090, 1.21, 1.84),
+    element(14, "Si", "Silicon", 28.085, 0xF0C8A0, 0xDAA520, 1.11, 2.10),
+    element(15, "P", "Phosphorus", 30.974, 0xFF8000, 0xFFA500, 1.07, 1.80),
+    element(16, "S", "Sulfur", 32.06, 0xFFFF30, 0xFFC832, 1.05, 1.80),
+    element(17, "Cl", "Chlorine", 35.45, 0x1FF01F, 0x00FF00, 1.02, 1.75),
+    element(18, "Ar", "Argon", 39.948, 0x80D1E3, 0xFF1493, 1.06, 1.88),
+    element(19, "K", "Potassium", 39.098, 0x8F40D4, 0xFF1493, 2.03, 2.75),
+    element(20, "Ca", "Calcium", 40.078, 0x3DFF00, 0x808090, 1.76, 2.31),
+    element(21, "Sc", "Scandium", 44.956, 0xE6E6E6, 0xFF1493, 1.70, 2.58),
+    element(22, "Ti", "Titanium", 47.867, 0xBFC2C7, 0x808090, 1.60, 2.46),
+    element(23, "V", "Vanadium", 50.942, 0xA6A6AB, 0xFF1493, 1.53, 2.42),
+    element(24, "Cr", "Chromium", 51.996, 0x8A99C7, 0x808090, 1.39, 2.45),
+    element(25, "Mn", "Manganese", 54.938, 0x9C7AC7, 0x808090, 1.39, 2.45),
+    element(26, "Fe", "Iron", 55.845, 0xE06633, 0xFFA500, 1.32, 2.44),
+    element(27, "Co", "Cobalt", 58.933, 0xF090A0, 0xFF1493, 1.26, 2.40),
+    element(28, "Ni", "Nickel", 58.693, 0x50D050, 0xA52A2A, 1.24, 1.63),
+    element(29, "Cu", "Copper", 63.546, 0xC88033, 0xA52A2A, 1.32, 1.40),
+    element(30, "Zn", "Zinc", 65.38, 0x7D80B0, 0xA52A2A, 1.22, 1.39),
+    element(31, "Ga", "Gallium", 69.723, 0xC28F8F, 0xFF1493, 1.22, 1.87),
+    element(32, "Ge", "Germanium", 72.630, 0x668F8F, 0xFF1493, 1.20, 2.11),
+    element(33, "As", "Arsenic", 74.922, 0xBD80E3, 0xFF1493, 1.19, 1.85),
+    element(34, "Se", "Selenium", 78.971, 0xFFA100, 0xFF1493, 1.20, 1.90),
+    element(35, "Br", "Bromine", 79.904, 0xA62929, 0xA52A2A, 1.20, 1.85),
+    element(36, "Kr", "Krypton", 83.798, 0x5CB8D1, 0xFF1493, 1.16, 2.02),
+    element(37, "Rb", "Rubidium", 85.468, 0x702EB0, 0xFF1493, 2.20, 3.03),
+    element(38, "Sr", "Strontium", 87.62, 0x00FF00, 0xFF1493, 1.95, 2.49),
+    element(39, "Y", "Yttrium", 88.906, 0x94FFFF, 0xFF1493, 1.90, 2.75),
+    element(40, "Zr", "Zirconium", 91.224, 0x94E0E0, 0xFF1493, 1.75, 2.52),
+    element(41, "Nb", "Niobium", 92.906, 0x73C2C9, 0xFF1493, 1.64, 2.56),
+    element(42, "Mo", "Molybdenum", 95.95, 0x54B5B5, 0xFF1493, 1.54, 2.45),
+    element(43, "Tc", "Technetium", 98.0, 0x3B9E9E, 0xFF1493, 1.47, 2.44),
+    element(44, "Ru", "Ruthenium", 101.07, 0x248F8F, 0xFF1493, 1.46, 2.46),
+    element(45, "Rh", "Rhodium", 102.91, 0x0A7D8C, 0xFF1493, 1.42, 2.44),
+    element(46, "Pd", "Palladium", 106.42, 0x006985, 0xFF1493, 1.39, 1.63),
+    element(47, "Ag", "Silver", 107.87, 0xC0C0C0, 0x808090, 1.45, 1.72),
+    element(48, "Cd", "Cadmium", 112.41, 0xFFD98F, 0xFF1493, 1.44, 1.58),
+    element(49, "In", "Indium", 114.82, 0xA67573, 0xFF1493, 1.42, 1.93),
+    element(50, "Sn", "Tin", 118.71, 0x668080, 0xFF1493, 1.39, 2.17),
+    element(51, "Sb", "Antimony", 121.76, 0x9E63B5, 0xFF1493, 1.39, 2.06),
+    element(52, "Te", "Tellurium", 127.60, 0xD47A00, 0xFF1493, 1.38, 2.06),
+    element(53, "I", "Iodine", 126.90, 0x940094, 0xA020F0, 1.39, 1.98),
+    element(54, "Xe", "Xenon", 131.29, 0x429EB0, 0xFF1493, 1.40, 2.16),
+    element(55, "Cs", "Caesium", 132.91, 0x57178F, 0xFF1493, 2.44, 3.43),
+    element(56, "Ba", "Barium", 137.33, 0x00C900, 0xFFA500, 2.15, 2.68),
+    element(57, "La", "Lanthanum", 138.91, 0x70D4FF, 0xFF1493, 2.07, 2.98),
+    element(58, "Ce", "Cerium", 140.12, 0xFFFFC7, 0xFF1493, 2.04, 2.88),
+    element(59, "Pr", "Praseodymium", 140.91, 0xD9FFC7, 0xFF1493, 2.03, 2.92),
+    element(60, "Nd", "Neodymium", 144.24, 0xC7FFC7, 0xFF1493, 2.01, 2.95),
+    element(61, "Pm", "Promethium", 145.0, 0xA3FFC7, 0xFF1493, 1.99, 2.90),
+    element(62, "Sm", "Samarium", 150.36, 0x8FFFC7, 0xFF1493, 1.98, 2.90),
+    element(63, "Eu", "Europium", 151.96, 0x61FFC7, 0xFF1493, 1.98, 2.87),
+    element(64, "Gd", "Gadolinium", 157.25, 0x45FFC7, 0xFF1493, 1.96, 2.83),
+    element(65, "Tb", "Terbium", 158.93, 0x30FFC7, 0xFF1493, 1.94, 2.79),
+    element(66, "Dy", "Dysprosium", 162.50, 0x1FFFC7, 0xFF1493, 1.92, 2.87),
+    element(67, "Ho", "Holmium", 164.93, 0x00FF9C, 0xFF1493, 1.92, 2.81),
+    element(68, "Er", "Erbium", 167.26, 0x00E675, 0xFF1493, 1.89, 2.83),
+    element(69, "Tm", "Thulium", 168.93, 0x00D452, 0xFF1493, 1.90, 2.79),
+    element(70, "Yb", "Ytterbium", 173.05, 0x00BF38, 0xFF1493, 1.87, 2.80),
+    element(71, "Lu", "Lutetium", 174.97, 0x00AB24, 0xFF1493, 1.87, 2.74),
+    element(72, "Hf", "Hafnium", 178.49, 0x4DC2FF, 0xFF1493, 1.75, 2.63),
+    element(73, "Ta", "Tantalum", 180.95, 0x4DA6FF, 0xFF1493, 1.70, 2.53),
+    element(74, "W", "Tungsten", 183.84, 0x2194D6, 0xFF1493, 1.62, 2.57),
+    element(75, "Re", "Rhenium", 186.21, 0x267DAB, 0xFF1493, 1.51, 2.49),
+    element(76, "Os", "Osmium", 190.23, 0x266696, 0xFF1493, 1.44, 2.48),
+    element(77, "Ir", "Iridium", 192.22, 0x175487, 0xFF1493, 1.41, 2.41),
+    element(78, "Pt", "Platinum", 195.08, 0xD0D0E0, 0xFF1493, 1.36, 1.75),
+    element(79, "Au", "Gold", 196.97, 0xFFD123, 0xDAA520, 1.36, 1.66),
+    element(80, "Hg", "Mercury", 200.59, 0xB8B8D0, 0xFF1493, 1.32, 1.55),
+    element(81, "Tl", "Thallium", 204.38, 0xA6544D, 0xFF1493, 1.45, 1.96),
+    element(82, "Pb", "Lead", 207.2, 0x575961, 0xFF1493, 1.46, 2.02),
+    element(83, "Bi", "Bismuth", 208.98, 0x9E4FB5, 0xFF1493, 1.48, 2.07),
+    element(84, "Po", "Polonium", 209.0, 0xAB5C00, 0xFF1493, 1.40, 1.97),
+    element(85, "At", "Astatine", 210.0, 0x754F45, 0xFF1493, 1.50, 2.02),
+    element(86, "Rn", "Radon", 222.0, 0x428296, 0xFF1493, 1.50, 2.20),
+    element(87, "Fr", "Francium", 223.0, 0x420066, 0xFF1493, 2.60, 3.48),
+    element(88, "Ra", "Radium", 226.0, 0x007D00, 0xFF1493, 2.21, 2.83),
+    element(89, "Ac", "Actinium", 227.0, 0x70ABFA, 0xFF1493, 2.15, 2.80),
+    element(90, "Th", "Thorium", 232.04, 0x00BAFF, 0xFF1493, 2.06, 2.93),
+    element(91, "Pa", "Protactinium", 231.04, 0x00A1FF, 0xFF1493, 2.00, 2.88),
+    element(92, "U", "Uranium", 238.03, 0x008FFF, 0xFF1493, 1.96, 1.86),
+    element(93, "Np", "Neptunium", 237.0, 0x0080FF, 0xFF1493, 1.90, 2.82),
+    element(94, "Pu", "Plutonium", 244.0, 0x006BFF, 0xFF1493, 1.87, 2.81),
+    element(95, "Am", "Americium", 243.0, 0x545CF2, 0xFF1493, 1.80, 2.83),
+    element(96, "Cm", "Curium", 247.0, 0x785CE3, 0xFF1493, 1.69, 3.05),
+    element(97, "Bk", "Berkelium", 247.0, 0x8A4FE3, 0xFF1493, 1.68, 3.40),
+    element(98, "Cf", "Californium", 251.0, 0xA136D4, 0xFF1493, 1.68, 3.05),
+    element(99, "Es", "Einsteinium", 252.0, 0xB31FD4, 0xFF1493, 1.65, 2.70),
+    element(100, "Fm", "Fermium", 257.0, 0xB31FBA, 0xFF1493, 1.67, 2.00),
+    element(101, "Md", "Mendelevium", 258.0, 0xB30DA6, 0xFF1493, 1.73, 2.00),
+    element(102, "No", "Nobelium", 259.0, 0xBD0D87, 0xFF1493, 1.76, 2.00),
+    element(103, "Lr", "Lawrencium", 266.0, 0xC70066, 0xFF1493, 1.61, 2.00),
+    element(104, "Rf", "Rutherfordium", 267.0, 0xCC0059, 0xFF1493, 1.57, 2.00),
+    element(105, "Db", "Dubnium", 268.0, 0xD1004F, 0xFF1493, 1.49, 2.00),
+    element(106, "Sg", "Seaborgium", 269.0, 0xD90045, 0xFF1493, 1.43, 2.00),
+    element(107, "Bh", "Bohrium", 270.0, 0xE00038, 0xFF1493, 1.41, 2.00),
+    element(108, "Hs", "Hassium", 269.0, 0xE6002E, 0xFF1493, 1.34, 2.00),
+    element(109, "Mt", "Meitnerium", 278.0, 0xEB0026, 0xFF1493, 1.29, 2.00),
+    element(110, "Ds", "Darmstadtium", 281.0, 0xFF1493, 0xFF1493, 1.28, 2.00),
+    element(111, "Rg", "Roentgenium", 282.0, 0xFF1493, 0xFF1493, 1.21, 2.00),
+    element(112, "Cn", "Copernicium", 285.0, 0xFF1493, 0xFF1493, 1.22, 2.00),
+    element(113, "Nh", "Nihonium", 286.0, 0xFF1493, 0xFF1493, 1.36, 2.00),
+    element(114, "Fl", "Flerovium", 289.0, 0xFF1493, 0xFF1493, 1.43, 2.00),
+    element(115, "Mc", "Moscovium", 290.0, 0xFF1493, 0xFF1493, 1.62, 2.00),
+    element(116, "Lv", "Livermorium", 293.0, 0xFF1493, 0xFF1493, 1.75, 2.00),
+    element(117, "Ts", "Tennessine", 294.0, 0xFF1493, 0xFF1493, 1.65, 2.00),
+    element(118, "Og", "Oganesson", 294.0, 0xFF1493, 0xFF1493, 1.57, 2.00),
+];
+
+#[rustfmt::skip]
+static IONIC_RADII: [IonicRadius; 485] = [
+    ion(1, 1, 1, None, -0.38),
+    ion(1, 1, 2, None, -0.18),
+    ion(3, 1, 4, None, 0.59),
+    ion(3, 1, 6, None, 0.76),
+    ion(3, 1, 8, None, 0.92),
+    ion(4, 2, 3, None, 0.16),
+    ion(4, 2, 4, None, 0.27),
+    ion(4, 2, 6, None, 0.45),
+    ion(5, 3, 3, None, 0.01),
+    ion(5, 3, 4, None, 0.11),
+    ion(5, 3, 6, None, 0.27),
+    ion(6, 4, 3, None, -0.08),
+    ion(6, 4, 4, None, 0.15),
+    ion(6, 4, 6, None, 0.16),
+    ion(7, -3, 4, None, 1.46),
+    ion(7, 3, 6, None, 0.16),
+    ion(7, 5, 3, None, -0.104),
+    ion(7, 5, 6, None, 0.13),
+    ion(8, -2, 2, None, 1.35),
+    ion(8, -2, 3, None, 1.36),
+    ion(8, -2, 4, None, 1.38),
+    ion(8, -2, 6, None, 1.40),
+    ion(8, -2, 8, None, 1.42),
+    ion(9, -1, 2, None, 1.285),
+    ion(9, -1, 3, None, 1.30),
+    ion(9, -1, 4, None, 1.31),
+    ion(9, -1, 6, None, 1.33),
+    ion(9, 7, 6, None, 0.08),
+    ion(11, 1, 4, None, 0.99),
+    ion(11, 1, 5, None, 1.00),
+    ion(11, 1, 6, None, 1.02),
+    ion(11, 1, 7, None, 1.12),
+    ion(11, 1, 8, None, 1.18),
+    ion(11, 1, 9, None, 1.24),
+    ion(11, 1, 12, None, 1.39),
+    ion(12, 2, 4, None, 0.57),
+    ion(12, 2, 5, None, 0.66),
+    ion(12, 2, 6, None, 0.72),
+    ion(12, 2, 8, None, 0.89),
+    ion(13, 3, 4, None, 0.39),
+    ion(13, 3, 5, None, 0.48),
+    ion(13, 3, 6, None, 0.535),
+    ion(14, 4, 4, None, 0.26),
+    ion(14, 4, 6, None, 0.40),
+    ion(15, 3, 6, None, 0.44),
+    ion(15, 5, 4, None, 0.17),
+    ion(15, 5, 5, None, 0.29),
+    ion(15, 5, 6, None, 0.38),
+    ion(16, -2, 6, None, 1.84),
+    ion(16, 4, 6, None, 0.37),
+    ion(16, 6, 4, None, 0.12),
+    ion(16, 6, 6, None, 0.29),
+    ion(17, -1, 6, None, 1.81),
+    ion(17, 5, 3, None, 0.12),
+    ion(17, 7, 4, None, 0.08),
+    ion(17, 7, 6, None, 0.27),
+    ion(19, 1, 4, None, 1.37),
+    ion(19, 1, 6, None, 1.38),
+    ion(19, 1, 7, None, 1.46),
+    ion(19, 1, 8, None, 1.51),
+    ion(19, 1, 9, None, 1.55),
+    ion(19, 1, 10, None, 1.59),
+    ion(19, 1, 12, None, 1.64),
+    ion(20, 2, 6, None, 1.00),
+    ion(20, 2, 7, None, 1.06),
+    ion(20, 2, 8, None, 1.12),
+    ion(20, 2, 9, None, 1.18),
+    ion(20, 2, 10, None, 1.23),
+    ion(20, 2, 12, None, 1.34),
+    ion(21, 3, 6, None, 0.745),
+    ion(21, 3, 8, None, 0.87),
+    ion(22, 2, 6, None, 0.86),
+    ion(22, 3, 6, None, 0.67),
+    ion(22, 4, 4, None, 0.42),
+    ion(22, 4, 5, None, 0.51),
+    ion(22, 4, 6, None, 0.605),
+    ion(22, 4, 8, None, 0.74),
+    ion(23, 2, 6, None, 0.79),
+    ion(23, 3, 6, None, 0.64),
+    ion(23, 4, 5, None, 0.53),
+    ion(23, 4, 6, None, 0.58),
+    ion(23, 4, 8, None, 0.72),
+    ion(23, 5, 4, None, 0.355),
+    ion(23, 5, 5, None, 0.46),
+    ion(23, 5, 6, None, 0.54),
+    ion(24, 2, 6, Some(Spin::Low), 0.73),
+    ion(24, 2, 6, Some(Spin::High), 0.80),
+    ion(24, 3, 6, None, 0.615),
+    ion(24, 4, 4, None, 0.41),
+    ion(24, 4, 6, None, 0.55),
+    ion(24, 5, 4, None, 0.345),
+    ion(24, 5, 6, None, 0.49),
+    ion(24, 6, 4, None, 0.26),
+    ion(24, 6, 6, None, 0.44),
+    ion(25, 2, 4, Some(Spin::High), 0.66),
+    ion(25, 2, 5, Some(Spin::High), 0.75),
+    ion(25, 2, 6, Some(Spin::Low), 0.67),
+    ion(25, 2, 6, Some(Spin::High), 0.83),
+    ion(25, 2, 7, Some(Spin::High), 0.90),
+    ion(25, 2, 8, None, 0.96),
+    ion(25, 3, 5, None, 0.58),
+    ion(25, 3, 6, Some(Spin::Low), 0.58),
+    ion(25, 3, 6, Some(Spin::High), 0.645),
+    ion(25, 4, 4, None, 0.39),
+    ion(25, 4, 6, None, 0.53),
+    ion(25, 5, 4, None, 0.33),
+    ion(25, 6, 4, None, 0.255),
+    ion(25, 7, 4, None, 0.25),
+    ion(25, 7, 6, None, 0.46),
+    ion(26, 2, 4, Some(Spin::High), 0.63),
+    ion(26, 2, 6, Some(Spin::Low), 0.61),
+    ion(26, 2, 6, Some(Spin::High), 0.78),
+    ion(26, 2, 8, Some(Spin::High), 0.92),
+    ion(26, 3, 4, Some(Spin::High), 0.49),
+    ion(26, 3, 5, None, 0.58),
+    ion(26, 3, 6, Some(Spin::Low), 0.55),
+    ion(26, 3, 6, Some(Spin::High), 0.645),
+    ion(26, 3, 8, Some(Spin::High), 0.78),
+    ion(26, 4, 6, None, 0.585),
+    ion(26, 6, 4, None, 0.25),
+    ion(27, 2, 4, Some(Spin::High), 0.58),
+    ion(27, 2, 5, None, 0.67),
+    ion(27, 2, 6, Some(Spin::Low), 0.65),
+    ion(27, 2, 6, Some(Spin::High), 0.745),
+    ion(27, 2, 8, None, 0.90),
+    ion(27, 3, 6, Some(Spin::Low), 0.545),
+    ion(27, 3, 6, Some(Spin::High), 0.61),
+    ion(27, 4, 4, None, 0.40),
+    ion(27, 4, 6, Some(Spin::High), 0.53),
+    ion(28, 2, 4, None, 0.55),
+    ion(28, 2, 5, None, 0.63),
+    ion(28, 2, 6, None, 0.69),
+    ion(28, 3, 6, Some(Spin::Low), 0.56),
+    ion(28, 3, 6, Some(Spin::High), 0.60),
+    ion(28, 4, 6, Some(Spin::Low), 0.48),
+    ion(29, 1, 2, None, 0.46),
+    ion(29, 1, 4, None, 0.60),
+    ion(29, 1, 6, None, 0.77),
+    ion(29, 2, 4, None, 0.57),
+    ion(29, 2, 5, None, 0.65),
+    ion(29, 2, 6, None, 0.73),
+    ion(29, 3, 6, Some(Spin::Low), 0.54),
+    ion(30, 2, 4, None, 0.60),
+    ion(30, 2, 5, None, 0.68),
+    ion(30, 2, 6, None, 0.74),
+    ion(30, 2, 8, None, 0.90),
+    ion(31, 3, 4, None, 0.47),
+    ion(31, 3, 5, None, 0.55),
+    ion(31, 3, 6, None, 0.62),
+    ion(32, 2, 6, None, 0.73),
+    ion(32, 4, 4, None, 0.39),
+    ion(32, 4, 6, None, 0.53),
+    ion(33, 3, 6, None, 0.58),
+    ion(33, 5, 4, None, 0.335),
+    ion(33, 5, 6, None, 0.46),
+    ion(34, -2, 6, None, 1.98),
+    ion(34, 4, 6, None, 0.50),
+    ion(34, 6, 4, None, 0.28),
+    ion(34, 6, 6, None, 0.42),
+    ion(35, -1, 6, None, 1.96),
+    ion(35, 7, 4, None, 0.25),
+    ion(35, 7, 6, None, 0.39),
+    ion(37, 1, 6, None, 1.52),
+    ion(37, 1, 7, None, 1.56),
+    ion(37, 1, 8, None, 1.61),
+    ion(37, 1, 9, None, 1.63),
+    ion(37, 1, 10, None, 1.66),
+    ion(37, 1, 11, None, 1.69),
+    ion(37, 1, 12, None, 1.72),
+    ion(37, 1, 14, None, 1.83),
+    ion(38, 2, 6, None, 1.18),
+    ion(38, 2, 7, None, 1.21),
+    ion(38, 2, 8, None, 1.26),
+    ion(38, 2, 9, None, 1.31),
+    ion(38, 2, 10, None, 1.36),
+    ion(38, 2, 12, None, 1.44),
+    ion(39, 3, 6, None, 0.90),
+    ion(39, 3, 7, None, 0.96),
+    ion(39, 3, 8, None, 1.019),
+    ion(39, 3, 9, None, 1.075),
+    ion(40, 4, 4, None, 0.59),
+    ion(40, 4, 5, None, 0.66),
+    ion(40, 4, 6, None, 0.72),
+    ion(40, 4, 7, None, 0.78),
+    ion(40, 4, 8, None, 0.84),
+    ion(40, 4, 9, None, 0.89),
+    ion(41, 3, 6, None, 0.72),
+    ion(41, 4, 6, None, 0.68),
+    ion(41, 4, 8, None, 0.79),
+    ion(41, 5, 4, None, 0.48),
+    ion(41, 5, 6, None, 0.64),
+    ion(41, 5, 7, None, 0.69),
+    ion(41, 5, 8, None, 0.74),
+    ion(42, 3, 6, None, 0.69),
+    ion(42, 4, 6, None, 0.65),
+    ion(42, 5, 4, None, 0.46),
+    ion(42, 5, 6, None, 0.61),
+    ion(42, 6, 4, None, 0.41),
+    ion(42, 6, 5, None, 0.50),
+    ion(42, 6, 6, None, 0.59),
+    ion(42, 6, 7, None, 0.73),
+    ion(43, 4, 6, None, 0.645),
+    ion(43, 5, 6, None, 0.60),
+    ion(43, 7, 4, None, 0.37),
+    ion(43, 7, 6, None, 0.56),
+    ion(44, 3, 6, None, 0.68),
+    ion(44, 4, 6, None, 0.62),
+    ion(44, 5, 6, None, 0.565),
+    ion(44, 7, 4, None, 0.38),
+    ion(44, 8, 4, None, 0.36),
+    ion(45, 3, 6, None, 0.665),
+    ion(45, 4, 6, None, 0.60),
+    ion(45, 5, 6, None, 0.55),
+    ion(46, 1, 2, None, 0.59),
+    ion(46, 2, 4, None, 0.64),
+    ion(46, 2, 6, None, 0.86),
+    ion(46, 3, 6, None, 0.76),
+    ion(46, 4, 6, None, 0.615),
+    ion(47, 1, 2, None, 0.67),
+    ion(47, 1, 4, None, 1.00),
+    ion(47, 1, 5, None, 1.09),
+    ion(47, 1, 6, None, 1.15),
+    ion(47, 1, 7, None, 1.22),
+    ion(47, 1, 8, None, 1.28),
+    ion(47, 2, 4, None, 0.79),
+    ion(47, 2, 6, None, 0.94),
+    ion(47, 3, 4, None, 0.67),
+    ion(47, 3, 6, None, 0.75),
+    ion(48, 2, 4, None, 0.78),
+    ion(48, 2, 5, None, 0.87),
+    ion(48, 2, 6, None, 0.95),
+    ion(48, 2, 7, None, 1.03),
+    ion(48, 2, 8, None, 1.10),
+    ion(48, 2, 12, None, 1.31),
+    ion(49, 3, 4, None, 0.62),
+    ion(49, 3, 6, None, 0.80),
+    ion(49, 3, 8, None, 0.92),
+    ion(50, 4, 4, None, 0.55),
+    ion(50, 4, 5, None, 0.62),
+    ion(50, 4, 6, None, 0.69),
+    ion(50, 4, 7, None, 0.75),
+    ion(50, 4, 8, None, 0.81),
+    ion(51, 3, 4, None, 0.76),
+    ion(51, 3, 5, None, 0.80),
+    ion(51, 3, 6, None, 0.76),
+    ion(51, 5, 6, None, 0.60),
+    ion(52, -2, 6, None, 2.21),
+    ion(52, 4, 3, None, 0.52),
+    ion(52, 4, 4, None, 0.66),
+    ion(52, 4, 6, None, 0.97),
+    ion(52, 6, 4, None, 0.43),
+    ion(52, 6, 6, None, 0.56),
+    ion(53, -1, 6, None, 2.20),
+    ion(53, 5, 3, None, 0.44),
+    ion(53, 5, 6, None, 0.95),
+    ion(53, 7, 4, None, 0.42),
+    ion(53, 7, 6, None, 0.53),
+    ion(54, 8, 4, None, 0.40),
+    ion(54, 8, 6, None, 0.48),
+    ion(55, 1, 6, None, 1.67),
+    ion(55, 1, 8, None, 1.74),
+    ion(55, 1, 9, None, 1.78),
+    ion(55, 1, 10, None, 1.81),
+    ion(55, 1, 11, None, 1.85),
+    ion(55, 1, 12, None, 1.88),
+    ion(56, 2, 6, None, 1.35),
+    ion(56, 2, 7, None, 1.38),
+    ion(56, 2, 8, None, 1.42),
+    ion(56, 2, 9, None, 1.47),
+    ion(56, 2, 10, None, 1.52),
+    ion(56, 2, 11, None, 1.57),
+    ion(56, 2, 12, None, 1.61),
+    ion(57, 3, 6, None, 1.032),
+    ion(57, 3, 7, None, 1.10),
+    ion(57, 3, 8, None, 1.16),
+    ion(57, 3, 9, None, 1.216),
+    ion(57, 3, 10, None, 1.27),
+    ion(57, 3, 12, None, 1.36),
+    ion(58, 3, 6, None, 1.01),
+    ion(58, 3, 7, None, 1.07),
+    ion(58, 3, 8, None, 1.143),
+    ion(58, 3, 9, None, 1.196),
+    ion(58, 3, 10, None, 1.25),
+    ion(58, 3, 12, None, 1.34),
+    ion(58, 4, 6, None, 0.87),
+    ion(58, 4, 8, None, 0.97),
+    ion(58, 4, 10, None, 1.07),
+    ion(58, 4, 12, None, 1.14),
+    ion(59, 3, 6, None, 0.99),
+    ion(59, 3, 8, None, 1.126),
+    ion(59, 3, 9, None, 1.179),
+    ion(59, 4, 6, None, 0.85),
+    ion(59, 4, 8, None, 0.96),
+    ion(60, 2, 8, None, 1.29),
+    ion(60, 2, 9, None, 1.35),
+    ion(60, 3, 6, None, 0.983),
+    ion(60, 3, 8, None, 1.109),
+    ion(60, 3, 9, None, 1.163),
+    ion(60, 3, 12, None, 1.27),
+    ion(61, 3, 6, None, 0.97),
+    ion(61, 3, 8, None, 1.093),
+    ion(61, 3, 9, None, 1.144),
+    ion(62, 2, 7, None, 1.22),
+    ion(62, 2, 8, None, 1.27),
+    ion(62, 2, 9, None, 1.32),
+    ion(62, 3, 6, None, 0.958),
+    ion(62, 3, 7, None, 1.02),
+    ion(62, 3, 8, None, 1.079),
+    ion(62, 3, 9, None, 1.132),
+    ion(62, 3, 12, None, 1.24),
+    ion(63, 2, 6, None, 1.17),
+    ion(63, 2, 7, None, 1.20),
+    ion(63, 2, 8, None, 1.25),
+    ion(63, 2, 9, None, 1.30),
+    ion(63, 2, 10, None, 1.35),
+    ion(63, 3, 6, None, 0.947),
+    ion(63, 3, 7, None, 1.01),
+    ion(63, 3, 8, None, 1.066),
+    ion(63, 3, 9, None, 1.12),
+    ion(64, 3, 6, None, 0.938),
+    ion(64, 3, 7, None, 1.00),
+    ion(64, 3, 8, None, 1.053),
+    ion(64, 3, 9, None, 1.107),
+    ion(65, 3, 6, None, 0.923),
+    ion(65, 3, 7, None, 0.98),
+    ion(65, 3, 8, None, 1.04),
+    ion(65, 3, 9, None, 1.095),
+    ion(65, 4, 6, None, 0.76),
+    ion(65, 4, 8, None, 0.88),
+    ion(66, 2, 6, None, 1.07),
+    ion(66, 2, 7, None, 1.13),
+    ion(66, 2, 8, None, 1.19),
+    ion(66, 3, 6, None, 0.912),
+    ion(66, 3, 7, None, 0.97),
+    ion(66, 3, 8, None, 1.027),
+    ion(66, 3, 9, None, 1.083),
+    ion(67, 3, 6, None, 0.901),
+    ion(67, 3, 8, None, 1.015),
+    ion(67, 3, 9, None, 1.072),
+    ion(67, 3, 10, None, 1.12),
+    ion(68, 3, 6, None, 0.89),
+    ion(68, 3, 7, None, 0.945),
+    ion(68, 3, 8, None, 1.004),
+    ion(68, 3, 9, None, 1.062),
+    ion(69, 2, 6, None, 1.03),
+    ion(69, 2, 7, None, 1.09),
+    ion(69, 3, 6, None, 0.88),
+    ion(69, 3, 8, None, 0.994),
+    ion(69, 3, 9, None, 1.052),
+    ion(70, 2, 6, None, 1.02),
+    ion(70, 2, 7, None, 1.08),
+    ion(70, 2, 8, None, 1.14),
+    ion(70, 3, 6, None, 0.868),
+    ion(70, 3, 7, None, 0.925),
+    ion(70, 3, 8, None, 0.985),
+    ion(70, 3, 9, None, 1.042),
+    ion(71, 3, 6, None, 0.861),
+    ion(71, 3, 8, None, 0.977),
+    ion(71, 3, 9, None, 1.032),
+    ion(72, 4, 4, None, 0.58),
+    ion(72, 4, 6, None, 0.71),
+    ion(72, 4, 7, None, 0.76),
+    ion(72, 4, 8, None, 0.83),
+    ion(73, 3, 6, None, 0.72),
+    ion(73, 4, 6, None, 0.68),
+    ion(73, 5, 6, None, 0.64),
+    ion(73, 5, 7, None, 0.69),
+    ion(73, 5, 8, None, 0.74),
+    ion(74, 4, 6, None, 0.66),
+    ion(74, 5, 6, None, 0.62),
+    ion(74, 6, 4, None, 0.42),
+    ion(74, 6, 5, None, 0.51),
+    ion(74, 6, 6, None, 0.60),
+    ion(75, 4, 6, None, 0.63),
+    ion(75, 5, 6, None, 0.58),
+    ion(75, 6, 6, None, 0.55),
+    ion(75, 7, 4, None, 0.38),
+    ion(75, 7, 6, None, 0.53),
+    ion(76, 4, 6, None, 0.63),
+    ion(76, 5, 6, None, 0.575),
+    ion(76, 6, 5, None, 0.49),
+    ion(76, 6, 6, None, 0.545),
+    ion(76, 7, 6, None, 0.525),
+    ion(76, 8, 4, None, 0.39),
+    ion(77, 3, 6, None, 0.68),
+    ion(77, 4, 6, None, 0.625),
+    ion(77, 5, 6, None, 0.57),
+    ion(78, 2, 4, None, 0.60),
+    ion(78, 2, 6, None, 0.80),
+    ion(78, 4, 6, None, 0.625),
+    ion(78, 5, 6, None, 0.57),
+    ion(79, 1, 6, None, 1.37),
+    ion(79, 3, 4, None, 0.68),
+    ion(79, 3, 6, None, 0.85),
+    ion(79, 5, 6, None, 0.57),
+    ion(80, 1, 3, None, 0.97),
+    ion(80, 1, 6, None, 1.19),
+    ion(80, 2, 2, None, 0.69),
+    ion(80, 2, 4, None, 0.96),
+    ion(80, 2, 6, None, 1.02),
+    ion(80, 2, 8, None, 1.14),
+    ion(81, 1, 6, None, 1.50),
+    ion(81, 1, 8, None, 1.59),
+    ion(81, 1, 12, None, 1.70),
+    ion(81, 3, 4, None, 0.75),
+    ion(81, 3, 6, None, 0.885),
+    ion(81, 3, 8, None, 0.98),
+    ion(82, 2, 4, None, 0.98),
+    ion(82, 2, 6, None, 1.19),
+    ion(82, 2, 7, None, 1.23),
+    ion(82, 2, 8, None, 1.29),
+    ion(82, 2, 9, None, 1.35),
+    ion(82, 2, 10, None, 1.40),
+    ion(82, 2, 11, None, 1.45),
+    ion(82, 2, 12, None, 1.49),
+    ion(82, 4, 4, None, 0.65),
+    ion(82, 4, 5, None, 0.73),
+    ion(82, 4, 6, None, 0.775),
+    ion(82, 4, 8, None, 0.94),
+    ion(83, 3, 5, None, 0.96),
+    ion(83, 3, 6, None, 1.03),
+    ion(83, 3, 8, None, 1.17),
+    ion(83, 5, 6, None, 0.76),
+    ion(84, 4, 6, None, 0.94),
+    ion(84, 4, 8, None, 1.08),
+    ion(84, 6, 6, None, 0.67),
+    ion(85, 7, 6, None, 0.62),
+    ion(87, 1, 6, None, 1.80),
+    ion(88, 2, 8, None, 1.48),
+    ion(88, 2, 12, None, 1.70),
+    ion(89, 3, 6, None, 1.12),
+    ion(90, 4, 6, None, 0.94),
+    ion(90, 4, 8, None, 1.05),
+    ion(90, 4, 9, None, 1.09),
+    ion(90, 4, 10, None, 1.13),
+    ion(90, 4, 11, None, 1.18),
+    ion(90, 4, 12, None, 1.21),
+    ion(91, 3, 6, None, 1.04),
+    ion(91, 4, 6, None, 0.90),
+    ion(91, 4, 8, None, 1.01),
+    ion(91, 5, 6, None, 0.78),
+    ion(91, 5, 8, None, 0.91),
+    ion(91, 5, 9, None, 0.95),
+    ion(92, 3, 6, None, 1.025),
+    ion(92, 4, 6, None, 0.89),
+    ion(92, 4, 7, None, 0.95),
+    ion(92, 4, 8, None, 1.00),
+    ion(92, 4, 9, None, 1.05),
+    ion(92, 4, 12, None, 1.17),
+    ion(92, 5, 6, None, 0.76),
+    ion(92, 5, 7, None, 0.84),
+    ion(92, 6, 2, None, 0.45),
+    ion(92, 6, 4, None, 0.52),
+    ion(92, 6, 6, None, 0.73),
+    ion(92, 6, 7, None, 0.81),
+    ion(92, 6, 8, None, 0.86),
+    ion(93, 2, 6, None, 1.10),
+    ion(93, 3, 6, None, 1.01),
+    ion(93, 4, 6, None, 0.87),
+    ion(93, 4, 8, None, 0.98),
+    ion(93, 5, 6, None, 0.75),
+    ion(93, 6, 6, None, 0.72),
+    ion(93, 7, 6, None, 0.71),
+    ion(94, 3, 6, None, 1.00),
+    ion(94, 4, 6, None, 0.86),
+    ion(94, 4, 8, None, 0.96),
+    ion(94, 5, 6, None, 0.74),
+    ion(94, 6, 6, None, 0.71),
+    ion(95, 2, 7, None, 1.21),
+    ion(95, 2, 8, None, 1.26),
+    ion(95, 2, 9, None, 1.31),
+    ion(95, 3, 6, None, 0.975),
+    ion(95, 3, 8, None, 1.09),
+    ion(95, 4, 6, None, 0.85),
+    ion(95, 4, 8, None, 0.95),
+    ion(96, 3, 6, None, 0.97),
+    ion(96, 4, 6, None, 0.85),
+    ion(96, 4, 8, None, 0.95),
+    ion(97, 3, 6, None, 0.96),
+    ion(97, 4, 6, None, 0.83),
+    ion(97, 4, 8, None, 0.93),
+    ion(98, 3, 6, None, 0.95),
+    ion(98, 4, 6, None, 0.821),
+    ion(98, 4, 8, None, 0.92),
+    ion(102, 2, 6, None, 1.1),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ionic_radii_are_positive() {
+        let table = PeriodicTable::new();
+        let ionic = |symbol, charge, coordination| {
+            table.radius(
+                symbol,
+                AtomRadius::Ionic {
+                    charge,
+                    coordination,
+                    spin: None,
+                },
+            )
+        };
+        let hydrogen = table.radius("H", AtomRadius::Covalent);
+        assert_eq!(ionic("H", 1, 1), hydrogen);
+        assert_eq!(ionic("H", 1, 2), hydrogen);
+        assert_eq!(ionic("Li", 1, 4), 0.59);
+        // no data for this coordination
+        assert_eq!(ionic("Li", 1, 12), table.radius("Li", AtomRadius::Covalent));
+    }
+}
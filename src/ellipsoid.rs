@@ -0,0 +1,158 @@
+//! Ellipsoids, used to draw the anisotropic displacement of atoms as thermal ellipsoids.
+
+use std::f64::consts::PI;
+
+use nalgebra::{Matrix3, Rotation3, UnitQuaternion};
+use parry3d::{
+    math::{Isometry, Point, Vector},
+    query::{Ray, RayIntersection},
+    shape::FeatureId,
+};
+
+/// An ellipsoid with the semi-axes `radii` along the local axes.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ellipsoid {
+    pub radii: Vector<f32>,
+    /// cuts away the octant containing this local direction, like in ORTEP drawings
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub octant_cut: Option<Vector<f32>>,
+}
+
+impl Ellipsoid {
+    pub fn new(radii: Vector<f32>) -> Self {
+        Self {
+            radii,
+            octant_cut: None,
+        }
+    }
+
+    /// the ellipsoid containing the displaced atom with the given probability and the rotation
+    /// from its local axes to the principal axes of the displacement tensor `u` in Å²,
+    /// `None` if `u` is not positive definite
+    pub fn from_displacement(
+        u: &Matrix3<f32>,
+        probability: f32,
+    ) -> Option<(Self, UnitQuaternion<f32>)> {
+        let eigen = u.symmetric_eigen();
+        if eigen.eigenvalues.iter().any(|&value| value <= 0.0) {
+            return None;
+        }
+        let mut axes = eigen.eigenvectors;
+        // the eigenvectors may form a left handed basis
+        if axes.determinant() < 0.0 {
+            axes.set_column(2, &-axes.column(2));
+        }
+        let rotation =
+            UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix_unchecked(axes));
+        let radii = eigen.eigenvalues.map(f32::sqrt) * probability_radius(probability);
+        Some((Self::new(radii), rotation))
+    }
+
+    /// the signs of the coordinates in the cut octant
+    fn octant(&self) -> Option<Vector<f32>> {
+        self.octant_cut
+            .map(|direction| direction.map(|x| 1f32.copysign(x)))
+    }
+
+    fn in_cut(&self, point: &Point<f32>) -> bool {
+        self.octant()
+            .is_some_and(|signs| point.coords.component_mul(&signs).min() > 0.0)
+    }
+
+    pub fn cast_ray(
+        &self,
+        isometry: &Isometry<f32>,
+        ray: &Ray,
+        solid: bool,
+    ) -> Option<RayIntersection> {
+        let ray = ray.inverse_transform_by(isometry);
+        // in the space where the ellipsoid is a unit ball
+        let origin = ray.origin.coords.component_div(&self.radii);
+        let dir = ray.dir.component_div(&self.radii);
+        let a = dir.norm_squared();
+        let b = origin.dot(&dir);
+        let c = origin.norm_squared() - 1.0;
+        let discriminant = b * b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        if solid && c <= 0.0 && !self.in_cut(&ray.origin) {
+            return Some(RayIntersection::new(
+                0.0,
+                Vector::zeros(),
+                FeatureId::Unknown,
+            ));
+        }
+        let (enter, exit) = (
+            (-b - discriminant.sqrt()) / a,
+            (-b + discriminant.sqrt()) / a,
+        );
+
+        let surface = |t: f32| {
+            let point = ray.point_at(t);
+            (!self.in_cut(&point)).then(|| {
+                (
+                    t,
+                    point
+                        .coords
+                        .component_div(&self.radii.component_mul(&self.radii)),
+                )
+            })
+        };
+        let mut hits = [surface(enter), surface(exit), None, None, None];
+        if let Some(signs) = self.octant() {
+            // the faces of the cut lie in the coordinate planes, bounded by the other two axes
+            for axis in 0..3 {
+                if ray.dir[axis] == 0.0 {
+                    continue;
+                }
+                let t = -ray.origin[axis] / ray.dir[axis];
+                let point = ray.point_at(t);
+                let on_face =
+                    (0..3).all(|other| other == axis || point[other] * signs[other] >= 0.0);
+                if on_face && t >= enter && t <= exit {
+                    hits[2 + axis] = Some((t, Vector::ith(axis, signs[axis])));
+                }
+            }
+        }
+        let (toi, normal) = hits
+            .into_iter()
+            .flatten()
+            .filter(|(t, _)| *t >= 0.0)
+            .min_by(|a, b| a.0.total_cmp(&b.0))?;
+        Some(RayIntersection::new(
+            toi,
+            isometry * normal.normalize(),
+            FeatureId::Unknown,
+        ))
+    }
+}
+
+/// the radius of the sphere containing a trivariate standard normal distribution with the
+/// given probability, 1.5382 for the usual 50 %
+pub fn probability_radius(probability: f32) -> f32 {
+    let probability = probability.clamp(0.0, 0.9999) as f64;
+    // the cumulative chi distribution with three degrees of freedom
+    let cumulative = |r: f64| erf(r / 2f64.sqrt()) - (2.0 / PI).sqrt() * r * (-r * r / 2.0).exp();
+    let (mut low, mut high) = (0.0, 10.0);
+    for _ in 0..60 {
+        let mid = (low + high) / 2.0;
+        if cumulative(mid) < probability {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    ((low + high) / 2.0) as f32
+}
+
+/// the error function, Abramowitz and Stegun 7.1.26
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.327_591_1 * x.abs());
+    let polynomial = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    (1.0 - polynomial * (-x * x).exp()).copysign(x)
+}
@@ -0,0 +1,171 @@
+use std::f32::consts::PI;
+use std::path::{Path, PathBuf};
+
+use image::{ImageResult, Rgb32FImage};
+use parry3d::math::{Rotation, Vector};
+
+use crate::{distribution::Distribution2D, objects::Color, sky::Sky, texture, utils};
+
+/// What a ray sees when it leaves the scene.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
+pub enum Background {
+    Color(Color),
+    Map(EnvironmentMap),
+    Sky(Sky),
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Self::Color(Color::default())
+    }
+}
+
+impl From<Color> for Background {
+    fn from(value: Color) -> Self {
+        Self::Color(value)
+    }
+}
+
+impl From<EnvironmentMap> for Background {
+    fn from(value: EnvironmentMap) -> Self {
+        Self::Map(value)
+    }
+}
+
+impl From<Sky> for Background {
+    fn from(value: Sky) -> Self {
+        Self::Sky(value)
+    }
+}
+
+impl Background {
+    pub fn radiance(&self, direction: &Vector<f32>) -> Color {
+        match self {
+            Background::Color(color) => *color,
+            Background::Map(map) => map.radiance(direction),
+            Background::Sky(sky) => sky.radiance(direction),
+        }
+    }
+
+    /// samples a direction towards the background as `(direction, radiance, pdf)`,
+    /// returns `None` if the background is not worth sampling explicitly
+    pub fn sample(&self) -> Option<(Vector<f32>, Color, f32)> {
+        match self {
+            Background::Color(_) => None,
+            Background::Map(map) => map.sample(),
+            Background::Sky(sky) => sky.sample(),
+        }
+    }
+
+    /// the solid angle density with which `sample` returns `direction`
+    pub fn pdf(&self, direction: &Vector<f32>) -> f32 {
+        match self {
+            Background::Color(_) => 0.0,
+            Background::Map(map) => map.pdf(direction),
+            Background::Sky(sky) => sky.pdf(direction),
+        }
+    }
+}
+
+/// An equirectangular environment map with the zenith along the z axis.
+///
+/// Directions are importance sampled proportional to the luminance of the pixels.
+pub struct EnvironmentMap {
+    image: Rgb32FImage,
+    pub rotation: Rotation<f32>,
+    pub intensity: f32,
+    distribution: Distribution2D,
+    path: Option<PathBuf>,
+}
+
+impl EnvironmentMap {
+    /// panics for an image without pixels, which cannot be sampled
+    pub fn new(image: Rgb32FImage, rotation: Rotation<f32>, intensity: f32) -> Self {
+        assert!(
+            image.width() > 0 && image.height() > 0,
+            "environment maps need at least one pixel"
+        );
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let func: Vec<f32> = image
+            .enumerate_pixels()
+            .map(|(_, y, pixel)| {
+                let sin_theta = ((y as f32 + 0.5) / height as f32 * PI).sin();
+                let [r, g, b] = pixel.0;
+                Color(r, g, b).luminance().max(0.0) * sin_theta
+            })
+            .collect();
+        let distribution = Distribution2D::new(&func, width, height);
+        Self {
+            image,
+            rotation,
+            intensity,
+            distribution,
+            path: None,
+        }
+    }
+
+    /// opens a Radiance `.hdr` or OpenEXR file, or any other format supported by `image`
+    pub fn open(
+        path: impl AsRef<Path>,
+        rotation: Rotation<f32>,
+        intensity: f32,
+    ) -> ImageResult<Self> {
+        Ok(Self {
+            path: Some(path.as_ref().to_path_buf()),
+            ..Self::new(texture::open_image(path)?, rotation, intensity)
+        })
+    }
+
+    /// the file the map was opened from
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn radiance(&self, direction: &Vector<f32>) -> Color {
+        let (u, v) = self.direction_to_uv(direction);
+        let x = ((u * self.image.width() as f32) as u32).min(self.image.width() - 1);
+        let y = ((v * self.image.height() as f32) as u32).min(self.image.height() - 1);
+        let [r, g, b] = self.image.get_pixel(x, y).0;
+        Color(r, g, b).scale(self.intensity)
+    }
+
+    pub fn sample(&self) -> Option<(Vector<f32>, Color, f32)> {
+        let ((u, v), pdf) = self
+            .distribution
+            .sample(utils::random::<f32>(), utils::random::<f32>());
+        let theta = v * PI;
+        let phi = (u - 0.5) * 2.0 * PI;
+        let sin_theta = theta.sin();
+        if pdf == 0.0 || sin_theta <= 0.0 {
+            return None;
+        }
+        let direction =
+            self.rotation * Vector::new(sin_theta * phi.cos(), sin_theta * phi.sin(), theta.cos());
+        Some((
+            direction,
+            self.radiance(&direction),
+            pdf / (2.0 * PI * PI * sin_theta),
+        ))
+    }
+
+    pub fn pdf(&self, direction: &Vector<f32>) -> f32 {
+        let (u, v) = self.direction_to_uv(direction);
+        let sin_theta = (v * PI).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+        self.distribution.pdf(u, v) / (2.0 * PI * PI * sin_theta)
+    }
+
+    fn direction_to_uv(&self, direction: &Vector<f32>) -> (f32, f32) {
+        let local = self
+            .rotation
+            .inverse_transform_vector(direction)
+            .normalize();
+        (
+            (0.5 + local.y.atan2(local.x) / (2.0 * PI)).rem_euclid(1.0),
+            local.z.clamp(-1.0, 1.0).acos() / PI,
+        )
+    }
+}
@@ -0,0 +1,213 @@
+//! Scene graphs with groups of objects and prototypes that are instanced many times.
+//!
+//! Groups are flattened when they are added to a scene, composing the isometries of the nested
+//! groups with the ones of their children, and the named groups are remembered by the range of
+//! objects and instances they became. A prototype is stored once with its own bottom-level
+//! hierarchy, see `accel`, so a supercell costs one instance per unit cell instead of one
+//! object per atom.
+
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    ops::Range,
+    sync::OnceLock,
+};
+
+use parry3d::{bounding_volume::Aabb, math::Isometry};
+
+use crate::{accel::Blas, animation::Animatable, objects::Object};
+
+/// Objects in a local space shared by all instances of the prototype.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Prototype {
+    pub name: String,
+    objects: Vec<Object>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    blas: OnceLock<Blas>,
+}
+
+impl Prototype {
+    pub fn new(name: impl Into<String>, objects: Vec<Object>) -> Self {
+        Self {
+            name: name.into(),
+            objects,
+            blas: OnceLock::new(),
+        }
+    }
+
+    pub fn objects(&self) -> &[Object] {
+        &self.objects
+    }
+
+    /// the bounds of the objects in local space, `None` if one of them is unbounded
+    pub fn aabb(&self) -> Option<Aabb> {
+        self.blas().aabb()
+    }
+
+    /// the hierarchy over the objects, built once for all instances
+    pub fn blas(&self) -> &Blas {
+        self.blas.get_or_init(|| Blas::new(&self.objects))
+    }
+}
+
+/// A prototype placed in the scene, the prototype is referred to by its name.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Instance {
+    pub prototype: String,
+    #[cfg_attr(feature = "serde", serde(with = "crate::scene_file::isometry"))]
+    pub isometry: Isometry<f32>,
+    /// the isometry at the end of the frame interval for instances that move while the
+    /// shutter is open, like `Object::end_isometry`
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "crate::scene_file::optional_isometry"
+        )
+    )]
+    pub end_isometry: Option<Isometry<f32>>,
+}
+
+impl Instance {
+    pub fn new(prototype: impl Into<String>, isometry: Isometry<f32>) -> Self {
+        Self {
+            prototype: prototype.into(),
+            isometry,
+            end_isometry: None,
+        }
+    }
+
+    pub fn with_end_isometry(mut self, isometry: Isometry<f32>) -> Self {
+        self.end_isometry = Some(isometry);
+        self
+    }
+
+    /// the isometry at `time`, a fraction of the frame interval
+    pub fn isometry_at(&self, time: f32) -> Isometry<f32> {
+        match &self.end_isometry {
+            None => self.isometry,
+            Some(end) => Animatable::lerp(&self.isometry, end, time),
+        }
+    }
+}
+
+/// An instance of a prototype the scene does not have, with the name of the prototype.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownPrototype(pub String);
+
+impl Display for UnknownPrototype {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown prototype `{}`", self.0)
+    }
+}
+
+impl Error for UnknownPrototype {}
+
+/// Objects, instances and other groups placed relative to the group.
+pub struct Group {
+    /// groups without a name are not remembered by the scene
+    pub name: String,
+    pub isometry: Isometry<f32>,
+    pub children: Vec<Node>,
+}
+
+/// A child of a group.
+pub enum Node {
+    Object(Box<Object>),
+    Instance(Instance),
+    Group(Group),
+}
+
+/// The objects and instances a named group became in a scene, nested groups are named by the
+/// path of names from the outermost group, separated by slashes.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GroupRange {
+    pub name: String,
+    pub objects: Range<usize>,
+    pub instances: Range<usize>,
+}
+
+impl Group {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            isometry: Isometry::identity(),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn with_isometry(mut self, isometry: Isometry<f32>) -> Self {
+        self.isometry = isometry;
+        self
+    }
+
+    pub fn add_object(mut self, object: Object) -> Self {
+        self.children.push(Node::Object(Box::new(object)));
+        self
+    }
+
+    pub fn add_objects(mut self, objects: Vec<Object>) -> Self {
+        self.children.extend(
+            objects
+                .into_iter()
+                .map(|object| Node::Object(Box::new(object))),
+        );
+        self
+    }
+
+    pub fn add_instance(mut self, prototype: impl Into<String>, isometry: Isometry<f32>) -> Self {
+        self.children
+            .push(Node::Instance(Instance::new(prototype, isometry)));
+        self
+    }
+
+    pub fn add_group(mut self, group: Group) -> Self {
+        self.children.push(Node::Group(group));
+        self
+    }
+
+    /// appends the objects and instances of the group placed by `parent` in world space,
+    /// `path` is the name of the enclosing group
+    pub(crate) fn flatten(
+        self,
+        parent: &Isometry<f32>,
+        path: &str,
+        objects: &mut Vec<Object>,
+        instances: &mut Vec<Instance>,
+        groups: &mut Vec<GroupRange>,
+    ) {
+        let isometry = parent * self.isometry;
+        let name = match (path.is_empty(), self.name.is_empty()) {
+            (_, true) => path.to_string(),
+            (true, false) => self.name.clone(),
+            (false, false) => format!("{}/{}", path, self.name),
+        };
+        let start = (objects.len(), instances.len());
+        for child in self.children {
+            match child {
+                Node::Object(object) => {
+                    let mut object = *object;
+                    object.isometry = isometry * object.isometry;
+                    object.end_isometry = object.end_isometry.map(|end| isometry * end);
+                    objects.push(object);
+                }
+                Node::Instance(mut instance) => {
+                    instance.isometry = isometry * instance.isometry;
+                    instance.end_isometry = instance.end_isometry.map(|end| isometry * end);
+                    instances.push(instance);
+                }
+                Node::Group(group) => group.flatten(&isometry, &name, objects, instances, groups),
+            }
+        }
+        if !self.name.is_empty() {
+            groups.push(GroupRange {
+                name,
+                objects: start.0..objects.len(),
+                instances: start.1..instances.len(),
+            });
+        }
+    }
+}
@@ -0,0 +1,337 @@
+//! Reading LAMMPS dump files written by `dump atom` or `dump custom`.
+//!
+//! The species is taken from the `element` column if there is one and is the atom type
+//! otherwise. Positions may be wrapped, unwrapped or scaled, atoms are sorted by their id.
+
+use parry3d::math::{Point, Vector};
+
+use crate::{
+    crystal::Lattice,
+    structure::{self, Fields, Structure, StructureError},
+};
+
+impl Structure {
+    /// reads the first frame of a LAMMPS dump
+    pub fn from_lammps_dump(source: &str) -> Result<Self, StructureError> {
+        let mut lines = source.lines().enumerate().map(|(i, line)| (i + 1, line));
+        read_frame(&mut lines)?.ok_or(StructureError::Syntax {
+            line: 1,
+            message: "missing the timestep".into(),
+        })
+    }
+}
+
+/// reads the next frame, `None` at the end of the file
+pub(crate) fn read_frame<'a>(
+    lines: &mut impl Iterator<Item = (usize, &'a str)>,
+) -> Result<Option<Structure>, StructureError> {
+    let mut count = None;
+    // the lattice and the corner of the box
+    let mut bounds = None;
+    let mut last = 0;
+    loop {
+        let Some((number, line)) = lines.find(|(_, line)| !line.trim().is_empty()) else {
+            if last == 0 {
+                return Ok(None);
+            }
+            return Err(StructureError::Syntax {
+                line: last + 1,
+                message: "missing the atoms".into(),
+            });
+        };
+        last = number;
+        let item = line
+            .trim()
+            .strip_prefix("ITEM:")
+            .ok_or_else(|| StructureError::Syntax {
+                line: number,
+                message: format!("expected an item, got `{}`", line.trim()),
+            })?
+            .trim();
+        let mut next = |name: &str| {
+            lines
+                .next()
+                .map(|(number, line)| Fields::new(number, line))
+                .ok_or_else(|| StructureError::Syntax {
+                    line: number + 1,
+                    message: format!("missing {}", name),
+                })
+        };
+
+        if item == "NUMBER OF ATOMS" {
+            let mut fields = next("the number of atoms")?;
+            let value = fields.next("the number of atoms")?;
+            count = Some(value.parse().map_err(|_| StructureError::Syntax {
+                line: fields.line(),
+                message: format!("invalid number of atoms `{}`", value),
+            })?);
+        } else if let Some(flags) = item.strip_prefix("BOX BOUNDS") {
+            let mut rows = [[0.0; 3]; 3];
+            for row in &mut rows {
+                let mut fields = next("the box bounds")?;
+                row[0] = fields.number("lower bound")?;
+                row[1] = fields.number("upper bound")?;
+                if flags.contains("xy") {
+                    row[2] = fields.number("tilt factor")?;
+                }
+            }
+            bounds = Some(box_lattice(rows));
+        } else if let Some(columns) = item.strip_prefix("ATOMS") {
+            let count = count.ok_or(StructureError::Syntax {
+                line: number,
+                message: "missing the number of atoms".into(),
+            })?;
+            let columns: Vec<&str> = columns.split_whitespace().collect();
+            return read_atoms(lines, number, &columns, count, bounds).map(Some);
+        } else {
+            // the timestep and other items with a single line
+            next(item)?;
+        }
+    }
+}
+
+/// the lattice and origin of a box given by its bounds and tilt factors, as in the LAMMPS
+/// documentation on triclinic boxes
+fn box_lattice(rows: [[f32; 3]; 3]) -> (Lattice, Point<f32>) {
+    let [[xlo, xhi, xy], [ylo, yhi, xz], [zlo, zhi, yz]] = rows;
+    // the bounds enclose the tilted box
+    let xlo = xlo - [0.0, xy, xz, xy + xz].into_iter().fold(0.0, f32::min);
+    let xhi = xhi - [0.0, xy, xz, xy + xz].into_iter().fold(0.0, f32::max);
+    let ylo = ylo - yz.min(0.0);
+    let yhi = yhi - yz.max(0.0);
+    let lattice = Lattice::new(
+        Vector::new(xhi - xlo, 0.0, 0.0),
+        Vector::new(xy, yhi - ylo, 0.0),
+        Vector::new(xz, yz, zhi - zlo),
+    );
+    (lattice, Point::new(xlo, ylo, zlo))
+}
+
+fn read_atoms<'a>(
+    lines: &mut impl Iterator<Item = (usize, &'a str)>,
+    number: usize,
+    columns: &[&str],
+    count: usize,
+    bounds: Option<(Lattice, Point<f32>)>,
+) -> Result<Structure, StructureError> {
+    let column = |names: [&str; 3]| -> Option<[usize; 3]> {
+        let mut indices = [0; 3];
+        for (index, name) in indices.iter_mut().zip(names) {
+            *index = columns.iter().position(|column| *column == name)?;
+        }
+        Some(indices)
+    };
+    let find = |name: &str| columns.iter().position(|column| *column == name);
+    // wrapped positions are moved by the image flags, unwrapped ones are not
+    let (position, scaled, wrapped) = if let Some(position) = column(["x", "y", "z"]) {
+        (position, false, true)
+    } else if let Some(position) = column(["xu", "yu", "zu"]) {
+        (position, false, false)
+    } else if let Some(position) = column(["xs", "ys", "zs"]) {
+        (position, true, true)
+    } else if let Some(position) = column(["xsu", "ysu", "zsu"]) {
+        (position, true, false)
+    } else {
+        return Err(StructureError::Syntax {
+            line: number,
+            message: "missing the position columns".into(),
+        });
+    };
+    let images = column(["ix", "iy", "iz"]).filter(|_| wrapped);
+    let species = find("element").or(find("type"));
+    let Some(species) = species else {
+        return Err(StructureError::Syntax {
+            line: number,
+            message: "missing the element or type column".into(),
+        });
+    };
+    if scaled && bounds.is_none() {
+        return Err(StructureError::Syntax {
+            line: number,
+            message: "missing the box bounds".into(),
+        });
+    }
+    let id = find("id");
+
+    let mut atoms = Vec::with_capacity(count);
+    let mut last = number;
+    for _ in 0..count {
+        let (number, line) = lines.next().ok_or(StructureError::Syntax {
+            line: last + 1,
+            message: format!("expected {} atoms", count),
+        })?;
+        last = number;
+        let fields = Fields::new(number, line).rest();
+        let field = |index: usize| {
+            fields
+                .get(index)
+                .copied()
+                .ok_or_else(|| StructureError::Syntax {
+                    line: number,
+                    message: format!("missing {}", columns[index]),
+                })
+        };
+        let value = |index: usize| -> Result<f32, StructureError> {
+            let value = field(index)?;
+            value.parse().map_err(|_| StructureError::Syntax {
+                line: number,
+                message: format!("invalid {} `{}`", columns[index], value),
+            })
+        };
+        let mut coordinates = Vector::new(
+            value(position[0])?,
+            value(position[1])?,
+            value(position[2])?,
+        );
+        let image = match images {
+            Some(images) => Vector::new(value(images[0])?, value(images[1])?, value(images[2])?),
+            None => Vector::zeros(),
+        };
+        let point = match bounds {
+            Some((lattice, _)) if scaled => lattice.to_cartesian(&(coordinates + image)),
+            Some((lattice, origin)) => {
+                coordinates -= origin.coords;
+                Point::from(coordinates) + lattice.matrix() * image
+            }
+            None => Point::from(coordinates),
+        };
+        let id = match id {
+            Some(id) => {
+                let value = field(id)?;
+                value.parse().map_err(|_| StructureError::Syntax {
+                    line: number,
+                    message: format!("invalid id `{}`", value),
+                })?
+            }
+            None => atoms.len(),
+        };
+        atoms.push((id, field(species)?, point));
+    }
+    atoms.sort_by_key(|(id, _, _)| *id);
+
+    let has_element = find("element").is_some();
+    Ok(Structure {
+        species: atoms
+            .iter()
+            .map(|(_, species, _)| {
+                if has_element {
+                    structure::species(species)
+                } else {
+                    species.to_string()
+                }
+            })
+            .collect(),
+        positions: atoms.iter().map(|(_, _, position)| *position).collect(),
+        lattice: bounds.map(|(lattice, _)| lattice),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trajectory::Trajectory;
+
+    const TRICLINIC: &str = "\
+ITEM: TIMESTEP
+100
+ITEM: NUMBER OF ATOMS
+2
+ITEM: BOX BOUNDS xy xz yz pp pp pp
+-1.0 12.0 2.0
+0.0 9.5 -1.0
+0.0 6.0 1.5
+ITEM: ATOMS id type xs ys zs
+2 1 0.5 0.5 0.5
+1 2 0.0 0.0 0.0
+";
+
+    #[test]
+    fn reads_triclinic_box() {
+        let structure = Structure::from_lammps_dump(TRICLINIC).unwrap();
+        let [a, b, c] = structure.lattice.unwrap().vectors;
+        assert_eq!(a, Vector::new(10.0, 0.0, 0.0));
+        assert_eq!(b, Vector::new(2.0, 8.0, 0.0));
+        assert_eq!(c, Vector::new(-1.0, 1.5, 6.0));
+    }
+
+    #[test]
+    fn sorts_atoms_by_id() {
+        let structure = Structure::from_lammps_dump(TRICLINIC).unwrap();
+        assert_eq!(structure.species, ["2", "1"]);
+        assert_eq!(structure.positions[0], Point::origin());
+        assert_eq!(structure.positions[1], Point::new(5.5, 4.75, 3.0));
+    }
+
+    #[test]
+    fn box_bounds_enclose_the_tilted_box() {
+        let (lattice, origin) = box_lattice([[-2.0, 10.0, -2.0], [0.0, 8.0, 0.0], [1.0, 7.0, 0.0]]);
+        assert_eq!(lattice.vectors[0], Vector::new(10.0, 0.0, 0.0));
+        assert_eq!(origin, Point::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn moves_wrapped_positions_by_image_flags() {
+        let source = "\
+ITEM: TIMESTEP
+0
+ITEM: NUMBER OF ATOMS
+2
+ITEM: BOX BOUNDS pp pp pp
+-5 5
+-5 5
+0 10
+ITEM: ATOMS id element x y z ix iy iz
+1 O 4 0 1 1 0 0
+2 H 4 0 2 0 0 -1
+";
+        let structure = Structure::from_lammps_dump(source).unwrap();
+        assert_eq!(structure.species, ["O", "H"]);
+        assert_eq!(structure.positions[0], Point::new(19.0, 5.0, 1.0));
+        assert_eq!(structure.positions[1], Point::new(9.0, 5.0, -8.0));
+    }
+
+    #[test]
+    fn reads_unwrapped_positions_without_a_box() {
+        let source = "\
+ITEM: NUMBER OF ATOMS
+1
+ITEM: ATOMS type xu yu zu ix
+3 -1.5 0.5 12.0 4
+";
+        let structure = Structure::from_lammps_dump(source).unwrap();
+        assert!(structure.lattice.is_none());
+        assert_eq!(structure.positions[0], Point::new(-1.5, 0.5, 12.0));
+    }
+
+    #[test]
+    fn names_atom_types_of_every_frame() {
+        let source = format!("{}{}", TRICLINIC, TRICLINIC.replace("100", "200"));
+        let trajectory = Trajectory::from_lammps_dump(&source)
+            .unwrap()
+            .with_type_elements(&["Fe", "O"]);
+        assert_eq!(trajectory.len(), 2);
+        assert_eq!(trajectory.frames[1].species, ["O", "Fe"]);
+    }
+
+    #[test]
+    fn reports_missing_atoms() {
+        let source = TRICLINIC.lines().take(10).collect::<Vec<_>>().join("\n");
+        let Err(StructureError::Syntax { line, message }) = Structure::from_lammps_dump(&source)
+        else {
+            panic!("read a dump with missing atoms");
+        };
+        assert_eq!((line, message.as_str()), (11, "expected 2 atoms"));
+    }
+
+    #[test]
+    fn reports_scaled_positions_without_a_box() {
+        let source = "ITEM: NUMBER OF ATOMS\n1\nITEM: ATOMS id type xs ys zs\n1 1 0 0 0\n";
+        let Err(StructureError::Syntax { line, message }) = Structure::from_lammps_dump(source)
+        else {
+            panic!("read scaled positions without a box");
+        };
+        assert_eq!((line, message.as_str()), (3, "missing the box bounds"));
+        assert!(Structure::from_lammps_dump("").is_err());
+    }
+}
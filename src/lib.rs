@@ -1,3 +1,32 @@
+pub mod accel;
+pub mod animation;
+pub mod bonds;
+pub mod bvh;
 pub mod camera;
+pub mod cell;
+pub mod cif;
+pub mod crystal;
+pub mod defects;
+pub mod distribution;
+pub mod elements;
+pub mod ellipsoid;
+pub mod environment;
+pub mod graph;
+pub mod lammps;
+pub mod light;
+pub mod medium;
 pub mod objects;
+pub mod packet;
+pub mod pdb;
+pub mod polyhedra;
+pub mod poscar;
+pub mod progressive;
+#[cfg(feature = "serde")]
+pub mod scene_file;
+pub mod sky;
+pub mod structure;
+pub mod texture;
+pub mod tiles;
+pub mod trajectory;
 pub mod utils;
+pub mod xyz;
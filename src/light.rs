@@ -0,0 +1,307 @@
+use std::f32::consts::PI;
+
+use parry3d::{
+    math::{Point, Real, Vector},
+    query::Ray,
+};
+
+use crate::{objects::Color, utils};
+
+/// A light source that is sampled directly, in contrast to objects that are light sources.
+///
+/// Point, spot and directional lights are infinitely small and can only be reached by sampling,
+/// area lights are also seen by rays hitting them. Area lights emit from one side only,
+/// rectangles and disks towards their normal and spheres outwards.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
+pub enum Light {
+    /// `intensity` is the radiant intensity in every direction
+    Point {
+        position: Point<f32>,
+        intensity: Color,
+    },
+    /// a point light that only shines into a cone around `direction`,
+    /// the intensity falls off smoothly between `falloff_start` and `cone_angle`,
+    /// both measured from `direction` in radians
+    Spot {
+        position: Point<f32>,
+        direction: Vector<f32>,
+        intensity: Color,
+        cone_angle: f32,
+        falloff_start: f32,
+        /// an optional IES-style profile of the relative intensity, sampled at equal angles
+        /// from `direction` to `cone_angle`
+        profile: Option<Vec<f32>>,
+    },
+    /// light arriving from infinitely far away, travelling along `direction`,
+    /// `irradiance` is measured on a surface facing the light
+    Directional {
+        direction: Vector<f32>,
+        irradiance: Color,
+    },
+    /// a parallelogram spanned by `center ± u ± v`, emitting towards `u × v`
+    Rectangle {
+        center: Point<f32>,
+        u: Vector<f32>,
+        v: Vector<f32>,
+        radiance: Color,
+    },
+    Disk {
+        center: Point<f32>,
+        normal: Vector<f32>,
+        radius: f32,
+        radiance: Color,
+    },
+    Sphere {
+        center: Point<f32>,
+        radius: f32,
+        radiance: Color,
+    },
+}
+
+/// Light arriving at a point from a sampled direction.
+pub struct LightSample {
+    pub direction: Vector<f32>,
+    pub distance: Real,
+    pub radiance: Color,
+    /// the solid angle density of `direction`, one for delta lights
+    pub pdf: f32,
+    /// whether the light can only be reached by sampling it
+    pub is_delta: bool,
+}
+
+impl Light {
+    /// samples the light as seen from `point`
+    pub fn sample(&self, point: &Point<f32>) -> Option<LightSample> {
+        match self {
+            Light::Point {
+                position,
+                intensity,
+            } => delta_sample(point, position, *intensity),
+            Light::Spot {
+                position,
+                direction,
+                intensity,
+                ..
+            } => {
+                let cos_angle = (point - position).normalize().dot(&direction.normalize());
+                delta_sample(
+                    point,
+                    position,
+                    intensity.scale(self.spot_factor(cos_angle)),
+                )
+            }
+            Light::Directional {
+                direction,
+                irradiance,
+            } => Some(LightSample {
+                direction: -direction.normalize(),
+                distance: Real::MAX,
+                radiance: *irradiance,
+                pdf: 1.0,
+                is_delta: true,
+            }),
+            Light::Rectangle { center, u, v, .. } => {
+                let sample = center
+                    + u * (2.0 * utils::random::<f32>() - 1.0)
+                    + v * (2.0 * utils::random::<f32>() - 1.0);
+                self.area_sample(point, &sample)
+            }
+            Light::Disk {
+                center,
+                normal,
+                radius,
+                ..
+            } => {
+                let r = radius * utils::random::<f32>().sqrt();
+                let phi = 2.0 * PI * utils::random::<f32>();
+                let sample = center
+                    + utils::local_frame(normal.normalize())
+                        * Vector::new(r * phi.cos(), r * phi.sin(), 0.0);
+                self.area_sample(point, &sample)
+            }
+            Light::Sphere {
+                center,
+                radius,
+                radiance,
+            } => {
+                let to_center = center - point;
+                let distance = to_center.norm();
+                if distance <= *radius {
+                    return None;
+                }
+                let cos_max = (1.0 - (radius / distance).powi(2)).max(0.0).sqrt();
+                let cos_theta = 1.0 - utils::random::<f32>() * (1.0 - cos_max);
+                let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+                let phi = 2.0 * PI * utils::random::<f32>();
+                let direction = utils::local_frame(to_center / distance)
+                    * Vector::new(phi.cos() * sin_theta, phi.sin() * sin_theta, cos_theta);
+                let (distance, _) = self.intersect(&Ray::new(*point, direction))?;
+                Some(LightSample {
+                    direction,
+                    distance,
+                    radiance: *radiance,
+                    pdf: 1.0 / (2.0 * PI * (1.0 - cos_max)),
+                    is_delta: false,
+                })
+            }
+        }
+    }
+
+    /// the distance along the ray to the light and the radiance seen by the ray,
+    /// `None` for delta lights and if the ray misses the light
+    pub fn intersect(&self, ray: &Ray) -> Option<(Real, Color)> {
+        match self {
+            Light::Point { .. } | Light::Spot { .. } | Light::Directional { .. } => None,
+            Light::Rectangle {
+                center,
+                u,
+                v,
+                radiance,
+            } => {
+                let normal = u.cross(v).normalize();
+                let toi = plane_intersection(ray, center, &normal)?;
+                // the coordinates of the hit along `u` and `v`, which need not be perpendicular
+                let offset = ray.point_at(toi) - center;
+                let (uu, uv, vv) = (u.norm_squared(), u.dot(v), v.norm_squared());
+                let (ou, ov) = (offset.dot(u), offset.dot(v));
+                let det = uu * vv - uv * uv;
+                let s = (ou * vv - ov * uv) / det;
+                let t = (ov * uu - ou * uv) / det;
+                (s.abs() <= 1.0 && t.abs() <= 1.0).then_some((toi, *radiance))
+            }
+            Light::Disk {
+                center,
+                normal,
+                radius,
+                radiance,
+            } => {
+                let toi = plane_intersection(ray, center, &normal.normalize())?;
+                ((ray.point_at(toi) - center).norm() <= *radius).then_some((toi, *radiance))
+            }
+            Light::Sphere {
+                center,
+                radius,
+                radiance,
+            } => {
+                let dir = ray.dir.normalize();
+                let offset = ray.origin - center;
+                let b = offset.dot(&dir);
+                let c = offset.norm_squared() - radius * radius;
+                let discriminant = b * b - c;
+                if c <= 0.0 || discriminant < 0.0 {
+                    return None;
+                }
+                let toi = -b - discriminant.sqrt();
+                (toi > 0.0).then_some((toi / ray.dir.norm(), *radiance))
+            }
+        }
+    }
+
+    /// the solid angle density with which `sample` returns `direction` from `point`
+    pub fn pdf(&self, point: &Point<f32>, direction: &Vector<f32>) -> f32 {
+        let ray = Ray::new(*point, direction.normalize());
+        let Some((toi, _)) = self.intersect(&ray) else {
+            return 0.0;
+        };
+        match self {
+            Light::Point { .. } | Light::Spot { .. } | Light::Directional { .. } => 0.0,
+            Light::Rectangle { u, v, .. } => {
+                let normal = u.cross(v);
+                let area = 4.0 * normal.norm();
+                toi * toi / (area * normal.normalize().dot(&ray.dir).abs())
+            }
+            Light::Disk { normal, radius, .. } => {
+                let area = PI * radius * radius;
+                toi * toi / (area * normal.normalize().dot(&ray.dir).abs())
+            }
+            Light::Sphere { center, radius, .. } => {
+                let distance = (center - point).norm();
+                let cos_max = (1.0 - (radius / distance).powi(2)).max(0.0).sqrt();
+                1.0 / (2.0 * PI * (1.0 - cos_max))
+            }
+        }
+    }
+
+    fn spot_factor(&self, cos_angle: f32) -> f32 {
+        let Light::Spot {
+            cone_angle,
+            falloff_start,
+            profile,
+            ..
+        } = self
+        else {
+            return 1.0;
+        };
+        let angle = cos_angle.clamp(-1.0, 1.0).acos();
+        if angle >= *cone_angle {
+            return 0.0;
+        }
+        let falloff = if angle <= *falloff_start {
+            1.0
+        } else {
+            let t = (cone_angle - angle) / (cone_angle - falloff_start);
+            t * t * (3.0 - 2.0 * t)
+        };
+        let profile = match profile {
+            Some(profile) if profile.len() > 1 => {
+                let x = angle / cone_angle * (profile.len() - 1) as f32;
+                let i = (x as usize).min(profile.len() - 2);
+                let t = x - i as f32;
+                profile[i] * (1.0 - t) + profile[i + 1] * t
+            }
+            Some(profile) if profile.len() == 1 => profile[0],
+            _ => 1.0,
+        };
+        falloff * profile
+    }
+
+    fn area_sample(&self, point: &Point<f32>, sample: &Point<f32>) -> Option<LightSample> {
+        let offset = sample - point;
+        let distance = offset.norm();
+        if distance <= 0.0 {
+            return None;
+        }
+        let direction = offset / distance;
+        let (radiance, pdf) = match self.intersect(&Ray::new(*point, direction)) {
+            Some((_, radiance)) => (radiance, self.pdf(point, &direction)),
+            None => return None,
+        };
+        (pdf > 0.0 && pdf.is_finite()).then_some(LightSample {
+            direction,
+            distance,
+            radiance,
+            pdf,
+            is_delta: false,
+        })
+    }
+}
+
+fn delta_sample(
+    point: &Point<f32>,
+    position: &Point<f32>,
+    intensity: Color,
+) -> Option<LightSample> {
+    let offset = position - point;
+    let distance = offset.norm();
+    if distance <= 0.0 {
+        return None;
+    }
+    Some(LightSample {
+        direction: offset / distance,
+        distance,
+        radiance: intensity.scale(1.0 / (distance * distance)),
+        pdf: 1.0,
+        is_delta: true,
+    })
+}
+
+/// intersects the ray with the front side of a plane
+fn plane_intersection(ray: &Ray, center: &Point<f32>, normal: &Vector<f32>) -> Option<Real> {
+    let cos = ray.dir.dot(normal);
+    if cos >= 0.0 {
+        return None;
+    }
+    let toi = (center - ray.origin).dot(normal) / cos;
+    (toi > 0.0).then_some(toi)
+}
@@ -0,0 +1,307 @@
+use std::f32::consts::PI;
+
+use parry3d::{
+    bounding_volume::Aabb,
+    math::{Point, Real, Vector},
+    query::Ray,
+};
+
+use crate::{objects::Color, utils};
+
+/// An absorbing and scattering participating medium.
+///
+/// `sigma_a` and `sigma_s` are the absorption and scattering coefficients per unit length,
+/// `color` tints the scattered light and `g` is the Henyey–Greenstein asymmetry parameter.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Medium {
+    pub sigma_a: f32,
+    pub sigma_s: f32,
+    pub color: Color,
+    pub g: f32,
+    pub density: Density,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
+pub enum Density {
+    Homogeneous,
+    Grid(DensityGrid),
+}
+
+impl Medium {
+    pub fn homogeneous(sigma_a: f32, sigma_s: f32, color: Color, g: f32) -> Self {
+        Self {
+            sigma_a,
+            sigma_s,
+            color,
+            g,
+            density: Density::Homogeneous,
+        }
+    }
+
+    pub fn heterogeneous(
+        sigma_a: f32,
+        sigma_s: f32,
+        color: Color,
+        g: f32,
+        grid: DensityGrid,
+    ) -> Self {
+        Self {
+            sigma_a,
+            sigma_s,
+            color,
+            g,
+            density: Density::Grid(grid),
+        }
+    }
+
+    pub fn sigma_t(&self) -> f32 {
+        self.sigma_a + self.sigma_s
+    }
+
+    /// the fraction of light that is scattered instead of absorbed at a collision
+    pub fn albedo(&self) -> Color {
+        let sigma_t = self.sigma_t();
+        if sigma_t <= 0.0 {
+            return Color(0.0, 0.0, 0.0);
+        }
+        self.color.scale(self.sigma_s / sigma_t)
+    }
+
+    /// samples the distance to the next real collision along the ray before `t_max`
+    /// using delta tracking, returns `None` if the ray passes without a collision
+    pub fn sample_distance(&self, ray: &Ray, t_max: Real) -> Option<Real> {
+        let sigma_t = self.sigma_t();
+        if sigma_t <= 0.0 {
+            return None;
+        }
+        match &self.density {
+            Density::Homogeneous => {
+                let t = -(1.0 - utils::random::<f32>()).ln() / sigma_t;
+                (t < t_max).then_some(t)
+            }
+            Density::Grid(grid) => {
+                let (t_min, t_end) = grid.clip(ray, t_max)?;
+                let majorant = sigma_t * grid.max;
+                let mut t = t_min;
+                loop {
+                    t -= (1.0 - utils::random::<f32>()).ln() / majorant;
+                    if t >= t_end {
+                        return None;
+                    }
+                    if utils::random::<f32>() * grid.max < grid.density(&ray.point_at(t)) {
+                        return Some(t);
+                    }
+                }
+            }
+        }
+    }
+
+    /// estimates the transmittance along the ray up to `t_max` using ratio tracking
+    pub fn transmittance(&self, ray: &Ray, t_max: Real) -> f32 {
+        let sigma_t = self.sigma_t();
+        if sigma_t <= 0.0 {
+            return 1.0;
+        }
+        match &self.density {
+            Density::Homogeneous => (-sigma_t * t_max).exp(),
+            Density::Grid(grid) => {
+                let Some((t_min, t_end)) = grid.clip(ray, t_max) else {
+                    return 1.0;
+                };
+                let majorant = sigma_t * grid.max;
+                let mut transmittance = 1.0;
+                let mut t = t_min;
+                loop {
+                    t -= (1.0 - utils::random::<f32>()).ln() / majorant;
+                    if t >= t_end {
+                        return transmittance;
+                    }
+                    transmittance *= 1.0 - grid.density(&ray.point_at(t)) / grid.max;
+                }
+            }
+        }
+    }
+
+    /// the Henyey–Greenstein phase function for the cosine between the incoming and outgoing
+    /// direction of travel
+    pub fn phase(&self, cos_theta: f32) -> f32 {
+        let g = self.g;
+        let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+        (1.0 - g * g) / (4.0 * PI * denom * denom.sqrt())
+    }
+
+    /// samples an outgoing direction of travel from the phase function
+    pub fn sample_phase(&self, direction: Vector<f32>) -> Vector<f32> {
+        let g = self.g;
+        let u = utils::random::<f32>();
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - 2.0 * u
+        } else {
+            let sq = (1.0 - g * g) / (1.0 - g + 2.0 * g * u);
+            (1.0 + g * g - sq * sq) / (2.0 * g)
+        }
+        .clamp(-1.0, 1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let phi = 2.0 * PI * utils::random::<f32>();
+        utils::local_frame(direction)
+            * Vector::new(phi.cos() * sin_theta, phi.sin() * sin_theta, cos_theta)
+    }
+}
+
+/// A density grid in the space of the object filled by the medium, densities are interpolated
+/// trilinearly between the voxel centers and are zero outside of `bounds`.
+pub struct DensityGrid {
+    resolution: [usize; 3],
+    bounds: Aabb,
+    data: Vec<f32>,
+    max: f32,
+}
+
+impl DensityGrid {
+    /// `data` is indexed as `x + resolution[0] * (y + resolution[1] * z)`
+    pub fn new(resolution: [usize; 3], bounds: Aabb, data: Vec<f32>) -> Self {
+        assert_eq!(
+            data.len(),
+            resolution.iter().product::<usize>(),
+            "density grid data does not match its resolution"
+        );
+        let max = data.iter().copied().fold(0.0, f32::max);
+        Self {
+            resolution,
+            bounds,
+            data,
+            max,
+        }
+    }
+
+    /// builds a grid by evaluating `density` at every voxel center
+    pub fn from_fn(
+        resolution: [usize; 3],
+        bounds: Aabb,
+        density: impl Fn(Point<f32>) -> f32,
+    ) -> Self {
+        let extents = bounds.extents();
+        let mut data = Vec::with_capacity(resolution.iter().product());
+        for z in 0..resolution[2] {
+            for y in 0..resolution[1] {
+                for x in 0..resolution[0] {
+                    let offset = Vector::new(
+                        (x as f32 + 0.5) / resolution[0] as f32 * extents.x,
+                        (y as f32 + 0.5) / resolution[1] as f32 * extents.y,
+                        (z as f32 + 0.5) / resolution[2] as f32 * extents.z,
+                    );
+                    data.push(density(bounds.mins + offset).max(0.0));
+                }
+            }
+        }
+        Self::new(resolution, bounds, data)
+    }
+
+    pub fn density(&self, point: &Point<f32>) -> f32 {
+        if !self.bounds.contains_local_point(point) {
+            return 0.0;
+        }
+        let relative = (point - self.bounds.mins).component_div(&self.bounds.extents());
+        let mut base = [0; 3];
+        let mut frac = [0.0; 3];
+        for i in 0..3 {
+            let x = (relative[i] * self.resolution[i] as f32 - 0.5)
+                .clamp(0.0, (self.resolution[i] - 1) as f32);
+            base[i] = (x.floor() as usize).min(self.resolution[i].saturating_sub(2));
+            frac[i] = x - base[i] as f32;
+        }
+        let mut value = 0.0;
+        for corner in 0..8 {
+            let mut weight = 1.0;
+            let mut idx = [0; 3];
+            for i in 0..3 {
+                let upper = (corner >> i) & 1 == 1;
+                idx[i] = (base[i] + upper as usize).min(self.resolution[i] - 1);
+                weight *= if upper { frac[i] } else { 1.0 - frac[i] };
+            }
+            value += weight * self.voxel(idx);
+        }
+        value
+    }
+
+    pub fn resolution(&self) -> [usize; 3] {
+        self.resolution
+    }
+
+    pub fn bounds(&self) -> &Aabb {
+        &self.bounds
+    }
+
+    pub fn data(&self) -> &[f32] {
+        &self.data
+    }
+
+    fn voxel(&self, [x, y, z]: [usize; 3]) -> f32 {
+        self.data[x + self.resolution[0] * (y + self.resolution[1] * z)]
+    }
+
+    fn clip(&self, ray: &Ray, t_max: Real) -> Option<(Real, Real)> {
+        if self.max <= 0.0 {
+            return None;
+        }
+        let (t_min, t_end) = self.bounds.clip_ray_parameters(ray)?;
+        let t_end = t_end.min(t_max);
+        (t_min < t_end).then_some((t_min.max(0.0), t_end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ray() -> Ray {
+        Ray::new(Point::new(-2.0, 0.5, 0.5), Vector::x())
+    }
+
+    #[test]
+    fn homogeneous_transmittance_follows_beer_lambert() {
+        let medium = Medium::homogeneous(0.3, 0.2, Color(1.0, 1.0, 1.0), 0.0);
+        for distance in [0.0_f32, 0.5, 2.0, 10.0] {
+            let expected = (-0.5 * distance).exp();
+            assert!((medium.transmittance(&ray(), distance) - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn ratio_tracking_matches_beer_lambert_in_a_constant_grid() {
+        let _seeded = utils::seed_rng(7);
+        let bounds = Aabb::new(Point::origin(), Point::new(1.0, 1.0, 1.0));
+        let grid = DensityGrid::from_fn([4, 4, 4], bounds, |_| 1.0);
+        let medium = Medium::heterogeneous(1.5, 0.5, Color(1.0, 1.0, 1.0), 0.0, grid);
+        // the ray passes one unit of the grid
+        let samples = 20000;
+        let mean = (0..samples)
+            .map(|_| medium.transmittance(&ray(), 10.0))
+            .sum::<f32>()
+            / samples as f32;
+        assert!((mean - (-2.0_f32).exp()).abs() < 0.01, "{}", mean);
+    }
+
+    #[test]
+    fn delta_tracking_collides_as_often_as_beer_lambert_predicts() {
+        let _seeded = utils::seed_rng(11);
+        let bounds = Aabb::new(Point::origin(), Point::new(1.0, 1.0, 1.0));
+        let grid = DensityGrid::from_fn([4, 4, 4], bounds, |_| 1.0);
+        let medium = Medium::heterogeneous(0.5, 0.5, Color(1.0, 1.0, 1.0), 0.0, grid);
+        let samples = 20000;
+        let passed = (0..samples)
+            .filter(|_| medium.sample_distance(&ray(), 10.0).is_none())
+            .count();
+        let fraction = passed as f32 / samples as f32;
+        assert!((fraction - (-1.0_f32).exp()).abs() < 0.01, "{}", fraction);
+    }
+
+    #[test]
+    fn grid_is_zero_outside_of_its_bounds() {
+        let bounds = Aabb::new(Point::origin(), Point::new(1.0, 1.0, 1.0));
+        let grid = DensityGrid::from_fn([2, 2, 2], bounds, |point| point.x);
+        assert_eq!(grid.density(&Point::new(1.5, 0.5, 0.5)), 0.0);
+        assert!((grid.density(&Point::new(0.5, 0.5, 0.5)) - 0.5).abs() < 1e-6);
+    }
+}
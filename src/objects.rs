@@ -1,174 +1,1524 @@
-use crate::{camera::Camera, utils};
-use image::Rgb;
-use indicatif::{ParallelProgressIterator, ProgressIterator};
-use nalgebra::{Rotation3, UnitVector3};
+use crate::{
+    accel::{Blas, Tlas, TlasEntry},
+    animation::{Animatable, Animation},
+    bvh,
+    camera::{Camera, CameraSettings, Lens, PixelRays, Shutter},
+    ellipsoid::Ellipsoid,
+    environment::Background,
+    graph::{Group, GroupRange, Instance, Prototype, UnknownPrototype},
+    light::Light,
+    medium::Medium,
+    packet::{PacketWidth, RayPacket},
+    progressive::{Accumulator, Pass, Progressive},
+    texture::{NormalMap, Texture},
+    tiles::{Tile, Tiling},
+    utils,
+};
+use image::{ImageResult, Rgb};
+use indicatif::ProgressBar;
+use nalgebra::Point2;
 use parry3d::{
-    math::{Isometry, Real, Vector},
+    bounding_volume::Aabb,
+    math::{Isometry, Point, Real, Vector},
     query::{Ray, RayCast, RayIntersection},
-    shape::{Ball, Capsule, Cuboid, HalfSpace, RoundCuboid},
+    shape::{
+        Ball, Capsule, ConvexPolyhedron, Cuboid, Cylinder, HalfSpace, RoundCuboid, Shape as _,
+    },
 };
 use rayon::prelude::*;
-use std::ops::{Add, AddAssign, Mul};
+use std::{
+    f32::consts::PI,
+    ops::{Add, AddAssign, Mul},
+    sync::{Mutex, OnceLock},
+    time::Instant,
+};
+
+/// how far a ray is moved past a medium boundary so it does not hit the same boundary again
+const BOUNDARY_OFFSET: f32 = 1e-4;
+
+/// the density of uniformly sampled directions on the hemisphere,
+/// the brdf of a surface is its color times `Brdf::apply` times this
+const HEMISPHERE_PDF: f32 = 1.0 / (2.0 * PI);
 
 #[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Scene {
     objects: Vec<Object>,
-    background: Color,
+    background: Background,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    atmosphere: Option<Medium>,
+    lights: Vec<Light>,
+    camera: CameraSettings,
+    render: RenderSettings,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Animation::is_empty"))]
+    animation: Animation,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    prototypes: Vec<Prototype>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    instances: Vec<Instance>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    groups: Vec<GroupRange>,
+    /// the hierarchy over `objects`, built on the first render after they changed
+    #[cfg_attr(feature = "serde", serde(skip))]
+    blas: OnceLock<Blas>,
+    /// built on the first render after the objects or instances changed
+    #[cfg_attr(feature = "serde", serde(skip))]
+    tlas: OnceLock<Tlas>,
+}
+
+/// An object hit by a ray, `instance` places instanced objects in the world.
+struct Hit<'a> {
+    object: &'a Object,
+    instance: Option<Isometry<f32>>,
+    intersection: RayIntersection,
+}
+
+/// A medium a ray is inside of, with the isometry of the object it fills, so its density grid
+/// moves with the object.
+#[derive(Clone, Copy)]
+struct LocalMedium<'a> {
+    medium: &'a Medium,
+    isometry: Isometry<f32>,
+}
+
+impl<'a> LocalMedium<'a> {
+    fn filling(
+        object: &Object,
+        instance: Option<Isometry<f32>>,
+        medium: &'a Medium,
+        time: f32,
+    ) -> Self {
+        let isometry = object.isometry_at(time);
+        Self {
+            medium,
+            isometry: instance.map_or(isometry, |instance| instance * isometry),
+        }
+    }
+
+    /// the ray in the space of the object, isometries keep the distances along it
+    fn ray(&self, ray: &Ray) -> Ray {
+        ray.inverse_transform_by(&self.isometry)
+    }
+}
+
+/// What changes between the camera rays of a pixel.
+struct CameraSampler {
+    lens: Option<Lens>,
+    shutter: Shutter,
+    /// whether the shutter is open while objects move
+    motion_blur: bool,
+}
+
+/// Where and with which density the direction of a ray was sampled, to weight what it hits
+/// against sampling lights and the background directly.
+#[derive(Clone, Copy)]
+struct Scatter {
+    /// the point the ray left, rays passing through invisible or translucent boundaries are moved
+    /// past them but keep it
+    origin: Point<f32>,
+    pdf: f32,
+}
+
+/// How many rays are traced per pixel and how often they are reflected.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RenderSettings {
+    pub rays: usize,
+    pub max_reflections: usize,
+    /// makes renders reproducible, every pixel gets random numbers derived from the seed
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub seed: Option<u64>,
+    /// how many rays leaving a point together are traced as a packet, used by all renders
+    /// of the scene with the settings it stores
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "crate::scene_file::is_default")
+    )]
+    pub packet: PacketWidth,
+    /// how the image is split into tiles, seeded renders depend on the size of the tiles
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "crate::scene_file::is_default")
+    )]
+    pub tiles: Tiling,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            rays: 100,
+            max_reflections: 4,
+            seed: None,
+            packet: PacketWidth::default(),
+            tiles: Tiling::default(),
+        }
+    }
 }
 
 impl Scene {
-    pub fn new(background: Color) -> Self {
+    pub fn new(background: impl Into<Background>) -> Self {
         Self {
-            background,
+            background: background.into(),
             objects: Vec::new(),
+            atmosphere: None,
+            lights: Vec::new(),
+            camera: CameraSettings::default(),
+            render: RenderSettings::default(),
+            animation: Animation::default(),
+            prototypes: Vec::new(),
+            instances: Vec::new(),
+            groups: Vec::new(),
+            blas: OnceLock::new(),
+            tlas: OnceLock::new(),
         }
     }
 
+    /// the camera stored with the scene, used when rendering scene files
+    pub fn with_camera(mut self, camera: CameraSettings) -> Self {
+        self.camera = camera;
+        self
+    }
+
+    pub fn with_render_settings(mut self, render: RenderSettings) -> Self {
+        self.render = render;
+        self
+    }
+
+    pub fn camera_settings(&self) -> &CameraSettings {
+        &self.camera
+    }
+
+    pub fn render_settings(&self) -> &RenderSettings {
+        &self.render
+    }
+
+    pub fn with_animation(mut self, animation: Animation) -> Self {
+        self.animation = animation;
+        self
+    }
+
+    pub fn animation(&self) -> &Animation {
+        &self.animation
+    }
+
+    /// moves the animated objects and the camera to their state at `time`
+    pub fn at_time(&mut self, time: f32) {
+        self.blas = OnceLock::new();
+        self.tlas = OnceLock::new();
+        self.animation.camera.apply(&mut self.camera, time);
+        for animation in &self.animation.objects {
+            if let Some(object) = self.objects.get_mut(animation.object) {
+                animation.apply(object, time);
+                object.end_isometry = None;
+            }
+        }
+    }
+
+    /// like `at_time` at `start`, but the animated objects keep moving until `end`,
+    /// which the camera shutter blurs
+    pub fn at_interval(&mut self, start: f32, end: f32) {
+        self.at_time(start);
+        for animation in &self.animation.objects {
+            if let Some(object) = self.objects.get_mut(animation.object) {
+                let moved = animation.isometry_at(&object.isometry, end);
+                object.end_isometry = (moved != object.isometry).then_some(moved);
+            }
+        }
+    }
+
+    pub fn objects(&self) -> &[Object] {
+        &self.objects
+    }
+
+    /// the objects for changing them, which rebuilds their hierarchy, see `move_objects`
+    pub fn objects_mut(&mut self) -> &mut [Object] {
+        self.blas = OnceLock::new();
+        self.tlas = OnceLock::new();
+        &mut self.objects
+    }
+
+    /// moves the objects between frames, keeping their hierarchy and only updating its bounds,
+    /// which is faster than `objects_mut` while they move little
+    pub fn move_objects(&mut self, f: impl FnOnce(&mut [Object])) {
+        f(&mut self.objects);
+        if let Some(blas) = self.blas.get_mut() {
+            blas.refit(&self.objects);
+        }
+        self.tlas = OnceLock::new();
+    }
+
+    pub fn lights(&self) -> &[Light] {
+        &self.lights
+    }
+
+    pub fn background(&self) -> &Background {
+        &self.background
+    }
+
+    pub fn atmosphere(&self) -> Option<&Medium> {
+        self.atmosphere.as_ref()
+    }
+
+    /// fills the space outside of all objects with the given medium, its density grid is in
+    /// world space, a homogeneous atmosphere reaches to infinity and so takes up all light of
+    /// the background and of directional lights, a grid bounds it
+    pub fn with_atmosphere(mut self, medium: Medium) -> Self {
+        self.atmosphere = Some(medium);
+        self
+    }
+
+    pub fn add_light(mut self, light: Light) -> Self {
+        self.lights.push(light);
+        self
+    }
+
     pub fn add_objects(mut self, mut objects: Vec<Object>) -> Self {
+        self.blas = OnceLock::new();
+        self.tlas = OnceLock::new();
         self.objects.append(&mut objects);
         self
     }
     pub fn add_object(mut self, objects: Object) -> Self {
+        self.blas = OnceLock::new();
+        self.tlas = OnceLock::new();
         self.objects.push(objects);
         self
     }
 
+    /// adds a prototype for instances, replacing the one with the same name
+    pub fn add_prototype(mut self, prototype: Prototype) -> Self {
+        self.tlas = OnceLock::new();
+        self.prototypes.retain(|other| other.name != prototype.name);
+        self.prototypes.push(prototype);
+        self
+    }
+
+    pub fn prototypes(&self) -> &[Prototype] {
+        &self.prototypes
+    }
+
+    /// adds an instance of one of the prototypes added before
+    pub fn add_instance(mut self, instance: Instance) -> Result<Self, UnknownPrototype> {
+        self.check_prototypes(std::slice::from_ref(&instance))?;
+        self.tlas = OnceLock::new();
+        self.instances.push(instance);
+        Ok(self)
+    }
+
+    pub fn add_instances(mut self, mut instances: Vec<Instance>) -> Result<Self, UnknownPrototype> {
+        self.check_prototypes(&instances)?;
+        self.tlas = OnceLock::new();
+        self.instances.append(&mut instances);
+        Ok(self)
+    }
+
+    /// fails for the first of the instances whose prototype the scene does not have
+    pub(crate) fn check_prototypes(&self, instances: &[Instance]) -> Result<(), UnknownPrototype> {
+        for instance in instances {
+            let name = &instance.prototype;
+            if !self
+                .prototypes
+                .iter()
+                .any(|prototype| prototype.name == *name)
+            {
+                return Err(UnknownPrototype(name.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn instances(&self) -> &[Instance] {
+        &self.instances
+    }
+
+    /// the instances for moving them between frames, they have to keep to the prototypes of
+    /// the scene
+    pub fn instances_mut(&mut self) -> &mut [Instance] {
+        self.tlas = OnceLock::new();
+        &mut self.instances
+    }
+
+    /// adds the objects and instances of a group and its nested groups, placed by their
+    /// isometries, the instances have to be of prototypes added before
+    pub fn add_group(mut self, group: Group) -> Result<Self, UnknownPrototype> {
+        self.blas = OnceLock::new();
+        self.tlas = OnceLock::new();
+        let start = self.instances.len();
+        group.flatten(
+            &Isometry::identity(),
+            "",
+            &mut self.objects,
+            &mut self.instances,
+            &mut self.groups,
+        );
+        self.check_prototypes(&self.instances[start..])?;
+        Ok(self)
+    }
+
+    /// the objects and instances of a named group, see `GroupRange`
+    pub fn group(&self, name: &str) -> Option<&GroupRange> {
+        self.groups.iter().find(|group| group.name == name)
+    }
+
+    fn tlas(&self) -> &Tlas {
+        self.tlas.get_or_init(|| {
+            let objects = self.blas.get_or_init(|| Blas::new(&self.objects));
+            Tlas::new(objects, &self.instances, &self.prototypes)
+        })
+    }
+
+    fn camera_sampler(&self, camera: &Camera) -> CameraSampler {
+        let shutter = *camera.shutter();
+        CameraSampler {
+            lens: camera.lens(),
+            shutter,
+            motion_blur: !shutter.is_instant()
+                && self
+                    .objects
+                    .iter()
+                    .chain(self.prototypes.iter().flat_map(Prototype::objects))
+                    .any(|object| object.end_isometry.is_some())
+                || self
+                    .instances
+                    .iter()
+                    .any(|instance| instance.end_isometry.is_some()),
+        }
+    }
+
+    /// the scene's render settings with another number of rays and reflections
+    fn settings_with(&self, rays: usize, max_reflections: usize) -> RenderSettings {
+        RenderSettings {
+            rays,
+            max_reflections,
+            ..self.render
+        }
+    }
+
     pub fn render_par_with_progress(
         &self,
         camera: &mut Camera,
         rays: usize,
         max_reflections: usize,
     ) {
-        camera
-            .get_rays()
-            .into_par_iter()
-            .progress()
-            .for_each(|(ray, color)| *color = self.render_ray(&ray, rays, max_reflections).into());
+        let progress = ProgressBar::new(camera.width() as u64 * camera.height() as u64);
+        let settings = self.settings_with(rays, max_reflections);
+        self.render_tiles(camera, &settings, true, |tile, _| {
+            progress.inc(tile.pixel_count() as u64)
+        });
+        progress.finish();
     }
 
     pub fn render_with_progress(&self, camera: &mut Camera, rays: usize, max_reflections: usize) {
-        camera
-            .get_rays()
-            .into_iter()
-            .progress()
-            .for_each(|(ray, color)| *color = self.render_ray(&ray, rays, max_reflections).into());
+        let progress = ProgressBar::new(camera.width() as u64 * camera.height() as u64);
+        let settings = self.settings_with(rays, max_reflections);
+        self.render_tiles(camera, &settings, false, |tile, _| {
+            progress.inc(tile.pixel_count() as u64)
+        });
+        progress.finish();
     }
 
     pub fn render_par(&self, camera: &mut Camera, rays: usize, max_reflections: usize) {
-        camera
-            .get_rays()
-            .into_par_iter()
-            .for_each(|(ray, color)| *color = self.render_ray(&ray, rays, max_reflections).into());
+        let settings = self.settings_with(rays, max_reflections);
+        self.render_tiles(camera, &settings, true, |_, _| {});
     }
 
     pub fn render(&self, camera: &mut Camera, rays: usize, max_reflections: usize) {
-        camera
-            .get_rays()
-            .into_iter()
-            .for_each(|(ray, color)| *color = self.render_ray(&ray, rays, max_reflections).into());
+        let settings = self.settings_with(rays, max_reflections);
+        self.render_tiles(camera, &settings, false, |_, _| {});
+    }
+
+    /// renders in parallel, reporting the progress in pixels to the given bar
+    pub fn render_with_settings(
+        &self,
+        camera: &mut Camera,
+        settings: &RenderSettings,
+        progress: ProgressBar,
+    ) {
+        self.render_tiles(camera, settings, true, |tile, _| {
+            progress.inc(tile.pixel_count() as u64)
+        });
+    }
+
+    /// renders tile by tile in the order of the tiling of the settings, `on_tile` is called
+    /// with every finished tile once it is written to the image of the camera
+    pub fn render_tiles(
+        &self,
+        camera: &mut Camera,
+        settings: &RenderSettings,
+        parallel: bool,
+        on_tile: impl Fn(&Tile, &Camera) + Sync,
+    ) {
+        self.schedule_tiles(camera, settings, parallel, None, |tile, colors, camera| {
+            let colors: Vec<Color> = colors
+                .into_iter()
+                .map(|color| color.unwrap_or_default())
+                .collect();
+            camera.put_tile(tile, &colors);
+            on_tile(tile, camera);
+        });
+    }
+
+    /// renders in passes of `progressive.pass_rays` samples per pixel until one of its limits
+    /// is reached, after every pass the camera shows the mean of all passes so far, the preview
+    /// and heat map are saved and `on_pass` is called, a seeded render uses another seed for
+    /// every pass
+    pub fn render_progressive(
+        &self,
+        camera: &mut Camera,
+        settings: &RenderSettings,
+        progressive: &Progressive,
+        mut on_pass: impl FnMut(&Pass, &Camera),
+    ) -> ImageResult<Pass> {
+        let start = Instant::now();
+        let mut accumulator = Accumulator::new(camera.width(), camera.height());
+        let mut active = accumulator.active(progressive);
+        loop {
+            let index = accumulator.passes();
+            let rays = progressive
+                .pass_rays
+                .min(
+                    progressive
+                        .target_rays
+                        .saturating_sub(accumulator.max_rays()),
+                )
+                .max(1);
+            let pass_settings = RenderSettings {
+                rays,
+                seed: settings.seed.map(|seed| seed.wrapping_add(index as u64)),
+                ..*settings
+            };
+            let shared = Mutex::new(&mut accumulator);
+            self.schedule_tiles(
+                camera,
+                &pass_settings,
+                true,
+                Some(&active),
+                |tile, colors, camera| {
+                    let mut accumulator = shared.lock().expect("no tile panicked while adding");
+                    camera.put_tile(tile, &accumulator.add_tile(tile, &colors, rays));
+                },
+            );
+            accumulator.finish_pass();
+            active = accumulator.active(progressive);
+            let pass = Pass {
+                index,
+                rays: accumulator.max_rays(),
+                mean_rays: accumulator.mean_rays(),
+                active: active.iter().filter(|active| **active).count(),
+                elapsed: start.elapsed(),
+                noise: accumulator.noise(),
+            };
+            if let Some(path) = &progressive.preview {
+                camera.save_img(path)?;
+            }
+            if let Some(path) = &progressive.heatmap {
+                accumulator.heatmap().save(path)?;
+            }
+            on_pass(&pass, camera);
+            if progressive.is_done(&pass) {
+                return Ok(pass);
+            }
+        }
+    }
+
+    /// renders the tiles in the order of the tiling of the settings, `finish` gets the colors
+    /// of the pixels of every finished tile with the camera they are written to, only the
+    /// pixels marked in `active` row by row are rendered if it is given
+    fn schedule_tiles(
+        &self,
+        camera: &mut Camera,
+        settings: &RenderSettings,
+        parallel: bool,
+        active: Option<&[bool]>,
+        finish: impl Fn(&Tile, Vec<Option<Color>>, &mut Camera) + Sync,
+    ) {
+        let sampler = self.camera_sampler(camera);
+        let rays = camera.rays();
+        let width = camera.width();
+        let is_active = |x: u32, y: u32| {
+            active.is_none_or(|active| active[y as usize * width as usize + x as usize])
+        };
+        let tiles = settings.tiles.tiles(camera.width(), camera.height());
+        let camera = Mutex::new(camera);
+        let render = |tile: Tile| {
+            if !tile.pixels().any(|(x, y)| is_active(x, y)) {
+                return;
+            }
+            let samples = self.tile_samples(&tile, &rays, &sampler, settings, is_active);
+            let mut camera = camera.lock().expect("no tile panicked while writing");
+            finish(&tile, samples, &mut camera);
+        };
+        // bridging hands the tiles to the threads in order, splitting would not
+        if parallel {
+            tiles.into_iter().par_bridge().for_each(render);
+        } else {
+            tiles.into_iter().for_each(render);
+        }
     }
 
-    fn render_ray(&self, ray: &Ray, rays: usize, max_reflections: usize) -> Color {
+    /// the colors of the active pixels of a tile row by row, a seeded render reseeds the random
+    /// numbers per tile
+    fn tile_samples(
+        &self,
+        tile: &Tile,
+        rays: &PixelRays,
+        sampler: &CameraSampler,
+        settings: &RenderSettings,
+        is_active: impl Fn(u32, u32) -> bool,
+    ) -> Vec<Option<Color>> {
+        let _seeded = settings.seed.map(|seed| utils::seed_rng(tile.seed(seed)));
+        tile.pixels()
+            .map(|(x, y)| {
+                is_active(x, y).then(|| {
+                    self.render_ray(
+                        &rays.ray(x, y),
+                        sampler,
+                        settings.rays,
+                        settings.max_reflections,
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// with a lens or motion blur every ray starts at another point of the lens and another
+    /// time instead of splitting at the first intersection
+    fn render_ray(
+        &self,
+        ray: &Ray,
+        sampler: &CameraSampler,
+        rays: usize,
+        max_reflections: usize,
+    ) -> Color {
+        let media: Vec<LocalMedium> = self
+            .atmosphere
+            .iter()
+            .map(|medium| LocalMedium {
+                medium,
+                isometry: Isometry::identity(),
+            })
+            .collect();
+        if sampler.lens.is_none() && !sampler.motion_blur {
+            let time = sampler.shutter.open;
+            return self.trace(ray, rays, max_reflections, &media, None, time);
+        }
+        let (samples, times): (Vec<Ray>, Vec<f32>) = (0..rays)
+            .map(|_| {
+                let ray = sampler.lens.as_ref().map_or(*ray, |lens| lens.sample(ray));
+                (ray, sampler.shutter.sample())
+            })
+            .unzip();
+        let mut color = Color(0.0, 0.0, 0.0);
+        for sample in self.trace_packets(&samples, &times, max_reflections, &media, None) {
+            color += sample;
+        }
+        color.scale(1.0 / rays as f32)
+    }
+
+    /// traces single samples of many rays in packets of the width of the render settings,
+    /// the rays are coherent if they leave about the same point
+    fn trace_packets(
+        &self,
+        rays: &[Ray],
+        times: &[f32],
+        max_reflections: usize,
+        media: &[LocalMedium<'_>],
+        scatter: Option<Scatter>,
+    ) -> Vec<Color> {
+        // a single ray gains nothing from a packet
+        let width = match rays.len() {
+            0 | 1 => PacketWidth::Scalar,
+            _ => self.render.packet,
+        };
+        match width {
+            PacketWidth::Scalar => rays
+                .iter()
+                .zip(times)
+                .map(|(ray, time)| self.trace(ray, 1, max_reflections, media, scatter, *time))
+                .collect(),
+            PacketWidth::Four => {
+                self.trace_packets_of::<4>(rays, times, max_reflections, media, scatter)
+            }
+            PacketWidth::Eight => {
+                self.trace_packets_of::<8>(rays, times, max_reflections, media, scatter)
+            }
+            PacketWidth::Sixteen => {
+                self.trace_packets_of::<16>(rays, times, max_reflections, media, scatter)
+            }
+        }
+    }
+
+    fn trace_packets_of<const N: usize>(
+        &self,
+        rays: &[Ray],
+        times: &[f32],
+        max_reflections: usize,
+        media: &[LocalMedium<'_>],
+        scatter: Option<Scatter>,
+    ) -> Vec<Color> {
+        if max_reflections == 0 {
+            return rays.iter().map(|ray| self.escaped(ray, scatter)).collect();
+        }
+        let mut colors = Vec::with_capacity(rays.len());
+        for (rays, times) in rays.chunks(N).zip(times.chunks(N)) {
+            let hits = self.closest_intersections(&RayPacket::<N>::new(rays, times));
+            for ((ray, time), hit) in rays.iter().zip(times).zip(hits) {
+                colors.push(self.shade(ray, hit, 1, max_reflections, media, scatter, *time));
+            }
+        }
+        colors
+    }
+
+    /// `media` are the media the ray is inside of with the innermost last, starting with the
+    /// atmosphere, `scatter` is where and with which density the direction of the ray was
+    /// sampled, it is `None` for camera rays, `time` is the fraction of the frame interval at
+    /// which the ray travels
+    fn trace(
+        &self,
+        ray: &Ray,
+        rays: usize,
+        max_reflections: usize,
+        media: &[LocalMedium<'_>],
+        scatter: Option<Scatter>,
+        time: f32,
+    ) -> Color {
         if max_reflections == 0 {
-            return self.background;
+            return self.escaped(ray, scatter);
         }
-        match self.closest_intersection(ray) {
-            None => self.background,
-            Some((idx, intersection)) => {
-                let object = &self.objects[idx];
+        let closest = self.closest_intersection(ray, time);
+        self.shade(ray, closest, rays, max_reflections, media, scatter, time)
+    }
+
+    /// the color seen along a ray with the closest object it hits, like `trace`
+    #[allow(clippy::too_many_arguments)]
+    fn shade(
+        &self,
+        ray: &Ray,
+        mut closest: Option<Hit<'_>>,
+        rays: usize,
+        max_reflections: usize,
+        media: &[LocalMedium<'_>],
+        scatter: Option<Scatter>,
+        time: f32,
+    ) -> Color {
+        let closest_light = self.closest_light(ray).filter(|(_, toi, _)| {
+            closest
+                .as_ref()
+                .is_none_or(|hit| *toi < hit.intersection.toi)
+        });
+        if closest_light.is_some() {
+            closest = None;
+        }
+        if let Some(local) = media.last() {
+            let medium = local.medium;
+            let t_max = match (&closest, &closest_light) {
+                (Some(hit), _) => hit.intersection.toi,
+                (None, Some((_, toi, _))) => *toi,
+                (None, None) => Real::MAX,
+            };
+            if let Some(t) = medium.sample_distance(&local.ray(ray), t_max) {
+                let scatter_point = ray.point_at(t);
+                let albedo = medium.albedo();
+                let mut color = Color(0.0, 0.0, 0.0);
+                for _ in 0..rays {
+                    color += self.direct_light(&scatter_point, media, time, |direction| {
+                        let phase = medium.phase(ray.dir.dot(direction));
+                        Some((albedo.scale(phase), phase))
+                    });
+                    let direction = medium.sample_phase(ray.dir);
+                    let pdf = medium.phase(ray.dir.dot(&direction));
+                    let new_ray = Ray::new(scatter_point, direction);
+                    color += albedo
+                        * self.trace(
+                            &new_ray,
+                            1,
+                            max_reflections - 1,
+                            media,
+                            Some(Scatter {
+                                origin: scatter_point,
+                                pdf,
+                            }),
+                            time,
+                        );
+                }
+                return color.scale(1.0 / rays as f32);
+            }
+        }
+        if let Some((idx, _, radiance)) = closest_light {
+            // the ray may have been moved past boundaries since it was scattered
+            return match scatter {
+                None => radiance,
+                Some(scatter) => radiance.scale(utils::power_heuristic(
+                    scatter.pdf,
+                    self.lights[idx].pdf(&scatter.origin, &ray.dir),
+                )),
+            };
+        }
+        match closest {
+            None => self.escaped(ray, scatter),
+            Some(Hit {
+                object,
+                instance,
+                mut intersection,
+            }) => {
                 let intersection_point = ray.point_at(intersection.toi);
+                if let Some(medium) = &object.medium {
+                    // the boundary of a medium is invisible, the ray only changes the medium
+                    let entering = intersection.normal.dot(&ray.dir) < 0.0;
+                    let interior = LocalMedium::filling(object, instance, medium, time);
+                    let media = cross_boundary(media, interior, entering);
+                    let new_ray = Ray::new(intersection_point + ray.dir * BOUNDARY_OFFSET, ray.dir);
+                    return self.trace(&new_ray, rays, max_reflections, &media, scatter, time);
+                }
+                // light passing through a translucent surface, already weighted by its share
+                let mut behind = None;
+                if object.opacity < 1.0 {
+                    let new_ray = Ray::new(intersection_point + ray.dir * BOUNDARY_OFFSET, ray.dir);
+                    // a single ray passes through at random, several rays would make that noisy
+                    if rays > 1 {
+                        let color =
+                            self.trace(&new_ray, rays, max_reflections, media, scatter, time);
+                        behind = Some(color.scale(1.0 - object.opacity));
+                    } else if utils::random::<f32>() >= object.opacity {
+                        return self.trace(&new_ray, rays, max_reflections, media, scatter, time);
+                    }
+                    // back faces of translucent shapes are shaded like front faces
+                    if intersection.normal.dot(&ray.dir) > 0.0 {
+                        intersection.normal = -intersection.normal;
+                    }
+                }
+                // instanced objects are shaded in the space of their prototype
+                let (point, local_normal) = match instance {
+                    None => (intersection_point, intersection.normal),
+                    Some(instance) => (
+                        instance.inverse_transform_point(&intersection_point),
+                        instance.inverse_transform_vector(&intersection.normal),
+                    ),
+                };
+                let object_color = object.color_at(&point, &local_normal, time);
                 if object.is_light_source {
-                    return object.color;
+                    return object_color;
                 }
+                let roughness = object.roughness_at(&point, &local_normal, time);
                 let mut color = Color(0.0, 0.0, 0.0);
-                let direction_transform = Rotation3::from_axis_angle(
-                    &UnitVector3::<f32>::new_normalize(intersection.normal.cross(&Vector::z())),
-                    -intersection.normal.angle(&Vector::z()),
-                );
+                let normal = object.shading_normal(&point, &local_normal, time);
+                let normal = instance.map_or(normal, |instance| instance * normal);
+                // rays leaving the surface must not hit it again, translucent shapes are not solid
+                let origin = intersection_point + intersection.normal * BOUNDARY_OFFSET;
+                if let Brdf::Mirror = object.brdf {
+                    // light samples never hit the single reflected direction, so it is traced alone
+                    let reflected = ray.dir - normal * (2.0 * ray.dir.dot(&normal));
+                    let mut color = object.emission_at(&point, &local_normal, time);
+                    if intersection.normal.dot(&reflected) > 0.0 {
+                        let new_ray = Ray::new(origin, reflected);
+                        color += object_color
+                            * self.trace(&new_ray, rays, max_reflections - 1, media, None, time);
+                    }
+                    return match behind {
+                        Some(behind) => color.scale(object.opacity) + behind,
+                        None => color,
+                    };
+                }
+                let direction_transform = utils::local_frame(normal);
+                let mut new_rays = Vec::with_capacity(rays);
+                let mut factors = Vec::with_capacity(rays);
                 for _ in 0..rays {
+                    color += self.direct_light(&origin, media, time, |direction| {
+                        if intersection.normal.dot(direction) <= 0.0 {
+                            return None;
+                        }
+                        let factor = object.brdf(ray.dir, *direction, normal, roughness)
+                            * normal.dot(direction).max(0.0)
+                            * HEMISPHERE_PDF;
+                        Some((object_color.scale(factor), HEMISPHERE_PDF))
+                    });
                     let out_going = direction_transform * utils::rand_unit_vec_pos_z();
-                    let new_ray = Ray::new(intersection_point, out_going);
-                    let factor = object.brdf(ray.dir, out_going, intersection.normal)
-                        * (intersection.normal.dot(&out_going));
-                    color += object.color.scale(factor)
-                        * self.render_ray(&new_ray, 1, max_reflections - 1);
+                    // directions below the geometric surface would leak light through it
+                    if intersection.normal.dot(&out_going) <= 0.0 {
+                        continue;
+                    }
+                    new_rays.push(Ray::new(origin, out_going));
+                    let factor = object.brdf(ray.dir, out_going, normal, roughness)
+                        * (normal.dot(&out_going));
+                    factors.push(object_color.scale(factor));
+                }
+                // the rays all leave the same point, so they are traced together
+                let times = vec![time; new_rays.len()];
+                let incoming = self.trace_packets(
+                    &new_rays,
+                    &times,
+                    max_reflections - 1,
+                    media,
+                    Some(Scatter {
+                        origin,
+                        pdf: HEMISPHERE_PDF,
+                    }),
+                );
+                for (factor, incoming) in factors.into_iter().zip(incoming) {
+                    color += factor * incoming;
+                }
+                let color = color.scale(1.0 / rays as f32)
+                    + object.emission_at(&point, &local_normal, time);
+                match behind {
+                    Some(behind) => color.scale(object.opacity) + behind,
+                    None => color,
                 }
-                color.scale(1.0 / rays as f32)
             }
         }
     }
 
-    fn closest_intersection(&self, ray: &Ray) -> Option<(usize, RayIntersection)> {
-        self.objects
+    /// the background seen by a ray leaving the scene,
+    /// weighted against explicitly sampling the background
+    fn escaped(&self, ray: &Ray, scatter: Option<Scatter>) -> Color {
+        let radiance = self.background.radiance(&ray.dir);
+        match scatter {
+            None => radiance,
+            Some(scatter) => radiance.scale(utils::power_heuristic(
+                scatter.pdf,
+                self.background.pdf(&ray.dir),
+            )),
+        }
+    }
+
+    /// next event estimation towards the background and all lights,
+    /// `scattering` returns the scattering function times the cosine term for a direction
+    /// and the density with which it would have been sampled
+    fn direct_light(
+        &self,
+        origin: &Point<f32>,
+        media: &[LocalMedium<'_>],
+        time: f32,
+        scattering: impl Fn(&Vector<f32>) -> Option<(Color, f32)>,
+    ) -> Color {
+        let mut color = Color(0.0, 0.0, 0.0);
+        if let Some((direction, radiance, pdf)) = self.background.sample() {
+            if let Some((factor, scatter_pdf)) = scattering(&direction) {
+                let transmittance =
+                    self.transmittance(&Ray::new(*origin, direction), Real::MAX, media, time);
+                color += (factor * radiance)
+                    .scale(transmittance * utils::power_heuristic(pdf, scatter_pdf) / pdf);
+            }
+        }
+        for light in &self.lights {
+            let Some(sample) = light.sample(origin) else {
+                continue;
+            };
+            let Some((factor, scatter_pdf)) = scattering(&sample.direction) else {
+                continue;
+            };
+            let transmittance = self.transmittance(
+                &Ray::new(*origin, sample.direction),
+                sample.distance,
+                media,
+                time,
+            );
+            let weight = if sample.is_delta {
+                1.0
+            } else {
+                utils::power_heuristic(sample.pdf, scatter_pdf)
+            };
+            color += (factor * sample.radiance).scale(transmittance * weight / sample.pdf);
+        }
+        color
+    }
+
+    /// the fraction of light reaching the origin of the ray from the distance `t_max`,
+    /// opaque objects block it entirely while media attenuate it
+    fn transmittance(&self, ray: &Ray, t_max: Real, media: &[LocalMedium<'_>], time: f32) -> f32 {
+        let mut ray = *ray;
+        let mut t_max = t_max;
+        let mut media = media.to_vec();
+        let mut transmittance = 1.0;
+        loop {
+            match self
+                .closest_intersection(&ray, time)
+                .filter(|hit| hit.intersection.toi < t_max)
+            {
+                None => {
+                    return transmittance
+                        * media.last().map_or(1.0, |local| {
+                            local.medium.transmittance(&local.ray(&ray), t_max)
+                        });
+                }
+                Some(Hit {
+                    object,
+                    instance,
+                    intersection,
+                }) => {
+                    if object.medium.is_none() && object.opacity >= 1.0 {
+                        return 0.0;
+                    }
+                    transmittance *= media.last().map_or(1.0, |local| {
+                        local
+                            .medium
+                            .transmittance(&local.ray(&ray), intersection.toi)
+                    });
+                    match &object.medium {
+                        Some(medium) => {
+                            let entering = intersection.normal.dot(&ray.dir) < 0.0;
+                            let interior = LocalMedium::filling(object, instance, medium, time);
+                            media = cross_boundary(&media, interior, entering);
+                        }
+                        None => transmittance *= 1.0 - object.opacity,
+                    }
+                    let step = intersection.toi + BOUNDARY_OFFSET;
+                    ray = Ray::new(ray.point_at(step), ray.dir);
+                    t_max -= step;
+                }
+            }
+        }
+    }
+
+    /// the closest area light hit by the ray as `(index, toi, radiance)`
+    fn closest_light(&self, ray: &Ray) -> Option<(usize, Real, Color)> {
+        self.lights
             .iter()
             .enumerate()
-            .map(|(i, obj)| (i, obj.get_intersection(ray)))
-            .filter_map(|(i, opt)| match opt {
-                Some(inter) => {
-                    if inter.toi > 0.0 {
-                        Some((i, inter))
-                    } else {
-                        None
+            .filter_map(|(i, light)| {
+                light
+                    .intersect(ray)
+                    .map(|(toi, radiance)| (i, toi, radiance))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    /// the closest intersections of the rays of a packet, like `closest_intersection`
+    fn closest_intersections<const N: usize>(&self, packet: &RayPacket<N>) -> [Option<Hit<'_>>; N] {
+        let mut packet = *packet;
+        let mut hits: [Option<Hit>; N] = std::array::from_fn(|_| None);
+        self.tlas()
+            .traverse_packet(&mut packet, |packet, entry| match entry {
+                TlasEntry::Objects => {
+                    let blas = self.blas.get().expect("built with the TLAS");
+                    let found = blas.closest_packet(&self.objects, packet);
+                    for (hit, found) in hits.iter_mut().zip(found) {
+                        if let Some((i, intersection)) = found {
+                            *hit = Some(Hit {
+                                object: &self.objects[i],
+                                instance: None,
+                                intersection,
+                            });
+                        }
                     }
                 }
-                None => None,
-            })
-            .min_by(|a, b| a.1.toi.total_cmp(&b.1.toi))
+                TlasEntry::Instance {
+                    instance,
+                    prototype,
+                } => {
+                    let instance = &self.instances[instance];
+                    let prototype = &self.prototypes[prototype];
+                    let isometries: [Isometry<f32>; N] =
+                        std::array::from_fn(|lane| instance.isometry_at(packet.time[lane]));
+                    let mut local = packet.inverse_transform_by(|lane| isometries[lane]);
+                    let found = prototype
+                        .blas()
+                        .closest_packet(prototype.objects(), &mut local);
+                    packet.toi = local.toi;
+                    for ((hit, found), isometry) in hits.iter_mut().zip(found).zip(isometries) {
+                        if let Some((i, mut intersection)) = found {
+                            intersection.normal = isometry * intersection.normal;
+                            *hit = Some(Hit {
+                                object: &prototype.objects()[i],
+                                instance: Some(isometry),
+                                intersection,
+                            });
+                        }
+                    }
+                }
+            });
+        hits
+    }
+
+    fn closest_intersection(&self, ray: &Ray, time: f32) -> Option<Hit<'_>> {
+        let hit = self.tlas().closest(ray, |entry| match entry {
+            TlasEntry::Objects => {
+                let blas = self.blas.get().expect("built with the TLAS");
+                let (toi, (i, intersection)) = blas.closest(&self.objects, ray, time)?;
+                Some((toi, (&self.objects[i], None, intersection)))
+            }
+            TlasEntry::Instance {
+                instance,
+                prototype,
+            } => {
+                let isometry = self.instances[instance].isometry_at(time);
+                let prototype = &self.prototypes[prototype];
+                // isometries keep distances, so the hit is as far away in the prototype space
+                let local = ray.inverse_transform_by(&isometry);
+                let (toi, (i, mut intersection)) =
+                    prototype
+                        .blas()
+                        .closest(prototype.objects(), &local, time)?;
+                intersection.normal = isometry * intersection.normal;
+                Some((toi, (&prototype.objects()[i], Some(isometry), intersection)))
+            }
+        });
+        hit.map(|(_, (object, instance, intersection))| Hit {
+            object,
+            instance,
+            intersection,
+        })
     }
 }
 
+/// the media inside of which a ray is after crossing the boundary of `interior`, with the
+/// innermost last, a ray leaving a medium it was not known to be in keeps its media
+fn cross_boundary<'a>(
+    media: &[LocalMedium<'a>],
+    interior: LocalMedium<'a>,
+    entering: bool,
+) -> Vec<LocalMedium<'a>> {
+    let mut media = media.to_vec();
+    if entering {
+        media.push(interior);
+    } else if let Some(i) = media
+        .iter()
+        .rposition(|local| std::ptr::eq(local.medium, interior.medium))
+    {
+        media.remove(i);
+    }
+    media
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Object {
     pub shape: Shape,
+    #[cfg_attr(feature = "serde", serde(with = "crate::scene_file::isometry"))]
     pub isometry: Isometry<f32>,
+    /// the isometry at the end of the frame interval for objects that move while the shutter
+    /// is open, `isometry` is the one at its start
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "crate::scene_file::optional_isometry"
+        )
+    )]
+    pub end_isometry: Option<Isometry<f32>>,
+    #[cfg_attr(feature = "serde", serde(default))]
     pub brdf: Brdf,
     pub color: Color,
+    #[cfg_attr(feature = "serde", serde(default))]
     pub is_light_source: bool,
+    /// from zero for a polished to one for a matte surface, used by `Brdf::Glossy`
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default = "crate::scene_file::one",
+            skip_serializing_if = "crate::scene_file::is_one"
+        )
+    )]
+    pub roughness: f32,
+    /// the light the surface emits on top of the light it reflects
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "crate::scene_file::is_default")
+    )]
+    pub emission: Color,
+    /// the probability that a ray is scattered instead of passing through the surface,
+    /// translucent shapes also show their back faces
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default = "crate::scene_file::one",
+            skip_serializing_if = "crate::scene_file::is_one"
+        )
+    )]
+    pub opacity: f32,
+    /// a medium filling the inside of the shape, the surface itself is then invisible, a medium
+    /// inside of another one replaces it until the ray leaves the inner shape again, a density
+    /// grid is given in the space of the shape and moves with it
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub medium: Option<Medium>,
+    /// multiplies `color`, so it drives the albedo or the emission of light sources
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub texture: Option<Texture>,
+    /// multiplies `roughness` by the luminance of the texture
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub roughness_map: Option<Texture>,
+    /// multiplies `emission`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub emission_map: Option<Texture>,
+    /// perturbs the normal used for shading
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub normal_map: Option<NormalMap>,
 }
 
 impl Object {
-    pub fn get_intersection(&self, ray: &Ray) -> Option<RayIntersection> {
-        self.shape.cast_ray_and_get_normal(&self.isometry, ray)
+    /// an opaque object that does not move or emit light, shaded with the default brdf
+    pub fn new(shape: Shape, isometry: Isometry<f32>, color: Color) -> Self {
+        Self {
+            shape,
+            isometry,
+            end_isometry: None,
+            brdf: Brdf::default(),
+            color,
+            is_light_source: false,
+            roughness: 1.0,
+            emission: Color::default(),
+            opacity: 1.0,
+            medium: None,
+            texture: None,
+            roughness_map: None,
+            emission_map: None,
+            normal_map: None,
+        }
+    }
+
+    pub fn with_end_isometry(mut self, isometry: Isometry<f32>) -> Self {
+        self.end_isometry = Some(isometry);
+        self
+    }
+
+    pub fn with_brdf(mut self, brdf: Brdf) -> Self {
+        self.brdf = brdf;
+        self
+    }
+
+    /// makes the object emit its color instead of reflecting light
+    pub fn with_light_source(mut self) -> Self {
+        self.is_light_source = true;
+        self
+    }
+
+    pub fn with_roughness(mut self, roughness: f32) -> Self {
+        self.roughness = roughness;
+        self
+    }
+
+    pub fn with_emission(mut self, emission: Color) -> Self {
+        self.emission = emission;
+        self
+    }
+
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    pub fn with_medium(mut self, medium: Medium) -> Self {
+        self.medium = Some(medium);
+        self
+    }
+
+    pub fn with_texture(mut self, texture: Texture) -> Self {
+        self.texture = Some(texture);
+        self
+    }
+
+    pub fn with_roughness_map(mut self, roughness_map: Texture) -> Self {
+        self.roughness_map = Some(roughness_map);
+        self
     }
 
-    fn brdf(&self, in_coming: Vector<f32>, out_going: Vector<f32>, normal: Vector<f32>) -> f32 {
-        self.brdf.apply(in_coming, out_going)
+    pub fn with_emission_map(mut self, emission_map: Texture) -> Self {
+        self.emission_map = Some(emission_map);
+        self
+    }
+
+    pub fn with_normal_map(mut self, normal_map: NormalMap) -> Self {
+        self.normal_map = Some(normal_map);
+        self
+    }
+
+    /// the isometry at `time`, a fraction of the frame interval, interpolating the translation
+    /// linearly and the rotation spherically
+    pub fn isometry_at(&self, time: f32) -> Isometry<f32> {
+        match &self.end_isometry {
+            None => self.isometry,
+            Some(end) => Animatable::lerp(&self.isometry, end, time),
+        }
+    }
+
+    /// the bounds of the object over the whole frame interval, `None` for unbounded shapes
+    pub fn aabb(&self) -> Option<Aabb> {
+        let local = self.shape.local_aabb()?;
+        Some(bvh::swept_aabb(
+            &local,
+            &self.isometry,
+            self.end_isometry.as_ref(),
+        ))
+    }
+
+    /// the intersection with a ray at `time`, a fraction of the frame interval
+    pub fn get_intersection(&self, ray: &Ray, time: f32) -> Option<RayIntersection> {
+        // rays inside of a medium or a translucent shape have to find the boundary from the inside
+        self.shape.cast_ray_and_get_normal(
+            &self.isometry_at(time),
+            ray,
+            self.medium.is_none() && self.opacity >= 1.0,
+        )
+    }
+
+    /// the point of the shape at a point on the surface given in world space
+    fn surface_at(&self, point: &Point<f32>, normal: &Vector<f32>, time: f32) -> SurfacePoint {
+        let isometry = self.isometry_at(time);
+        self.shape.surface_point(
+            isometry.inverse_transform_point(point),
+            isometry.inverse_transform_vector(normal),
+        )
+    }
+
+    /// the color at a point on the surface given in world space
+    pub fn color_at(&self, point: &Point<f32>, normal: &Vector<f32>, time: f32) -> Color {
+        match &self.texture {
+            None => self.color,
+            Some(texture) => self.color * texture.color_at(&self.surface_at(point, normal, time)),
+        }
+    }
+
+    /// the roughness at a point on the surface given in world space
+    pub fn roughness_at(&self, point: &Point<f32>, normal: &Vector<f32>, time: f32) -> f32 {
+        match &self.roughness_map {
+            None => self.roughness,
+            Some(texture) => {
+                let surface = self.surface_at(point, normal, time);
+                self.roughness * texture.color_at(&surface).luminance()
+            }
+        }
+    }
+
+    /// the emitted light at a point on the surface given in world space
+    pub fn emission_at(&self, point: &Point<f32>, normal: &Vector<f32>, time: f32) -> Color {
+        match &self.emission_map {
+            None => self.emission,
+            Some(texture) => {
+                self.emission * texture.color_at(&self.surface_at(point, normal, time))
+            }
+        }
+    }
+
+    /// the normal used for shading at a point on the surface given in world space
+    pub fn shading_normal(
+        &self,
+        point: &Point<f32>,
+        normal: &Vector<f32>,
+        time: f32,
+    ) -> Vector<f32> {
+        match &self.normal_map {
+            None => *normal,
+            Some(normal_map) => {
+                let surface = self.surface_at(point, normal, time);
+                self.isometry_at(time) * normal_map.perturb(&surface)
+            }
+        }
+    }
+
+    fn brdf(
+        &self,
+        in_coming: Vector<f32>,
+        out_going: Vector<f32>,
+        normal: Vector<f32>,
+        roughness: f32,
+    ) -> f32 {
+        self.brdf.apply(in_coming, out_going, normal, roughness)
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
 pub enum Shape {
     Ball(Ball),
     Cuboid(Cuboid),
     Capsule(Capsule),
+    /// a cylinder along the y axis
+    Cylinder(Cylinder),
+    /// stored in scene files as the points it is the hull of
+    ConvexPolyhedron(
+        #[cfg_attr(
+            feature = "serde",
+            serde(with = "crate::scene_file::convex_polyhedron")
+        )]
+        ConvexPolyhedron,
+    ),
+    Ellipsoid(Ellipsoid),
     RoundCuboid(RoundCuboid),
     HalfSpace(HalfSpace),
 }
 
+/// A point on the surface of a shape in the local space of the shape.
+#[derive(Debug, Clone, Copy)]
+pub struct SurfacePoint {
+    pub uv: Point2<f32>,
+    pub point: Point<f32>,
+    pub normal: Vector<f32>,
+    /// the direction in which `u` increases, perpendicular to the normal
+    pub tangent: Vector<f32>,
+}
+
 impl Shape {
+    /// computes the uv coordinates and tangent of a point on the shape,
+    /// spheres and ellipsoids use spherical coordinates,
+    /// capsules and cylinders cylindrical coordinates,
+    /// cuboids and convex polyhedra project along the largest component of the normal
+    /// and half spaces use the plane coordinates
+    pub fn surface_point(&self, point: Point<f32>, normal: Vector<f32>) -> SurfacePoint {
+        let (uv, tangent) = match self {
+            Shape::Ball(_) => spherical_uv(&normal),
+            Shape::Ellipsoid(ellipsoid) => {
+                spherical_uv(&point.coords.component_div(&ellipsoid.radii))
+            }
+            Shape::Cuboid(cuboid) => box_uv(&point, &normal, &cuboid.half_extents),
+            Shape::RoundCuboid(cuboid) => box_uv(
+                &point,
+                &normal,
+                &cuboid
+                    .inner_shape
+                    .half_extents
+                    .add_scalar(cuboid.border_radius),
+            ),
+            Shape::Capsule(capsule) => {
+                let axis = capsule.segment.scaled_direction();
+                let length = axis.norm();
+                let frame = utils::local_frame(axis / length);
+                let local = frame.inverse() * (point - capsule.segment.a);
+                let phi = local.y.atan2(local.x);
+                (
+                    [0.5 + phi / (2.0 * PI), local.z / length].into(),
+                    frame * Vector::new(-phi.sin(), phi.cos(), 0.0),
+                )
+            }
+            Shape::Cylinder(cylinder) => {
+                let phi = point.z.atan2(point.x);
+                (
+                    [
+                        0.5 + phi / (2.0 * PI),
+                        0.5 + 0.5 * point.y / cylinder.half_height,
+                    ]
+                    .into(),
+                    Vector::new(-phi.sin(), 0.0, phi.cos()),
+                )
+            }
+            Shape::ConvexPolyhedron(polyhedron) => {
+                let aabb = polyhedron.local_aabb();
+                box_uv(
+                    &(point - aabb.center().coords),
+                    &normal,
+                    &aabb.half_extents(),
+                )
+            }
+            Shape::HalfSpace(half_space) => {
+                let frame = utils::local_frame(*half_space.normal);
+                let local = frame.inverse() * point.coords;
+                ([local.x, local.y].into(), frame * Vector::x())
+            }
+        };
+        let tangent = (tangent - normal * normal.dot(&tangent))
+            .try_normalize(1e-6)
+            .unwrap_or_else(|| utils::local_frame(normal) * Vector::x());
+        SurfacePoint {
+            uv,
+            point,
+            normal,
+            tangent,
+        }
+    }
+
+    /// the bounds in the local space of the shape, `None` for half spaces
+    pub fn local_aabb(&self) -> Option<Aabb> {
+        match self {
+            Shape::Ball(ball) => Some(ball.compute_local_aabb()),
+            Shape::Cuboid(cuboid) => Some(cuboid.compute_local_aabb()),
+            Shape::Capsule(capsule) => Some(capsule.compute_local_aabb()),
+            Shape::Cylinder(cylinder) => Some(cylinder.compute_local_aabb()),
+            Shape::ConvexPolyhedron(polyhedron) => Some(polyhedron.compute_local_aabb()),
+            Shape::Ellipsoid(ellipsoid) => {
+                Some(Aabb::new((-ellipsoid.radii).into(), ellipsoid.radii.into()))
+            }
+            Shape::RoundCuboid(cuboid) => Some(cuboid.compute_local_aabb()),
+            Shape::HalfSpace(_) => None,
+        }
+    }
+
     fn cast_ray_and_get_normal(
         &self,
         isometry: &Isometry<f32>,
         ray: &Ray,
+        solid: bool,
     ) -> Option<RayIntersection> {
         match self {
-            Shape::Ball(ball) => ball.cast_ray_and_get_normal(isometry, ray, Real::MAX, true),
-            Shape::Cuboid(cuboid) => cuboid.cast_ray_and_get_normal(isometry, ray, Real::MAX, true),
+            Shape::Ball(ball) => ball.cast_ray_and_get_normal(isometry, ray, Real::MAX, solid),
+            Shape::Cuboid(cuboid) => {
+                cuboid.cast_ray_and_get_normal(isometry, ray, Real::MAX, solid)
+            }
             Shape::Capsule(capsule) => {
-                capsule.cast_ray_and_get_normal(isometry, ray, Real::MAX, true)
+                capsule.cast_ray_and_get_normal(isometry, ray, Real::MAX, solid)
+            }
+            Shape::Cylinder(cylinder) => {
+                cylinder.cast_ray_and_get_normal(isometry, ray, Real::MAX, solid)
             }
             Shape::RoundCuboid(cuboid) => {
-                cuboid.cast_ray_and_get_normal(isometry, ray, Real::MAX, true)
+                cuboid.cast_ray_and_get_normal(isometry, ray, Real::MAX, solid)
             }
+            Shape::ConvexPolyhedron(polyhedron) => {
+                polyhedron.cast_ray_and_get_normal(isometry, ray, Real::MAX, solid)
+            }
+            Shape::Ellipsoid(ellipsoid) => ellipsoid.cast_ray(isometry, ray, solid),
             Shape::HalfSpace(half_space) => {
-                half_space.cast_ray_and_get_normal(isometry, ray, Real::MAX, true)
+                half_space.cast_ray_and_get_normal(isometry, ray, Real::MAX, solid)
             }
         }
     }
 }
 
+fn spherical_uv(direction: &Vector<f32>) -> (Point2<f32>, Vector<f32>) {
+    let direction = direction.normalize();
+    (
+        [
+            0.5 + direction.y.atan2(direction.x) / (2.0 * PI),
+            0.5 + direction.z.clamp(-1.0, 1.0).asin() / PI,
+        ]
+        .into(),
+        Vector::new(-direction.y, direction.x, 0.0),
+    )
+}
+
+fn box_uv(
+    point: &Point<f32>,
+    normal: &Vector<f32>,
+    half_extents: &Vector<f32>,
+) -> (Point2<f32>, Vector<f32>) {
+    let axis = normal.iamax();
+    let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+    let mut tangent = Vector::zeros();
+    tangent[a] = 1.0;
+    (
+        [
+            0.5 + 0.5 * point[a] / half_extents[a],
+            0.5 + 0.5 * point[b] / half_extents[b],
+        ]
+        .into(),
+        tangent,
+    )
+}
+
 #[derive(Default, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Brdf {
+    /// Lambertian reflection, twice as bright as `One`
     Diffuse,
+    /// perfect specular reflection in the color of the surface, ignores `Object::roughness`
     Mirror,
+    /// a highlight that narrows as `Object::roughness` decreases
     Glossy,
     #[default]
     One,
@@ -177,17 +1527,33 @@ pub enum Brdf {
 impl Brdf {
     /// computes the given brdf
     /// fullfills po
-    fn apply(&self, in_comming: Vector<f32>, out_going: Vector<f32>) -> f32 {
+    fn apply(
+        &self,
+        in_comming: Vector<f32>,
+        out_going: Vector<f32>,
+        normal: Vector<f32>,
+        roughness: f32,
+    ) -> f32 {
         match self {
-            Brdf::Diffuse => todo!(),
-            Brdf::Mirror => todo!(),
-            Brdf::Glossy => todo!(),
+            // 1 / pi relative to `HEMISPHERE_PDF`
+            Brdf::Diffuse => 2.0,
+            // no sampled direction hits the reflection, `Scene::shade` traces it instead
+            Brdf::Mirror => 0.0,
+            Brdf::Glossy => {
+                // a normalized Blinn-Phong lobe with the exponent matching the roughness,
+                // relative to `HEMISPHERE_PDF`, polished surfaces need many rays
+                let alpha = roughness.clamp(0.1, 1.0).powi(2);
+                let exponent = 2.0 / (alpha * alpha) - 2.0;
+                let half = (out_going - in_comming).normalize();
+                (exponent + 2.0) / 4.0 * normal.dot(&half).max(0.0).powf(exponent)
+            }
             Brdf::One => 1.0,
         }
     }
 }
 
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Color(pub f32, pub f32, pub f32);
 
 impl Mul for Color {
@@ -218,6 +1584,15 @@ impl Color {
     pub fn scale(&self, factor: f32) -> Self {
         Self(self.0 * factor, self.1 * factor, self.2 * factor)
     }
+
+    /// the relative luminance with Rec. 709 weights
+    pub fn luminance(&self) -> f32 {
+        0.2126 * self.0 + 0.7152 * self.1 + 0.0722 * self.2
+    }
+
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        self.scale(1.0 - t) + other.scale(t)
+    }
 }
 
 impl From<Color> for Rgb<u8> {
@@ -229,3 +1604,221 @@ impl From<Color> for Rgb<u8> {
         ])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use parry3d::{bounding_volume::Aabb, shape::Cuboid};
+
+    use super::*;
+    use crate::medium::DensityGrid;
+
+    #[test]
+    fn density_grids_move_with_their_object() {
+        let _seeded = utils::seed_rng(3);
+        let bounds = Aabb::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0));
+        let grid = DensityGrid::from_fn([4, 4, 4], bounds, |_| 1.0);
+        let medium = Medium::heterogeneous(1.0, 0.0, Color(1.0, 1.0, 1.0), 0.0, grid);
+        let fog = Object::new(
+            Shape::Cuboid(Cuboid::new(Vector::new(1.0, 1.0, 1.0))),
+            Isometry::translation(10.0, 0.0, 0.0),
+            Color(1.0, 1.0, 1.0),
+        )
+        .with_medium(medium);
+        let scene = Scene::new(Color(0.0, 0.0, 0.0)).add_object(fog);
+        let through = |origin: Point<f32>| {
+            let ray = Ray::new(origin, Vector::z());
+            let samples = 4000;
+            (0..samples)
+                .map(|_| scene.transmittance(&ray, 100.0, &[], 0.0))
+                .sum::<f32>()
+                / samples as f32
+        };
+        // the box is two units deep
+        let moved = through(Point::new(10.0, 0.0, -5.0));
+        assert!((moved - (-2.0_f32).exp()).abs() < 0.02, "{}", moved);
+        assert_eq!(through(Point::new(0.0, 0.0, -5.0)), 1.0);
+    }
+
+    #[test]
+    fn instanced_hits_match_the_flattened_objects() {
+        let _seeded = utils::seed_rng(11);
+        let cell = || {
+            vec![
+                Object::new(
+                    Shape::Ball(Ball::new(0.5)),
+                    Isometry::translation(0.5, 0.0, 0.0),
+                    Color(1.0, 0.0, 0.0),
+                ),
+                Object::new(
+                    Shape::Cuboid(Cuboid::new(Vector::new(0.2, 0.6, 0.3))),
+                    Isometry::new(Vector::new(-0.5, 0.5, 0.0), Vector::new(0.3, 0.0, 0.7)),
+                    Color(0.0, 1.0, 0.0),
+                ),
+            ]
+        };
+        let ground = || {
+            Object::new(
+                Shape::Cuboid(Cuboid::new(Vector::new(10.0, 10.0, 0.1))),
+                Isometry::translation(4.0, 4.0, -2.0),
+                Color(1.0, 1.0, 1.0),
+            )
+        };
+        let mut scene = Scene::new(Color(0.0, 0.0, 0.0))
+            .add_object(ground())
+            .add_prototype(Prototype::new("cell", cell()));
+        let mut flattened = vec![ground()];
+        for i in 0..25 {
+            let random = || utils::random::<f32>() * 2.0 - 1.0;
+            let rotation = Vector::new(random(), random(), random()) * PI;
+            let translation = Vector::new((i % 5) as f32 * 2.0, (i / 5) as f32 * 2.0, 0.0);
+            let isometry = Isometry::new(translation, rotation);
+            scene = scene.add_instance(Instance::new("cell", isometry)).unwrap();
+            flattened.extend(cell().into_iter().map(|mut object| {
+                object.isometry = isometry * object.isometry;
+                object
+            }));
+        }
+        for _ in 0..1000 {
+            let origin = Point::new(4.0, 4.0, 6.0);
+            let target = Point::new(
+                utils::random::<f32>() * 10.0 - 1.0,
+                utils::random::<f32>() * 10.0 - 1.0,
+                0.0,
+            );
+            let ray = Ray::new(origin, (target - origin).normalize());
+            let expected = flattened
+                .iter()
+                .filter_map(|object| object.get_intersection(&ray, 0.0))
+                .filter(|intersection| intersection.toi > 0.0)
+                .min_by(|a, b| a.toi.total_cmp(&b.toi));
+            let found = scene.closest_intersection(&ray, 0.0);
+            match (expected, found) {
+                (None, None) => {}
+                (Some(expected), Some(hit)) => {
+                    let found = hit.intersection;
+                    assert!((expected.toi - found.toi).abs() < 1e-4, "{:?}", ray);
+                    assert!((expected.normal - found.normal).norm() < 1e-4, "{:?}", ray);
+                }
+                (expected, found) => panic!(
+                    "{:?} hit {:?} instead of {:?}",
+                    ray,
+                    found.map(|hit| hit.intersection),
+                    expected
+                ),
+            }
+        }
+        let unknown = Instance::new("cage", Isometry::identity());
+        assert_eq!(
+            scene.add_instance(unknown).err(),
+            Some(UnknownPrototype("cage".into()))
+        );
+    }
+
+    #[test]
+    fn packets_find_the_closest_hits_of_single_rays() {
+        let _seeded = utils::seed_rng(13);
+        let random = |scale: f32| (utils::random::<f32>() * 2.0 - 1.0) * scale;
+        let mut scene = Scene::new(Color(0.0, 0.0, 0.0))
+            .add_object(Object::new(
+                Shape::Cuboid(Cuboid::new(Vector::new(1.0, 2.0, 0.5))),
+                Isometry::new(Vector::new(1.0, -2.0, 0.0), Vector::new(0.4, 0.2, 0.0)),
+                Color(1.0, 1.0, 1.0),
+            ))
+            .add_object(Object::new(
+                Shape::Cylinder(Cylinder::new(1.5, 0.7)),
+                Isometry::new(Vector::new(-3.0, 2.0, 1.0), Vector::new(0.0, 0.0, 1.0)),
+                Color(1.0, 1.0, 1.0),
+            ))
+            .add_prototype(Prototype::new(
+                "pair",
+                vec![
+                    Object::new(
+                        Shape::Ball(Ball::new(0.4)),
+                        Isometry::translation(0.5, 0.0, 0.0),
+                        Color(1.0, 1.0, 1.0),
+                    ),
+                    Object::new(
+                        Shape::Cuboid(Cuboid::new(Vector::new(0.3, 0.3, 0.3))),
+                        Isometry::translation(-0.5, 0.0, 0.0),
+                        Color(1.0, 1.0, 1.0),
+                    ),
+                ],
+            ))
+            .add_instance(Instance::new(
+                "pair",
+                Isometry::new(Vector::new(0.0, 0.0, -3.0), Vector::new(0.0, 1.0, 0.0)),
+            ))
+            .unwrap();
+        for _ in 0..120 {
+            let center = Vector::new(random(4.0), random(4.0), random(4.0));
+            scene = scene.add_object(Object::new(
+                Shape::Ball(Ball::new(0.2 + utils::random::<f32>() * 0.6)),
+                Isometry::translation(center.x, center.y, center.z),
+                Color(1.0, 1.0, 1.0),
+            ));
+        }
+        let rays: Vec<Ray> = (0..200)
+            .map(|_| {
+                let origin = Point::new(random(2.0), random(2.0), 12.0);
+                let target = Point::new(random(4.0), random(4.0), random(4.0));
+                Ray::new(origin, (target - origin).normalize())
+            })
+            .collect();
+        // every length from a single ray to a full packet
+        let (mut start, mut found) = (0, 0);
+        for len in (1..=8).cycle() {
+            let Some(rays) = rays.get(start..start + len) else {
+                break;
+            };
+            start += len;
+            let times = vec![0.0; len];
+            let hits = scene.closest_intersections::<8>(&RayPacket::new(rays, &times));
+            for (ray, hit) in rays.iter().zip(hits) {
+                let single = scene.closest_intersection(ray, 0.0);
+                match (single, hit) {
+                    (None, None) => {}
+                    (Some(single), Some(hit)) => {
+                        found += 1;
+                        assert!(std::ptr::eq(single.object, hit.object), "{:?}", ray);
+                        let (a, b) = (single.intersection, hit.intersection);
+                        assert!((a.toi - b.toi).abs() < 1e-4, "{:?}", ray);
+                        assert!((a.normal - b.normal).norm() < 1e-4, "{:?}", ray);
+                    }
+                    (single, hit) => panic!(
+                        "{:?} hit {:?} in a packet but {:?} alone",
+                        ray,
+                        hit.map(|hit| hit.intersection),
+                        single.map(|hit| hit.intersection)
+                    ),
+                }
+            }
+        }
+        assert!(found > rays.len() / 2, "{}", found);
+    }
+
+    #[test]
+    fn mirrors_reflect_in_their_color() {
+        let mirror = Object::new(
+            Shape::Cuboid(Cuboid::new(Vector::new(5.0, 5.0, 0.1))),
+            Isometry::identity(),
+            Color(0.5, 0.25, 1.0),
+        )
+        .with_brdf(Brdf::Mirror);
+        let light = Object::new(
+            Shape::Ball(Ball::new(1.0)),
+            Isometry::translation(0.0, 0.0, 10.0),
+            Color(2.0, 2.0, 2.0),
+        )
+        .with_light_source();
+        let scene = Scene::new(Color(0.0, 0.0, 0.0))
+            .add_object(mirror)
+            .add_object(light);
+        let trace = |direction: Vector<f32>| {
+            let ray = Ray::new(Point::new(0.0, 0.0, 5.0), direction.normalize());
+            scene.trace(&ray, 1, 3, &[], None, 0.0)
+        };
+        assert_eq!(trace(-Vector::z()), Color(1.0, 0.5, 2.0));
+        // the reflection misses the light
+        assert_eq!(trace(Vector::new(1.0, 0.0, -1.0)), Color(0.0, 0.0, 0.0));
+    }
+}
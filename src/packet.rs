@@ -0,0 +1,210 @@
+//! Packets of rays traced together through the acceleration structure.
+//!
+//! The rays of a packet are stored as a structure of arrays with one lane per ray, so a bounding
+//! box or a sphere is tested against all lanes at once in code the compiler turns into SIMD
+//! instructions. A node of a hierarchy is visited if any lane passes through its bounds, which
+//! pays off for coherent rays like the samples of a pixel through a lens or the rays leaving a
+//! point of a surface. Spheres are kept as a separate structure of arrays, all other shapes are
+//! cast against lane by lane.
+
+use parry3d::{
+    bounding_volume::Aabb,
+    math::{Isometry, Point, Real, Vector},
+    query::{Ray, RayIntersection},
+    shape::FeatureId,
+};
+
+use crate::objects::{Object, Shape};
+
+/// How many rays are traced together, `Scalar` traces every ray on its own.
+///
+/// Packets are opt-in, their gain depends on the scene and the machine, see `benches/packets.rs`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PacketWidth {
+    #[default]
+    Scalar,
+    Four,
+    Eight,
+    Sixteen,
+}
+
+/// Up to `N` rays, lanes without a ray never hit anything.
+#[derive(Debug, Clone, Copy)]
+pub struct RayPacket<const N: usize> {
+    pub origin: [[Real; N]; 3],
+    pub dir: [[Real; N]; 3],
+    /// the component-wise inverse of the direction for the slab tests against bounding boxes
+    inv_dir: [[Real; N]; 3],
+    /// the distance of the closest hit found so far, negative for lanes without a ray
+    pub toi: [Real; N],
+    /// the fraction of the frame interval at which each ray travels
+    pub time: [f32; N],
+    /// the number of lanes with a ray, they come first
+    len: usize,
+}
+
+impl<const N: usize> RayPacket<N> {
+    /// `rays` and `times` can be shorter than the packet but not longer
+    pub fn new(rays: &[Ray], times: &[f32]) -> Self {
+        assert!(rays.len() <= N && times.len() == rays.len());
+        let mut packet = Self {
+            origin: [[0.0; N]; 3],
+            dir: [[0.0; N]; 3],
+            inv_dir: [[0.0; N]; 3],
+            toi: [-1.0; N],
+            time: [0.0; N],
+            len: rays.len(),
+        };
+        for (lane, (ray, time)) in rays.iter().zip(times).enumerate() {
+            packet.set_ray(lane, ray);
+            packet.toi[lane] = Real::MAX;
+            packet.time[lane] = *time;
+        }
+        packet
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn ray(&self, lane: usize) -> Ray {
+        Ray::new(
+            Point::new(
+                self.origin[0][lane],
+                self.origin[1][lane],
+                self.origin[2][lane],
+            ),
+            Vector::new(self.dir[0][lane], self.dir[1][lane], self.dir[2][lane]),
+        )
+    }
+
+    fn set_ray(&mut self, lane: usize, ray: &Ray) {
+        for axis in 0..3 {
+            self.origin[axis][lane] = ray.origin[axis];
+            self.dir[axis][lane] = ray.dir[axis];
+            self.inv_dir[axis][lane] = 1.0 / ray.dir[axis];
+        }
+    }
+
+    /// the rays moved into the local space of `isometry`, which gives the isometry of a lane
+    pub fn inverse_transform_by(&self, isometry: impl Fn(usize) -> Isometry<f32>) -> Self {
+        let mut packet = *self;
+        for lane in 0..self.len {
+            packet.set_ray(lane, &self.ray(lane).inverse_transform_by(&isometry(lane)));
+        }
+        packet
+    }
+
+    /// the sum of the directions, the packet mostly travels along it
+    pub fn direction(&self) -> Vector<Real> {
+        Vector::new(
+            self.dir[0].iter().sum(),
+            self.dir[1].iter().sum(),
+            self.dir[2].iter().sum(),
+        )
+    }
+
+    /// whether any ray passes through the box before its closest hit so far
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.cast_aabb(aabb).contains(&true)
+    }
+
+    /// which rays pass through the box before their closest hit so far
+    pub fn cast_aabb(&self, aabb: &Aabb) -> [bool; N] {
+        let mut hit = [false; N];
+        for (lane, hit) in hit.iter_mut().enumerate() {
+            let mut entry: Real = 0.0;
+            let mut exit = self.toi[lane];
+            for axis in 0..3 {
+                let a = (aabb.mins[axis] - self.origin[axis][lane]) * self.inv_dir[axis][lane];
+                let b = (aabb.maxs[axis] - self.origin[axis][lane]) * self.inv_dir[axis][lane];
+                entry = entry.max(a.min(b));
+                exit = exit.min(a.max(b));
+            }
+            *hit = entry <= exit;
+        }
+        hit
+    }
+}
+
+/// The solid spheres among objects as a structure of arrays indexed like the objects, other
+/// objects have a radius of zero.
+#[derive(Debug, Clone, Default)]
+pub struct Spheres {
+    center: [Vec<Real>; 3],
+    radius: Vec<Real>,
+}
+
+impl Spheres {
+    /// moving spheres and the ones rays can enter are left out
+    pub fn new(objects: &[Object]) -> Self {
+        let mut spheres = Self::default();
+        for object in objects {
+            let radius = match &object.shape {
+                Shape::Ball(ball)
+                    if object.end_isometry.is_none()
+                        && object.medium.is_none()
+                        && object.opacity >= 1.0 =>
+                {
+                    ball.radius
+                }
+                _ => 0.0,
+            };
+            for axis in 0..3 {
+                spheres.center[axis].push(object.isometry.translation.vector[axis]);
+            }
+            spheres.radius.push(radius);
+        }
+        spheres
+    }
+
+    /// whether the object with the index is one of the spheres
+    pub fn contains(&self, i: usize) -> bool {
+        self.radius[i] > 0.0
+    }
+
+    /// the distance at which a ray enters the sphere with the index, `None` if it misses it
+    /// or starts inside of it
+    pub fn cast(&self, i: usize, ray: &Ray) -> Option<Real> {
+        let center = Point::new(self.center[0][i], self.center[1][i], self.center[2][i]);
+        let offset = ray.origin - center;
+        let a = ray.dir.norm_squared();
+        let b = offset.dot(&ray.dir);
+        let c = offset.norm_squared() - self.radius[i] * self.radius[i];
+        let discriminant = b * b - a * c;
+        let toi = (-b - discriminant.sqrt()) / a;
+        (discriminant >= 0.0 && toi > 0.0).then_some(toi)
+    }
+
+    /// shortens the lanes of the packet hitting the sphere with the index before their closest
+    /// hit so far and returns which ones did
+    pub fn cast_packet<const N: usize>(&self, i: usize, packet: &mut RayPacket<N>) -> [bool; N] {
+        let center = [self.center[0][i], self.center[1][i], self.center[2][i]];
+        let radius2 = self.radius[i] * self.radius[i];
+        let mut hit = [false; N];
+        for (lane, hit) in hit.iter_mut().enumerate() {
+            let offset = [0, 1, 2].map(|axis| packet.origin[axis][lane] - center[axis]);
+            let dir = [0, 1, 2].map(|axis| packet.dir[axis][lane]);
+            let a = dir[0] * dir[0] + dir[1] * dir[1] + dir[2] * dir[2];
+            let b = offset[0] * dir[0] + offset[1] * dir[1] + offset[2] * dir[2];
+            let c = offset[0] * offset[0] + offset[1] * offset[1] + offset[2] * offset[2] - radius2;
+            let discriminant = b * b - a * c;
+            let toi = (-b - discriminant.sqrt()) / a;
+            *hit = discriminant >= 0.0 && toi > 0.0 && toi < packet.toi[lane];
+            packet.toi[lane] = if *hit { toi } else { packet.toi[lane] };
+        }
+        hit
+    }
+
+    /// the intersection of a ray with the sphere with the index at the distance `toi`
+    pub fn intersection(&self, i: usize, ray: &Ray, toi: Real) -> RayIntersection {
+        let center = Point::new(self.center[0][i], self.center[1][i], self.center[2][i]);
+        let normal = (ray.point_at(toi) - center).normalize();
+        RayIntersection::new(toi, normal, FeatureId::Face(0))
+    }
+}
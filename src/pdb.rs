@@ -0,0 +1,175 @@
+//! Reading Protein Data Bank files.
+//!
+//! Only the first model is read. Bonds come from the `CONECT` records, the cell from `CRYST1`.
+
+use std::collections::HashMap;
+
+use parry3d::math::Point;
+
+use crate::{
+    bonds::Bond,
+    crystal::Lattice,
+    elements,
+    structure::{Structure, StructureError},
+};
+
+impl Structure {
+    pub fn from_pdb(source: &str) -> Result<Self, StructureError> {
+        let mut structure = Structure::default();
+        // atom serial numbers to indices
+        let mut serials = HashMap::new();
+        let mut bonds = Vec::new();
+        for (i, line) in source.lines().enumerate() {
+            let number = i + 1;
+            let record = column(line, 1, 6);
+            let parse = |start: usize, end: usize, name: &str| {
+                let value = column(line, start, end);
+                value.parse::<f32>().map_err(|_| StructureError::Syntax {
+                    line: number,
+                    message: format!("invalid {} `{}`", name, value),
+                })
+            };
+            match record {
+                "ATOM" | "HETATM" => {
+                    let serial = column(line, 7, 11);
+                    let element = match column(line, 77, 78) {
+                        // the element is right-justified in the first two columns of the name
+                        "" => elements::parse_symbol(
+                            line.get(12..14)
+                                .unwrap_or_default()
+                                .trim_start_matches(|c: char| !c.is_ascii_alphabetic()),
+                        ),
+                        element => elements::parse_symbol(element),
+                    };
+                    if let Ok(serial) = serial.parse::<usize>() {
+                        serials.insert(serial, structure.len());
+                    }
+                    structure.species.push(element);
+                    structure.positions.push(Point::new(
+                        parse(31, 38, "x")?,
+                        parse(39, 46, "y")?,
+                        parse(47, 54, "z")?,
+                    ));
+                }
+                "CRYST1" => {
+                    let lengths = [parse(7, 15, "a")?, parse(16, 24, "b")?, parse(25, 33, "c")?];
+                    let angles = [
+                        parse(34, 40, "alpha")?,
+                        parse(41, 47, "beta")?,
+                        parse(48, 54, "gamma")?,
+                    ];
+                    // placeholder cells of structures that are not crystals
+                    if lengths != [1.0; 3] {
+                        structure.lattice = Some(Lattice::from_parameters(
+                            lengths,
+                            angles.map(f32::to_radians),
+                        ));
+                    }
+                }
+                "CONECT" => {
+                    let serial = |start: usize| column(line, start, start + 4).parse::<usize>();
+                    let Ok(from) = serial(7) else {
+                        return Err(StructureError::Syntax {
+                            line: number,
+                            message: "invalid atom serial number".into(),
+                        });
+                    };
+                    for start in [12, 17, 22, 27] {
+                        if let Ok(to) = serial(start) {
+                            bonds.push((from, to));
+                        }
+                    }
+                }
+                "ENDMDL" | "END" => break,
+                _ => (),
+            }
+        }
+
+        if !bonds.is_empty() {
+            let mut indices: Vec<Bond> = bonds
+                .into_iter()
+                .filter_map(|(from, to)| {
+                    let (from, to) = (*serials.get(&from)?, *serials.get(&to)?);
+                    Some(Bond::new(from.min(to), from.max(to)))
+                })
+                .collect();
+            // every bond is usually listed from both atoms
+            indices.sort_unstable();
+            indices.dedup();
+            structure.bonds = Some(indices);
+        }
+        Ok(structure)
+    }
+}
+
+/// the trimmed text between the one-based inclusive columns
+fn column(line: &str, start: usize, end: usize) -> &str {
+    line.get(start - 1..end.min(line.len()))
+        .unwrap_or_default()
+        .trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METHANOL: &str = "\
+HEADER    METHANOL
+CRYST1    1.000    1.000    1.000  90.00  90.00  90.00 P 1           1
+HETATM    1  C   MOH A   1       0.000   0.000   0.000  1.00  0.00           C
+HETATM    2  O   MOH A   1       1.430   0.000   0.000  1.00  0.00           O
+HETATM    3  H   MOH A   1      -0.360   1.030   0.000  1.00  0.00
+HETATM    4 CA   CAL B   2       5.000   5.000   5.000  1.00  0.00
+CONECT    1    2    3
+CONECT    2    1
+END
+HETATM    5  C   MOH A   1       9.000   9.000   9.000  1.00  0.00           C
+";
+
+    #[test]
+    fn reads_atoms_of_the_first_model() {
+        let structure = Structure::from_pdb(METHANOL).unwrap();
+        assert_eq!(structure.species, ["C", "O", "H", "Ca"]);
+        assert_eq!(structure.positions[2], Point::new(-0.36, 1.03, 0.0));
+        // the placeholder cell is not a lattice
+        assert!(structure.lattice.is_none());
+    }
+
+    #[test]
+    fn reads_each_bond_once() {
+        let structure = Structure::from_pdb(METHANOL).unwrap();
+        assert_eq!(structure.bonds.unwrap(), [Bond::new(0, 1), Bond::new(0, 2)]);
+    }
+
+    #[test]
+    fn reads_the_cell() {
+        let source = "\
+CRYST1    5.000    6.000    7.000  90.00  90.00 120.00 P 1           1
+ATOM      1  N   ALA A   1       1.000   2.000   3.000  1.00  0.00           N
+";
+        let structure = Structure::from_pdb(source).unwrap();
+        let [a, b, c] = structure.lattice.unwrap().vectors;
+        assert!((a.norm() - 5.0).abs() < 1e-4);
+        assert!((b.norm() - 6.0).abs() < 1e-4);
+        assert!((c.norm() - 7.0).abs() < 1e-4);
+        assert!((a.angle(&b).to_degrees() - 120.0).abs() < 1e-3);
+        assert!(structure.bonds.is_none());
+    }
+
+    #[test]
+    fn reports_invalid_coordinates() {
+        let source =
+            "ATOM      1  N   ALA A   1       1.000   two     3.000  1.00  0.00           N";
+        let Err(StructureError::Syntax { line, message }) = Structure::from_pdb(source) else {
+            panic!("read an invalid coordinate");
+        };
+        assert_eq!((line, message.as_str()), (1, "invalid y `two`"));
+    }
+
+    #[test]
+    fn columns_are_one_based_and_trimmed() {
+        assert_eq!(column("ATOM      1", 7, 11), "1");
+        assert_eq!(column("ATOM", 7, 11), "");
+        assert_eq!(column("END", 1, 6), "END");
+    }
+}
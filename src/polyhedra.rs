@@ -0,0 +1,148 @@
+//! Coordination polyhedra, the convex hulls of the ligands around centre atoms.
+
+use std::collections::{BTreeSet, HashMap};
+
+use parry3d::{
+    math::{Isometry, Point},
+    shape::ConvexPolyhedron,
+};
+
+use crate::{
+    elements::PeriodicTable,
+    objects::{Color, Object, Shape},
+    structure::{self, Structure},
+};
+
+/// How the polyhedra around the atoms of one species are found and drawn.
+#[derive(Debug, Clone)]
+pub struct PolyhedronStyle {
+    /// atoms closer to the centre than this in Å are ligands
+    pub cutoff: f32,
+    /// the species that can be ligands, every species other than the centre's if empty
+    pub ligands: Vec<String>,
+    /// the color of the faces, `None` takes the color of the centre
+    pub color: Option<Color>,
+    pub opacity: f32,
+    /// the radius of the cylinders along the edges, no outline if `None`
+    pub edge_radius: Option<f32>,
+    pub edge_color: Color,
+}
+
+impl Default for PolyhedronStyle {
+    fn default() -> Self {
+        Self {
+            cutoff: 2.5,
+            ligands: Vec::new(),
+            color: None,
+            opacity: 0.5,
+            edge_radius: Some(0.04),
+            edge_color: Color(0.2, 0.2, 0.2),
+        }
+    }
+}
+
+/// The species that are centres of coordination polyhedra and their styles.
+#[derive(Debug, Clone, Default)]
+pub struct CoordinationPolyhedra {
+    styles: HashMap<String, PolyhedronStyle>,
+}
+
+impl CoordinationPolyhedra {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// draws a polyhedron around every atom of the species
+    pub fn with_centre(mut self, species: &str, style: PolyhedronStyle) -> Self {
+        self.styles.insert(species.into(), style);
+        self
+    }
+
+    pub fn style(&self, species: &str) -> Option<&PolyhedronStyle> {
+        self.styles.get(species)
+    }
+}
+
+impl Structure {
+    /// the positions of the ligands of an atom, including periodic images
+    pub fn ligands(&self, centre: usize, style: &PolyhedronStyle) -> Vec<Point<f32>> {
+        let is_ligand = |species: &String| match style.ligands.is_empty() {
+            true => *species != self.species[centre],
+            false => style.ligands.contains(species),
+        };
+        let mut ligands = Vec::new();
+        for image in self.images() {
+            for (j, species) in self.species.iter().enumerate() {
+                if !is_ligand(species) {
+                    continue;
+                }
+                let position = self.image_position(j, image);
+                let distance = (position - self.positions[centre]).norm();
+                if distance > 0.0 && distance <= style.cutoff {
+                    ligands.push(position);
+                }
+            }
+        }
+        ligands
+    }
+
+    /// semi-transparent convex hulls around the centres and the cylinders outlining them
+    pub fn polyhedra(
+        &self,
+        polyhedra: &CoordinationPolyhedra,
+        table: &PeriodicTable,
+    ) -> Vec<Object> {
+        let mut objects = Vec::new();
+        for (i, (species, centre)) in self.species.iter().zip(&self.positions).enumerate() {
+            let Some(style) = polyhedra.style(species) else {
+                continue;
+            };
+            let points: Vec<Point<f32>> = self
+                .ligands(i, style)
+                .iter()
+                .map(|ligand| Point::from(ligand - centre))
+                .collect();
+            // fewer than four ligands or ligands in a plane do not enclose a volume
+            if points.len() < 4 {
+                continue;
+            }
+            let Some(hull) = ConvexPolyhedron::from_convex_hull(&points) else {
+                continue;
+            };
+
+            if let Some(radius) = style.edge_radius {
+                // the edges between coplanar triangles of the hull are not on a face boundary
+                let edges: BTreeSet<u32> = hull
+                    .faces()
+                    .iter()
+                    .flat_map(|face| {
+                        let first = face.first_vertex_or_edge as usize;
+                        hull.edges_adj_to_face()[first..first + face.num_vertices_or_edges as usize]
+                            .iter()
+                            .copied()
+                    })
+                    .collect();
+                for edge in edges {
+                    let vertices = hull.edges()[edge as usize].vertices;
+                    let [a, b] = [vertices.x, vertices.y].map(|v| hull.points()[v as usize]);
+                    objects.extend(structure::cylinder(
+                        centre + a.coords,
+                        centre + b.coords,
+                        radius,
+                        style.edge_color,
+                    ));
+                }
+            }
+
+            objects.push(
+                Object::new(
+                    Shape::ConvexPolyhedron(hull),
+                    Isometry::translation(centre.x, centre.y, centre.z),
+                    style.color.unwrap_or_else(|| table.color(species)),
+                )
+                .with_opacity(style.opacity),
+            );
+        }
+        objects
+    }
+}
@@ -0,0 +1,181 @@
+//! Reading VASP POSCAR and CONTCAR files.
+//!
+//! Files without a line of species names, as written by VASP 4, take the species from the
+//! comment line.
+
+use parry3d::math::Vector;
+
+use crate::{
+    crystal::Lattice,
+    structure::{self, Fields, Structure, StructureError},
+};
+
+impl Structure {
+    pub fn from_poscar(source: &str) -> Result<Self, StructureError> {
+        let lines: Vec<&str> = source.lines().collect();
+        let mut index = 0;
+        let mut next = |name: &str| {
+            index += 1;
+            match lines.get(index - 1) {
+                Some(line) => Ok(Fields::new(index, line)),
+                None => Err(StructureError::Syntax {
+                    line: index,
+                    message: format!("missing {}", name),
+                }),
+            }
+        };
+
+        let comment = next("comment")?.rest();
+        let scale = next("scaling factor")?.number("scaling factor")?;
+        let mut vectors = [Vector::zeros(); 3];
+        for vector in &mut vectors {
+            let mut fields = next("lattice vector")?;
+            *vector = Vector::new(
+                fields.number("lattice vector")?,
+                fields.number("lattice vector")?,
+                fields.number("lattice vector")?,
+            );
+        }
+        // a negative scaling factor is the volume of the cell
+        let scale = if scale < 0.0 {
+            let volume = vectors[0].cross(&vectors[1]).dot(&vectors[2]).abs();
+            (-scale / volume).cbrt()
+        } else {
+            scale
+        };
+        let lattice = Lattice {
+            vectors: vectors.map(|vector| vector * scale),
+        };
+
+        let line = next("species")?;
+        let (names, counts_line, counts) = match line.clone().numbers::<usize>("atom count") {
+            Ok(counts) => (comment, line.line(), counts),
+            Err(_) => {
+                let names = line.rest();
+                let counts = next("atom counts")?;
+                (names, counts.line(), counts.numbers("atom count")?)
+            }
+        };
+        if counts.is_empty() || names.len() < counts.len() {
+            return Err(StructureError::Syntax {
+                line: counts_line,
+                message: "expected a species name for every atom count".into(),
+            });
+        }
+
+        let mut mode = next("coordinate mode")?.next("coordinate mode")?;
+        if mode.starts_with(['s', 'S']) {
+            // selective dynamics
+            mode = next("coordinate mode")?.next("coordinate mode")?;
+        }
+        let cartesian = mode.starts_with(['c', 'C', 'k', 'K']);
+
+        let mut structure = Structure {
+            lattice: Some(lattice),
+            ..Default::default()
+        };
+        for (name, &count) in names.iter().zip(&counts) {
+            for _ in 0..count {
+                let mut fields = next("position")?;
+                let position = Vector::new(
+                    fields.number("x")?,
+                    fields.number("y")?,
+                    fields.number("z")?,
+                );
+                structure.species.push(structure::species(name));
+                structure.positions.push(if cartesian {
+                    (position * scale).into()
+                } else {
+                    lattice.to_cartesian(&position)
+                });
+            }
+        }
+        Ok(structure)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parry3d::math::Point;
+
+    use super::*;
+
+    const RUTILE: &str = "\
+TiO2 rutile
+1.0
+4.594 0.0 0.0
+0.0 4.594 0.0
+0.0 0.0 2.959
+Ti O
+2 4
+Direct
+0.0 0.0 0.0
+0.5 0.5 0.5
+0.3 0.3 0.0
+0.7 0.7 0.0
+0.8 0.2 0.5
+0.2 0.8 0.5
+";
+
+    #[test]
+    fn reads_direct_coordinates() {
+        let structure = Structure::from_poscar(RUTILE).unwrap();
+        assert_eq!(structure.species, ["Ti", "Ti", "O", "O", "O", "O"]);
+        let position = structure.positions[1];
+        assert!((position - Point::new(2.297, 2.297, 1.4795)).norm() < 1e-5);
+        assert_eq!(
+            structure.lattice.unwrap().vectors[2],
+            Vector::new(0.0, 0.0, 2.959)
+        );
+    }
+
+    #[test]
+    fn reads_species_from_the_comment() {
+        let source = "\
+Si
+2.0
+0.0 1.0 1.0
+1.0 0.0 1.0
+1.0 1.0 0.0
+2
+Selective dynamics
+Cartesian
+0.0 0.0 0.0 T T T
+0.5 0.5 0.5 F F F
+";
+        let structure = Structure::from_poscar(source).unwrap();
+        assert_eq!(structure.species, ["Si", "Si"]);
+        assert_eq!(structure.positions[1], Point::new(1.0, 1.0, 1.0));
+        assert_eq!(
+            structure.lattice.unwrap().vectors[0],
+            Vector::new(0.0, 2.0, 2.0)
+        );
+    }
+
+    #[test]
+    fn negative_scale_is_the_volume() {
+        let source = "cube\n-27\n1 0 0\n0 1 0\n0 0 1\nFe\n1\nDirect\n0.5 0.5 0.5\n";
+        let structure = Structure::from_poscar(source).unwrap();
+        let lattice = structure.lattice.unwrap();
+        assert!((lattice.vectors[0].norm() - 3.0).abs() < 1e-5);
+        assert!((structure.positions[0] - Point::new(1.5, 1.5, 1.5)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn reports_missing_positions() {
+        let source = RUTILE.lines().take(12).collect::<Vec<_>>().join("\n");
+        let Err(StructureError::Syntax { line, message }) = Structure::from_poscar(&source) else {
+            panic!("read a file with missing positions");
+        };
+        assert_eq!((line, message.as_str()), (13, "missing position"));
+    }
+
+    #[test]
+    fn reports_counts_without_names() {
+        let source = RUTILE.replacen("Ti O", "Ti", 1);
+        let Err(StructureError::Syntax { line, .. }) = Structure::from_poscar(&source) else {
+            panic!("read more atom counts than species");
+        };
+        assert_eq!(line, 7);
+    }
+}
@@ -0,0 +1,266 @@
+//! Progressive rendering, refining the whole image in passes.
+//!
+//! Every pass adds the same number of samples to every pixel and the image shows the mean of all
+//! passes so far, so a long render can be looked at early and stopped if the framing is wrong.
+//! The noise of a pixel is estimated from how much the means of the single passes scatter, with
+//! adaptive sampling the pixels whose noise is low enough after a warm-up get no more samples,
+//! so flat backgrounds stop early while shadowed crevices keep being refined.
+
+use std::{path::PathBuf, time::Duration};
+
+use image::RgbImage;
+
+use crate::{objects::Color, tiles::Tile};
+
+/// When a progressive render stops and where it shows its progress.
+#[derive(Debug, Clone)]
+pub struct Progressive {
+    /// the samples per pixel added by every pass
+    pub pass_rays: usize,
+    /// the samples per pixel at which the render stops
+    pub target_rays: usize,
+    /// stops after the pass that used up the time
+    pub time_budget: Option<Duration>,
+    /// stops once the noise of the image is below it, see `Pass::noise`
+    pub noise_threshold: Option<f32>,
+    /// the image is saved here after every pass
+    pub preview: Option<PathBuf>,
+    /// only samples the pixels that are still noisy after a warm-up
+    pub adaptive: Option<Adaptive>,
+    /// a heat map of the samples per pixel is saved here after every pass, from black for
+    /// pixels without samples over red to white for the most samples
+    pub heatmap: Option<PathBuf>,
+}
+
+/// When a pixel of an adaptive render gets no more samples.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Adaptive {
+    /// the passes every pixel is sampled in before its noise is trusted, at least two
+    pub warm_up: usize,
+    /// the relative standard error of the brightness of a pixel below which it is done
+    pub threshold: f32,
+}
+
+impl Adaptive {
+    pub fn new(threshold: f32) -> Self {
+        Self {
+            warm_up: 4,
+            threshold,
+        }
+    }
+
+    pub fn with_warm_up(mut self, passes: usize) -> Self {
+        self.warm_up = passes;
+        self
+    }
+}
+
+impl Progressive {
+    pub fn new(pass_rays: usize, target_rays: usize) -> Self {
+        Self {
+            pass_rays,
+            target_rays,
+            time_budget: None,
+            noise_threshold: None,
+            preview: None,
+            adaptive: None,
+            heatmap: None,
+        }
+    }
+
+    pub fn with_time_budget(mut self, budget: Duration) -> Self {
+        self.time_budget = Some(budget);
+        self
+    }
+
+    pub fn with_noise_threshold(mut self, threshold: f32) -> Self {
+        self.noise_threshold = Some(threshold);
+        self
+    }
+
+    pub fn with_preview(mut self, path: impl Into<PathBuf>) -> Self {
+        self.preview = Some(path.into());
+        self
+    }
+
+    pub fn with_adaptive(mut self, adaptive: Adaptive) -> Self {
+        self.adaptive = Some(adaptive);
+        self
+    }
+
+    pub fn with_heatmap(mut self, path: impl Into<PathBuf>) -> Self {
+        self.heatmap = Some(path.into());
+        self
+    }
+
+    /// whether the render stops after the pass
+    pub fn is_done(&self, pass: &Pass) -> bool {
+        pass.active == 0
+            || self
+                .time_budget
+                .is_some_and(|budget| pass.elapsed >= budget)
+            || self
+                .noise_threshold
+                .zip(pass.noise)
+                .is_some_and(|(threshold, noise)| noise <= threshold)
+    }
+}
+
+/// The state of a progressive render after a pass.
+#[derive(Debug, Clone, Copy)]
+pub struct Pass {
+    /// counting from zero
+    pub index: usize,
+    /// the most samples a pixel got in all passes so far
+    pub rays: usize,
+    /// the samples per pixel on average, fewer than `rays` in adaptive renders
+    pub mean_rays: f32,
+    /// the pixels that are sampled in the next pass
+    pub active: usize,
+    /// the time since the render started
+    pub elapsed: Duration,
+    /// the mean relative standard error of the brightness of the pixels,
+    /// `None` after the first pass
+    pub noise: Option<f32>,
+}
+
+/// The running statistics of a pixel over the passes it was sampled in.
+#[derive(Debug, Clone, Copy, Default)]
+struct PixelStats {
+    /// the colors of the passes weighted by their samples
+    sum: Color,
+    rays: usize,
+    passes: usize,
+    /// the mean luminance of the passes and the sum of the squared deviations from it,
+    /// updated with Welford's algorithm
+    mean: f32,
+    deviations: f32,
+}
+
+impl PixelStats {
+    fn add(&mut self, color: Color, rays: usize) {
+        self.sum += color.scale(rays as f32);
+        self.rays += rays;
+        self.passes += 1;
+        let luminance = color.luminance();
+        let delta = luminance - self.mean;
+        self.mean += delta / self.passes as f32;
+        self.deviations += delta * (luminance - self.mean);
+    }
+
+    fn color(&self) -> Color {
+        self.sum.scale(1.0 / self.rays.max(1) as f32)
+    }
+
+    /// the standard error of the mean luminance relative to the mean, at least one step of the
+    /// 8 bit output so black pixels do not dominate it, `None` before the second pass
+    fn relative_error(&self) -> Option<f32> {
+        if self.passes < 2 {
+            return None;
+        }
+        let n = self.passes as f32;
+        let variance = self.deviations / (n - 1.0);
+        Some((variance / n).sqrt() / self.mean.max(1.0 / 255.0))
+    }
+}
+
+/// The samples of all passes so far.
+pub(crate) struct Accumulator {
+    width: u32,
+    height: u32,
+    pixels: Vec<PixelStats>,
+    passes: usize,
+}
+
+impl Accumulator {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![PixelStats::default(); width as usize * height as usize],
+            passes: 0,
+        }
+    }
+
+    pub fn passes(&self) -> usize {
+        self.passes
+    }
+
+    /// the most samples of a pixel, all pixels that are still sampled have that many
+    pub fn max_rays(&self) -> usize {
+        self.pixels
+            .iter()
+            .map(|pixel| pixel.rays)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn mean_rays(&self) -> f32 {
+        let total: usize = self.pixels.iter().map(|pixel| pixel.rays).sum();
+        total as f32 / self.pixels.len().max(1) as f32
+    }
+
+    /// which pixels are sampled in the next pass, row by row
+    pub fn active(&self, progressive: &Progressive) -> Vec<bool> {
+        self.pixels
+            .iter()
+            .map(|pixel| {
+                pixel.rays < progressive.target_rays
+                    && progressive.adaptive.is_none_or(|adaptive| {
+                        self.passes < adaptive.warm_up.max(2)
+                            || pixel
+                                .relative_error()
+                                .is_none_or(|error| error > adaptive.threshold)
+                    })
+            })
+            .collect()
+    }
+
+    /// adds the colors of the sampled pixels of a tile rendered with `rays` samples per pixel
+    /// and returns the mean of all pixels of the tile over all passes
+    pub fn add_tile(&mut self, tile: &Tile, colors: &[Option<Color>], rays: usize) -> Vec<Color> {
+        tile.pixels()
+            .zip(colors)
+            .map(|((x, y), color)| {
+                let pixel = &mut self.pixels[y as usize * self.width as usize + x as usize];
+                if let Some(color) = color {
+                    pixel.add(*color, rays);
+                }
+                pixel.color()
+            })
+            .collect()
+    }
+
+    /// ends a pass in which every tile was added
+    pub fn finish_pass(&mut self) {
+        self.passes += 1;
+    }
+
+    /// the mean relative standard error of the pixels
+    pub fn noise(&self) -> Option<f32> {
+        let total = self
+            .pixels
+            .iter()
+            .map(PixelStats::relative_error)
+            .sum::<Option<f32>>()?;
+        Some(total / self.pixels.len().max(1) as f32)
+    }
+
+    /// the samples per pixel, see `Progressive::heatmap`
+    pub fn heatmap(&self) -> RgbImage {
+        let max = self.max_rays().max(1) as f32;
+        let stops = [
+            Color(0.0, 0.0, 0.0),
+            Color(0.4, 0.0, 0.6),
+            Color(0.9, 0.1, 0.1),
+            Color(1.0, 0.8, 0.0),
+            Color(1.0, 1.0, 1.0),
+        ];
+        RgbImage::from_fn(self.width, self.height, |x, y| {
+            let rays = self.pixels[y as usize * self.width as usize + x as usize].rays;
+            let t = rays as f32 / max * (stops.len() - 1) as f32;
+            let i = (t as usize).min(stops.len() - 2);
+            stops[i].lerp(&stops[i + 1], t - i as f32).into()
+        })
+    }
+}
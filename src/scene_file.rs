@@ -0,0 +1,619 @@
+//! Loading and saving scenes as RON, JSON or TOML files.
+//!
+//! The format is chosen by the file extension. Image textures and environment maps are stored
+//! as the path they were opened from, relative paths are resolved from the directory of the
+//! scene file, or from the working directory for scenes parsed from strings.
+
+use std::{
+    cell::RefCell,
+    error::Error,
+    fmt::{self, Display},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use parry3d::{bounding_volume::Aabb, math::Vector};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de, ser};
+
+use crate::{
+    environment::EnvironmentMap,
+    medium::DensityGrid,
+    objects::Scene,
+    sky::{self, Sky},
+    texture::{ImageTexture, WrapMode},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Ron,
+    Json,
+    Toml,
+}
+
+impl Format {
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        match path.as_ref().extension()?.to_str()? {
+            "ron" => Some(Self::Ron),
+            "json" => Some(Self::Json),
+            "toml" => Some(Self::Toml),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SceneFileError {
+    Io(PathBuf, io::Error),
+    UnknownFormat(PathBuf),
+    /// invalid input, `line` and `column` start at one
+    Parse {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    Serialize(PathBuf, String),
+}
+
+impl Display for SceneFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneFileError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            SceneFileError::UnknownFormat(path) => write!(
+                f,
+                "{}: unknown scene format, expected a .ron, .json or .toml file",
+                path.display()
+            ),
+            SceneFileError::Parse {
+                path,
+                line,
+                column,
+                message,
+            } => write!(f, "{}:{}:{}: {}", path.display(), line, column, message),
+            SceneFileError::Serialize(path, message) => {
+                write!(f, "{}: {}", path.display(), message)
+            }
+        }
+    }
+}
+
+impl Error for SceneFileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SceneFileError::Io(_, err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl Scene {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SceneFileError> {
+        let path = path.as_ref();
+        let format =
+            Format::from_path(path).ok_or_else(|| SceneFileError::UnknownFormat(path.into()))?;
+        let source =
+            fs::read_to_string(path).map_err(|err| SceneFileError::Io(path.into(), err))?;
+        let _directory = in_directory(path);
+        Self::from_str(&source, format).map_err(|(line, column, message)| SceneFileError::Parse {
+            path: path.into(),
+            line,
+            column,
+            message,
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SceneFileError> {
+        let path = path.as_ref();
+        let format =
+            Format::from_path(path).ok_or_else(|| SceneFileError::UnknownFormat(path.into()))?;
+        let _directory = in_directory(path);
+        let source = self
+            .to_string(format)
+            .map_err(|message| SceneFileError::Serialize(path.into(), message))?;
+        fs::write(path, source).map_err(|err| SceneFileError::Io(path.into(), err))
+    }
+
+    /// parses a scene, errors are returned as `(line, column, message)`, instances of unknown
+    /// prototypes are reported where the name first appears
+    pub fn from_str(source: &str, format: Format) -> Result<Self, (usize, usize, String)> {
+        let scene = Self::parse(source, format)?;
+        if let Err(err) = scene.check_prototypes(scene.instances()) {
+            let offset = source
+                .find(&format!("\"{}\"", err.0))
+                .unwrap_or(source.len());
+            let (line, column) = line_column(source, offset);
+            return Err((line, column, err.to_string()));
+        }
+        Ok(scene)
+    }
+
+    fn parse(source: &str, format: Format) -> Result<Self, (usize, usize, String)> {
+        match format {
+            Format::Ron => ron::from_str(source)
+                .map_err(|err| (err.position.line, err.position.col, err.code.to_string())),
+            Format::Json => serde_json::from_str(source).map_err(|err| {
+                let message = err.to_string();
+                let message = match message.rfind(" at line ") {
+                    Some(idx) => message[..idx].to_string(),
+                    None => message,
+                };
+                (err.line(), err.column(), message)
+            }),
+            Format::Toml => toml::from_str(source).map_err(|err| {
+                let (line, column) = err
+                    .span()
+                    .map_or((1, 1), |span| line_column(source, span.start));
+                (line, column, err.message().to_string())
+            }),
+        }
+    }
+
+    pub fn to_string(&self, format: Format) -> Result<String, String> {
+        match format {
+            Format::Ron => {
+                let config = ron::ser::PrettyConfig::default()
+                    .compact_arrays(true)
+                    .extensions(ron::extensions::Extensions::IMPLICIT_SOME);
+                ron::ser::to_string_pretty(self, config).map_err(|err| err.to_string())
+            }
+            Format::Json => serde_json::to_string_pretty(self).map_err(|err| err.to_string()),
+            Format::Toml => toml::to_string(self).map_err(|err| err.to_string()),
+        }
+    }
+}
+
+thread_local! {
+    /// the directory of the scene file that is loaded or saved
+    static DIRECTORY: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Restores the previous directory of relative paths when dropped.
+struct InDirectory {
+    previous: Option<PathBuf>,
+}
+
+impl Drop for InDirectory {
+    fn drop(&mut self) {
+        DIRECTORY.set(self.previous.take());
+    }
+}
+
+/// resolves relative paths from the directory of the scene file at `path` until the guard is
+/// dropped
+fn in_directory(path: &Path) -> InDirectory {
+    let directory = path.parent().map(Path::to_path_buf);
+    InDirectory {
+        previous: DIRECTORY.replace(directory),
+    }
+}
+
+/// a path read from a scene file as seen from the working directory
+fn resolve(path: &Path) -> PathBuf {
+    DIRECTORY.with_borrow(|directory| match directory {
+        Some(directory) if path.is_relative() => directory.join(path),
+        _ => path.into(),
+    })
+}
+
+/// a path written to a scene file, relative to its directory if it is inside of it
+fn relative(path: &Path) -> PathBuf {
+    DIRECTORY.with_borrow(|directory| match directory {
+        Some(directory) => match path.strip_prefix(directory) {
+            Ok(relative) => relative.into(),
+            Err(_) => std::path::absolute(path).unwrap_or_else(|_| path.into()),
+        },
+        None => path.into(),
+    })
+}
+
+/// writes a unit variant as a string, because RON drops the name of unit variants nested in
+/// internally tagged enums
+pub(crate) fn variant_name<T: std::fmt::Debug, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{:?}", value))
+}
+
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|&c| c != '\n').count() + 1;
+    (line, column)
+}
+
+/// Isometries are stored as a translation and a rotation given as a scaled axis in radians.
+pub(crate) mod isometry {
+    use parry3d::math::Isometry;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct IsometryFile {
+        translation: [f32; 3],
+        #[serde(default)]
+        rotation: [f32; 3],
+    }
+
+    pub fn serialize<S: Serializer>(
+        isometry: &Isometry<f32>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        IsometryFile {
+            translation: isometry.translation.vector.into(),
+            rotation: isometry.rotation.scaled_axis().into(),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Isometry<f32>, D::Error> {
+        let file = IsometryFile::deserialize(deserializer)?;
+        Ok(Isometry::new(file.translation.into(), file.rotation.into()))
+    }
+}
+
+/// Optional isometries, stored like `isometry`.
+pub(crate) mod optional_isometry {
+    use parry3d::math::Isometry;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(transparent)]
+    struct IsometryFile(#[serde(with = "super::isometry")] Isometry<f32>);
+
+    pub fn serialize<S: Serializer>(
+        isometry: &Option<Isometry<f32>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        isometry.map(IsometryFile).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Isometry<f32>>, D::Error> {
+        Ok(Option::<IsometryFile>::deserialize(deserializer)?.map(|file| file.0))
+    }
+}
+
+/// Convex polyhedra are stored as the points they are the hull of.
+pub(crate) mod convex_polyhedron {
+    use parry3d::{math::Point, shape::ConvexPolyhedron};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+
+    #[derive(Serialize, Deserialize)]
+    struct ConvexPolyhedronFile {
+        points: Vec<Point<f32>>,
+    }
+
+    pub fn serialize<S: Serializer>(
+        polyhedron: &ConvexPolyhedron,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        ConvexPolyhedronFile {
+            points: polyhedron.points().to_vec(),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<ConvexPolyhedron, D::Error> {
+        let file = ConvexPolyhedronFile::deserialize(deserializer)?;
+        ConvexPolyhedron::from_convex_hull(&file.points).ok_or_else(|| {
+            de::Error::custom("the points of the polyhedron do not enclose a volume")
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ImageTextureFile {
+    path: PathBuf,
+    #[serde(default, serialize_with = "variant_name")]
+    wrap: WrapMode,
+}
+
+impl Serialize for ImageTexture {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let path = self.path().ok_or_else(|| {
+            ser::Error::custom("image textures that were not opened from a file cannot be saved")
+        })?;
+        ImageTextureFile {
+            path: relative(path),
+            wrap: self.wrap,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ImageTexture {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let file = ImageTextureFile::deserialize(deserializer)?;
+        let path = resolve(&file.path);
+        ImageTexture::open(&path, file.wrap)
+            .map_err(|err| de::Error::custom(format!("{}: {}", path.display(), err)))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct EnvironmentMapFile {
+    path: PathBuf,
+    /// scaled axis in radians
+    #[serde(default)]
+    rotation: [f32; 3],
+    #[serde(default = "one")]
+    intensity: f32,
+}
+
+pub(crate) fn one() -> f32 {
+    1.0
+}
+
+pub(crate) fn is_one(value: &f32) -> bool {
+    *value == 1.0
+}
+
+pub(crate) fn is_zero(value: &f32) -> bool {
+    *value == 0.0
+}
+
+pub(crate) fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+impl Serialize for EnvironmentMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let path = self.path().ok_or_else(|| {
+            ser::Error::custom("environment maps that were not opened from a file cannot be saved")
+        })?;
+        EnvironmentMapFile {
+            path: relative(path),
+            rotation: self.rotation.scaled_axis().into(),
+            intensity: self.intensity,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for EnvironmentMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let file = EnvironmentMapFile::deserialize(deserializer)?;
+        let rotation = parry3d::math::Rotation::new(Vector::from(file.rotation));
+        let path = resolve(&file.path);
+        EnvironmentMap::open(&path, rotation, file.intensity)
+            .map_err(|err| de::Error::custom(format!("{}: {}", path.display(), err)))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SkyFile {
+    elevation: f32,
+    azimuth: f32,
+    turbidity: f32,
+    #[serde(default = "sky_intensity")]
+    intensity: f32,
+    #[serde(default = "sun_intensity")]
+    sun_intensity: f32,
+    #[serde(default = "sun_angular_radius")]
+    sun_angular_radius: f32,
+}
+
+fn sky_intensity() -> f32 {
+    sky::SKY_INTENSITY
+}
+
+fn sun_intensity() -> f32 {
+    sky::SUN_INTENSITY
+}
+
+fn sun_angular_radius() -> f32 {
+    sky::SUN_ANGULAR_RADIUS
+}
+
+impl Serialize for Sky {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SkyFile {
+            elevation: self.elevation(),
+            azimuth: self.azimuth(),
+            turbidity: self.turbidity(),
+            intensity: self.intensity(),
+            sun_intensity: self.sun_intensity(),
+            sun_angular_radius: self.sun_angular_radius(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Sky {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let file = SkyFile::deserialize(deserializer)?;
+        Ok(Sky::new(file.elevation, file.azimuth, file.turbidity)
+            .with_intensity(file.intensity)
+            .with_sun(file.sun_intensity, file.sun_angular_radius))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct DensityGridFile {
+    resolution: [usize; 3],
+    bounds: Aabb,
+    data: Vec<f32>,
+}
+
+impl Serialize for DensityGrid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        DensityGridFile {
+            resolution: self.resolution(),
+            bounds: *self.bounds(),
+            data: self.data().to_vec(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DensityGrid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let file = DensityGridFile::deserialize(deserializer)?;
+        let expected: usize = file.resolution.iter().product();
+        if file.data.len() != expected {
+            return Err(de::Error::invalid_length(
+                file.data.len(),
+                &format!("{} densities for the resolution", expected).as_str(),
+            ));
+        }
+        Ok(DensityGrid::new(file.resolution, file.bounds, file.data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parry3d::{
+        math::{Isometry, Point},
+        shape::{Ball, Cuboid},
+    };
+
+    use super::*;
+    use crate::{
+        graph::{Instance, Prototype},
+        light::Light,
+        objects::{Brdf, Color, Object, Shape},
+        texture::{Texture, UvAxis},
+    };
+
+    fn scene() -> Scene {
+        Scene::new(Color(0.2, 0.3, 0.4))
+            .add_object(
+                Object::new(
+                    Shape::Ball(Ball::new(1.5)),
+                    Isometry::translation(1.0, 2.0, 3.0),
+                    Color(0.8, 0.1, 0.1),
+                )
+                .with_brdf(Brdf::Glossy)
+                .with_roughness(0.25)
+                .with_texture(Texture::Gradient {
+                    start: Color(0.1, 0.1, 0.1),
+                    end: Color(0.9, 0.9, 0.9),
+                    axis: UvAxis::V,
+                }),
+            )
+            .add_object(
+                Object::new(
+                    Shape::Cuboid(Cuboid::new(Vector::new(1.0, 2.0, 3.0))),
+                    Isometry::new(Vector::zeros(), Vector::new(0.0, 0.5, 0.0)),
+                    Color(5.0, 5.0, 5.0),
+                )
+                .with_light_source(),
+            )
+            .add_light(Light::Point {
+                position: Point::new(0.0, 10.0, 0.0),
+                intensity: Color(100.0, 90.0, 80.0),
+            })
+    }
+
+    #[test]
+    fn round_trips_in_every_format() {
+        for format in [Format::Ron, Format::Json, Format::Toml] {
+            let source = scene().to_string(format).unwrap();
+            let parsed = Scene::from_str(&source, format).unwrap();
+            assert_eq!(parsed.to_string(format).unwrap(), source, "{:?}", format);
+            assert_eq!(parsed.objects().len(), 2);
+            assert_eq!(parsed.lights().len(), 1);
+        }
+    }
+
+    #[test]
+    fn reports_lines_and_columns() {
+        let Err((line, column, _)) = Scene::from_str("(\n    objects: [,\n", Format::Ron) else {
+            panic!("parsed invalid RON");
+        };
+        assert_eq!((line, column), (2, 15));
+        let Err((line, column, message)) = Scene::from_str("{\n  \"objects\": [}\n", Format::Json)
+        else {
+            panic!("parsed invalid JSON");
+        };
+        assert_eq!((line, column), (2, 15));
+        assert!(!message.contains(" at line "));
+        let Err((line, column, _)) = Scene::from_str("lights = []\n[camera\n", Format::Toml) else {
+            panic!("parsed invalid TOML");
+        };
+        assert_eq!((line, column), (2, 8));
+    }
+
+    #[test]
+    fn reports_instances_of_unknown_prototypes() {
+        let ball = Object::new(
+            Shape::Ball(Ball::new(1.0)),
+            Isometry::identity(),
+            Color(1.0, 1.0, 1.0),
+        );
+        let source = Scene::new(Color(0.0, 0.0, 0.0))
+            .add_prototype(Prototype::new("cell", vec![ball]))
+            .add_instance(Instance::new("cell", Isometry::translation(1.0, 0.0, 0.0)))
+            .unwrap()
+            .to_string(Format::Json)
+            .unwrap()
+            .replace("\"prototype\": \"cell\"", "\"prototype\": \"cage\"");
+        let Err((line, column, message)) = Scene::from_str(&source, Format::Json) else {
+            panic!("parsed an instance of an unknown prototype");
+        };
+        let offset = source.find("\"cage\"").unwrap();
+        assert_eq!((line, column), line_column(&source, offset));
+        assert_eq!(message, "unknown prototype `cage`");
+    }
+
+    #[test]
+    fn reports_the_path_of_invalid_files() {
+        let directory =
+            std::env::temp_dir().join(format!("ray-scene-error-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("scene.json");
+        fs::write(&path, "{\n  \"objects\": 3\n}\n").unwrap();
+        let Err(error) = Scene::load(&path) else {
+            panic!("loaded an invalid scene");
+        };
+        fs::remove_dir_all(&directory).unwrap();
+        let SceneFileError::Parse { line, .. } = &error else {
+            panic!("expected a parse error, got {}", error);
+        };
+        assert_eq!(*line, 2);
+        assert!(error.to_string().starts_with(&path.display().to_string()));
+    }
+
+    #[test]
+    fn resolves_paths_from_the_scene_directory() {
+        let directory =
+            std::env::temp_dir().join(format!("ray-scene-paths-{}", std::process::id()));
+        fs::create_dir_all(directory.join("copies")).unwrap();
+        let texture = directory.join("texture.png");
+        image::RgbImage::from_pixel(2, 2, image::Rgb([200, 100, 50]))
+            .save(&texture)
+            .unwrap();
+        let scene = scene().add_object(
+            Object::new(
+                Shape::Ball(Ball::new(1.0)),
+                Isometry::identity(),
+                Color(1.0, 1.0, 1.0),
+            )
+            .with_texture(Texture::Image(
+                ImageTexture::open(&texture, WrapMode::Repeat).unwrap(),
+            )),
+        );
+
+        // the working directory is not the directory of the scene
+        let path = directory.join("scene.ron");
+        scene.save(&path).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        let loaded = Scene::load(&path);
+        // a scene in another directory refers to the texture by its absolute path
+        let copy = directory.join("copies").join("scene.ron");
+        scene.save(&copy).unwrap();
+        let copied = fs::read_to_string(&copy).unwrap();
+        let loaded_copy = Scene::load(&copy);
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert!(saved.contains("path: \"texture.png\""), "{}", saved);
+        assert_eq!(loaded.unwrap().objects().len(), 3);
+        assert!(copied.contains(&format!("{:?}", std::path::absolute(&texture).unwrap())));
+        assert_eq!(loaded_copy.unwrap().objects().len(), 3);
+    }
+}
@@ -0,0 +1,220 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use parry3d::math::Vector;
+
+use crate::{objects::Color, utils};
+
+/// the angular radius of the sun as seen from earth in radians
+pub const SUN_ANGULAR_RADIUS: f32 = 0.00465;
+/// the default scale of the sky radiance
+pub const SKY_INTENSITY: f32 = 0.05;
+/// the default irradiance of the sun
+pub const SUN_INTENSITY: f32 = 4.0;
+
+/// The analytic daylight sky of Preetham et al. with a sun disc, the zenith is along the z axis.
+///
+/// The sun is sampled explicitly, so it lights the scene like a directional light with a finite
+/// angular size.
+pub struct Sky {
+    elevation: f32,
+    azimuth: f32,
+    sun_direction: Vector<f32>,
+    turbidity: f32,
+    intensity: f32,
+    sun_intensity: f32,
+    cos_sun_radius: f32,
+    sun_color: Color,
+    /// Perez coefficients for the luminance and the two chromaticities
+    perez: [[f32; 5]; 3],
+    /// the luminance and chromaticities at the zenith divided by the Perez function there
+    zenith: [f32; 3],
+}
+
+impl Sky {
+    /// `elevation` is the angle of the sun above the horizon, `azimuth` is measured from the x axis
+    /// towards the y axis, both in radians, `turbidity` ranges from about 2 (clear) to 10 (hazy)
+    pub fn new(elevation: f32, azimuth: f32, turbidity: f32) -> Self {
+        let elevation = elevation.clamp(0.0, FRAC_PI_2);
+        let sun_direction = Vector::new(
+            elevation.cos() * azimuth.cos(),
+            elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+        );
+        let t = turbidity;
+        let perez = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+
+        let theta_s = FRAC_PI_2 - elevation;
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let (t1, t2, t3) = (theta_s, theta_s * theta_s, theta_s * theta_s * theta_s);
+        let zenith_x = t * t * (0.00166 * t3 - 0.00375 * t2 + 0.00209 * t1)
+            + t * (-0.02903 * t3 + 0.06377 * t2 - 0.03202 * t1 + 0.00394)
+            + (0.11693 * t3 - 0.21196 * t2 + 0.06052 * t1 + 0.25886);
+        let zenith_y = t * t * (0.00275 * t3 - 0.00610 * t2 + 0.00317 * t1)
+            + t * (-0.04214 * t3 + 0.08970 * t2 - 0.04153 * t1 + 0.00516)
+            + (0.15346 * t3 - 0.26756 * t2 + 0.06670 * t1 + 0.26688);
+        let zenith = [zenith_luminance, zenith_x, zenith_y];
+        let zenith = [0, 1, 2].map(|i| zenith[i] / perez_function(&perez[i], 0.0, theta_s));
+
+        let mut sky = Self {
+            elevation,
+            azimuth,
+            sun_direction,
+            turbidity,
+            intensity: SKY_INTENSITY,
+            sun_intensity: SUN_INTENSITY,
+            cos_sun_radius: SUN_ANGULAR_RADIUS.cos(),
+            sun_color: Color(1.0, 1.0, 1.0),
+            perez,
+            zenith,
+        };
+        sky.sun_color = sky.sun_transmittance();
+        sky
+    }
+
+    /// scales the radiance of the sky, not including the sun
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// sets the irradiance of the sun on a surface facing it and its angular radius in radians
+    pub fn with_sun(mut self, intensity: f32, angular_radius: f32) -> Self {
+        self.sun_intensity = intensity;
+        self.cos_sun_radius = angular_radius.cos();
+        self
+    }
+
+    pub fn elevation(&self) -> f32 {
+        self.elevation
+    }
+
+    pub fn azimuth(&self) -> f32 {
+        self.azimuth
+    }
+
+    pub fn turbidity(&self) -> f32 {
+        self.turbidity
+    }
+
+    pub fn intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    pub fn sun_intensity(&self) -> f32 {
+        self.sun_intensity
+    }
+
+    pub fn sun_angular_radius(&self) -> f32 {
+        self.cos_sun_radius.acos()
+    }
+
+    pub fn sun_direction(&self) -> Vector<f32> {
+        self.sun_direction
+    }
+
+    pub fn radiance(&self, direction: &Vector<f32>) -> Color {
+        let direction = direction.normalize();
+        let sky = self.sky_radiance(&direction);
+        if direction.dot(&self.sun_direction) >= self.cos_sun_radius {
+            sky + self.sun_radiance()
+        } else {
+            sky
+        }
+    }
+
+    /// samples a direction towards the sun disc as `(direction, radiance, pdf)`
+    pub fn sample(&self) -> Option<(Vector<f32>, Color, f32)> {
+        let cos_theta = 1.0 - utils::random::<f32>() * (1.0 - self.cos_sun_radius);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let phi = 2.0 * PI * utils::random::<f32>();
+        let direction = utils::local_frame(self.sun_direction)
+            * Vector::new(phi.cos() * sin_theta, phi.sin() * sin_theta, cos_theta);
+        Some((
+            direction,
+            self.radiance(&direction),
+            1.0 / self.sun_solid_angle(),
+        ))
+    }
+
+    pub fn pdf(&self, direction: &Vector<f32>) -> f32 {
+        if direction.normalize().dot(&self.sun_direction) >= self.cos_sun_radius {
+            1.0 / self.sun_solid_angle()
+        } else {
+            0.0
+        }
+    }
+
+    fn sun_solid_angle(&self) -> f32 {
+        2.0 * PI * (1.0 - self.cos_sun_radius)
+    }
+
+    fn sun_radiance(&self) -> Color {
+        self.sun_color
+            .scale(self.sun_intensity / self.sun_solid_angle())
+    }
+
+    fn sky_radiance(&self, direction: &Vector<f32>) -> Color {
+        // below the horizon the sky is continued with its value at the horizon
+        let cos_theta = direction.z.max(0.01);
+        let gamma = direction.dot(&self.sun_direction).clamp(-1.0, 1.0).acos();
+        let [luminance, x, y] = [0, 1, 2]
+            .map(|i| self.zenith[i] * perez_function(&self.perez[i], cos_theta.acos(), gamma));
+        xyy_to_rgb(luminance, x, y).scale(self.intensity)
+    }
+
+    /// an approximate color of the sun after passing through the atmosphere
+    fn sun_transmittance(&self) -> Color {
+        let elevation_deg = self.sun_direction.z.asin().to_degrees();
+        // Kasten and Young, 1989
+        let air_mass = 1.0
+            / (elevation_deg.to_radians().sin()
+                + 0.50572 * (elevation_deg + 6.07995).powf(-1.6364));
+        let haze = 0.5 + self.turbidity / 4.0;
+        let extinction = [0.02, 0.05, 0.12];
+        let [r, g, b] = extinction.map(|beta| (-beta * haze * air_mass).exp());
+        Color(r, g, b)
+    }
+}
+
+fn perez_function(coefficients: &[f32; 5], theta: f32, gamma: f32) -> f32 {
+    let [a, b, c, d, e] = *coefficients;
+    (1.0 + a * (b / theta.cos().max(0.01)).exp())
+        * (1.0 + c * (d * gamma).exp() + e * gamma.cos() * gamma.cos())
+}
+
+/// converts from the xyY color space to linear sRGB
+fn xyy_to_rgb(luminance: f32, x: f32, y: f32) -> Color {
+    if y <= 0.0 {
+        return Color(0.0, 0.0, 0.0);
+    }
+    let big_x = x / y * luminance;
+    let big_z = (1.0 - x - y) / y * luminance;
+    Color(
+        (3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z).max(0.0),
+        (-0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z).max(0.0),
+        (0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z).max(0.0),
+    )
+}
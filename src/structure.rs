@@ -0,0 +1,402 @@
+//! Atomistic structures independent of the file format they were read from, and their
+//! representation as scene objects.
+
+use std::{
+    error::Error,
+    f32::consts::{FRAC_PI_2, TAU},
+    fmt::{self, Display},
+    fs, io,
+    path::Path,
+    str::FromStr,
+};
+
+use nalgebra::{Matrix3, Rotation3, Translation, UnitQuaternion};
+use parry3d::{
+    bounding_volume::Aabb,
+    math::{Isometry, Point, Vector},
+    shape::Cylinder,
+};
+
+use crate::{
+    bonds::{Bond, DEFAULT_BOND_TOLERANCE},
+    camera::CameraSettings,
+    cif::CifError,
+    crystal::{Crystal, Lattice},
+    elements::{self, AtomRadius, PeriodicTable},
+    ellipsoid::Ellipsoid,
+    objects::{Color, Object, Scene, Shape},
+    texture::Texture,
+    utils,
+};
+
+/// Atoms with cartesian positions in Å, periodic if a lattice is given.
+#[derive(Debug, Clone, Default)]
+pub struct Structure {
+    /// the element symbol of every atom
+    pub species: Vec<String>,
+    pub positions: Vec<Point<f32>>,
+    pub lattice: Option<Lattice>,
+    /// the bonds given by the file, otherwise bonds are detected when needed
+    pub bonds: Option<Vec<Bond>>,
+    /// the displacement tensor U in Å² along the cartesian axes of every atom, empty if the
+    /// format has none
+    pub displacements: Vec<Option<Matrix3<f32>>>,
+}
+
+/// How atoms are turned into objects.
+///
+/// Bonds are cylinders split at their middle, each half colored like its atom.
+#[derive(Debug, Clone, Copy)]
+pub enum Representation {
+    /// balls with the chosen radius of each element multiplied by `scale`
+    Balls { radius: AtomRadius, scale: f32 },
+    /// balls with the van der Waals radius
+    SpaceFilling,
+    /// balls with the covalent radius multiplied by `ball_scale` connected by bonds
+    BallAndStick { ball_scale: f32, bond_radius: f32 },
+    /// bonds with rounded joints of the same radius
+    Licorice { radius: f32 },
+    /// thin bonds only, atoms without bonds are drawn as small balls
+    Wireframe { radius: f32 },
+    /// thermal ellipsoids enclosing the displaced atom with the given probability connected by
+    /// bonds, atoms without displacement are drawn as small balls
+    Ellipsoids {
+        probability: f32,
+        bond_radius: f32,
+        /// cuts away the octant facing this direction, usually towards the camera
+        octant_cut: Option<Vector<f32>>,
+        /// darkens bands around the principal axes
+        bands: bool,
+    },
+}
+
+/// What an object drawn for a structure depicts, so it can follow the atoms.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Part {
+    Atom(usize),
+    /// the half of a bond next to the atom `from`
+    HalfBond {
+        from: usize,
+        to: usize,
+    },
+}
+
+impl Default for Representation {
+    fn default() -> Self {
+        Self::Balls {
+            radius: AtomRadius::Covalent,
+            scale: 1.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum StructureError {
+    Io(io::Error),
+    /// malformed input, `line` starts at one
+    Syntax {
+        line: usize,
+        message: String,
+    },
+    Cif(CifError),
+    UnknownFormat,
+}
+
+impl Display for StructureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StructureError::Io(err) => write!(f, "{}", err),
+            StructureError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            StructureError::Cif(err) => write!(f, "{}", err),
+            StructureError::UnknownFormat => write!(
+                f,
+                "unknown structure format, expected a CIF, XYZ, PDB, POSCAR or LAMMPS dump file"
+            ),
+        }
+    }
+}
+
+impl Error for StructureError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StructureError::Io(err) => Some(err),
+            StructureError::Cif(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<CifError> for StructureError {
+    fn from(value: CifError) -> Self {
+        Self::Cif(value)
+    }
+}
+
+impl Structure {
+    /// reads a structure, choosing the format by the extension or, for POSCAR and CONTCAR files,
+    /// by the file name, CIF files give a single unit cell
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StructureError> {
+        let path = path.as_ref();
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        let source = || fs::read_to_string(path).map_err(StructureError::Io);
+        if name.starts_with("poscar") || name.starts_with("contcar") {
+            return Self::from_poscar(&source()?);
+        }
+        match extension.as_str() {
+            "cif" => Ok(Crystal::from_cif(&source()?)?.structure([1, 1, 1])),
+            "xyz" | "extxyz" => Self::from_xyz(&source()?),
+            "pdb" | "ent" => Self::from_pdb(&source()?),
+            "vasp" | "poscar" => Self::from_poscar(&source()?),
+            "lammpstrj" => Self::from_lammps_dump(&source()?),
+            _ if name.starts_with("dump.") => Self::from_lammps_dump(&source()?),
+            _ => Err(StructureError::UnknownFormat),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// the box around the atom centers, or around the cell if the structure is periodic
+    pub fn bounds(&self) -> Aabb {
+        let mut points = self.positions.clone();
+        if let Some(lattice) = &self.lattice {
+            for corner in 0..8 {
+                let fract = Vector::new(corner & 1, (corner >> 1) & 1, (corner >> 2) & 1);
+                points.push(lattice.to_cartesian(&fract.map(|x| x as f32)));
+            }
+        }
+        if points.is_empty() {
+            return Aabb::new(Point::origin(), Point::origin());
+        }
+        Aabb::from_points(&points)
+    }
+
+    pub fn objects(&self, representation: &Representation, table: &PeriodicTable) -> Vec<Object> {
+        self.parts(representation, table)
+            .into_iter()
+            .map(|(_, object)| object)
+            .collect()
+    }
+
+    /// the objects of `objects` with what they depict
+    pub(crate) fn parts(
+        &self,
+        representation: &Representation,
+        table: &PeriodicTable,
+    ) -> Vec<(Part, Object)> {
+        let (balls, bond_radius) = match *representation {
+            Representation::Balls { radius, scale } => (Some((radius, scale)), None),
+            Representation::SpaceFilling => (Some((AtomRadius::VanDerWaals, 1.0)), None),
+            Representation::BallAndStick {
+                ball_scale,
+                bond_radius,
+            } => (Some((AtomRadius::Covalent, ball_scale)), Some(bond_radius)),
+            Representation::Licorice { radius } => {
+                (Some((AtomRadius::Fixed(radius), 1.0)), Some(radius))
+            }
+            Representation::Wireframe { radius } => (None, Some(radius)),
+            Representation::Ellipsoids { bond_radius, .. } => {
+                (Some((AtomRadius::Covalent, 0.3)), Some(bond_radius))
+            }
+        };
+        let bonds = match bond_radius {
+            Some(_) => self
+                .bonds
+                .clone()
+                .unwrap_or_else(|| self.detect_bonds(table, DEFAULT_BOND_TOLERANCE)),
+            None => Vec::new(),
+        };
+
+        let mut bonded = vec![false; self.len()];
+        let mut objects = Vec::new();
+        for bond in &bonds {
+            let (a, b) = (self.positions[bond.a], self.positions[bond.b]);
+            let half = (self.image_position(bond.b, bond.image) - a) / 2.0;
+            let radius = bond_radius.unwrap_or_default();
+            let halves = [(bond.a, bond.b, a + half), (bond.b, bond.a, b - half)];
+            for (from, to, middle) in halves {
+                let color = table.color(&self.species[from]);
+                let position = self.positions[from];
+                if let Some(object) = cylinder(position, middle, radius, color) {
+                    objects.push((Part::HalfBond { from, to }, object));
+                }
+            }
+            bonded[bond.a] = true;
+            bonded[bond.b] = true;
+        }
+        for (i, (element, position)) in self.species.iter().zip(&self.positions).enumerate() {
+            if let Representation::Ellipsoids {
+                probability,
+                octant_cut,
+                bands,
+                ..
+            } = *representation
+            {
+                let displacement = self.displacements.get(i).copied().flatten();
+                let ellipsoid = displacement
+                    .and_then(|u| thermal_ellipsoid(&u, probability, octant_cut, bands));
+                if let Some((shape, rotation, texture)) = ellipsoid {
+                    let object = Object {
+                        shape,
+                        isometry: Isometry::from_parts(
+                            Translation::from(position.coords),
+                            rotation,
+                        ),
+                        texture,
+                        ..table.ball(element, *position, 0.0)
+                    };
+                    objects.push((Part::Atom(i), object));
+                    continue;
+                }
+            }
+            let radius = match (balls, bond_radius) {
+                (Some((radius, scale)), _) => table.radius(element, radius) * scale,
+                (None, Some(radius)) if !bonded[i] => 4.0 * radius,
+                (None, _) => continue,
+            };
+            objects.push((Part::Atom(i), table.ball(element, *position, radius)));
+        }
+        objects
+    }
+
+    /// a scene with the structure in front of a white background, the camera looks at the
+    /// center of the structure from a distance that fits it into the image
+    pub fn scene(&self, representation: &Representation, table: &PeriodicTable) -> Scene {
+        Scene::new(Color(1.0, 1.0, 1.0))
+            .add_objects(self.objects(representation, table))
+            .with_camera(self.camera())
+    }
+
+    /// a camera looking at the center of the structure from a distance that fits it into the
+    /// image
+    pub(crate) fn camera(&self) -> CameraSettings {
+        let bounds = self.bounds();
+        let target = bounds.center();
+        let fov = TAU / 10.0;
+        // leave room for the atoms at the border
+        let radius = bounds.half_extents().norm() + 2.0;
+        let distance = radius / (fov / 2.0).sin();
+        CameraSettings {
+            eye: target + Vector::new(3.0, 2.0, 1.5).normalize() * distance,
+            target,
+            fov,
+            ..Default::default()
+        }
+    }
+}
+
+/// an ellipsoid for the displacement `u` with its rotation and the texture of the bands
+fn thermal_ellipsoid(
+    u: &Matrix3<f32>,
+    probability: f32,
+    octant_cut: Option<Vector<f32>>,
+    bands: bool,
+) -> Option<(Shape, UnitQuaternion<f32>, Option<Texture>)> {
+    let (mut ellipsoid, rotation) = Ellipsoid::from_displacement(u, probability)?;
+    ellipsoid.octant_cut = octant_cut.map(|direction| rotation.inverse() * direction);
+    let texture = bands.then(|| Texture::PrincipalBands {
+        color: Color(0.1, 0.1, 0.1),
+        width: 0.08 * ellipsoid.radii.min(),
+    });
+    Some((Shape::Ellipsoid(ellipsoid), rotation, texture))
+}
+
+/// a cylinder between two points, `None` if they coincide
+pub(crate) fn cylinder(
+    from: Point<f32>,
+    to: Point<f32>,
+    radius: f32,
+    color: Color,
+) -> Option<Object> {
+    let axis = to - from;
+    let length = axis.norm();
+    if length <= 0.0 {
+        return None;
+    }
+    // the cylinder lies along the y axis, `local_frame` maps the z axis
+    let rotation = utils::local_frame(axis / length)
+        * Rotation3::from_axis_angle(&Vector::x_axis(), FRAC_PI_2);
+    let center = from + axis / 2.0;
+    Some(Object::new(
+        Shape::Cylinder(Cylinder::new(length / 2.0, radius)),
+        Isometry::from_parts(
+            Translation::from(center.coords),
+            UnitQuaternion::from_rotation_matrix(&rotation),
+        ),
+        color,
+    ))
+}
+
+/// splits a line into whitespace separated fields, reporting missing fields as syntax errors
+#[derive(Clone)]
+pub(crate) struct Fields<'a> {
+    line: usize,
+    fields: std::str::SplitWhitespace<'a>,
+}
+
+impl<'a> Fields<'a> {
+    pub(crate) fn new(line: usize, source: &'a str) -> Self {
+        Self {
+            line,
+            fields: source.split_whitespace(),
+        }
+    }
+
+    pub(crate) fn line(&self) -> usize {
+        self.line
+    }
+
+    pub(crate) fn rest(self) -> Vec<&'a str> {
+        self.fields.collect()
+    }
+
+    /// parses all remaining fields
+    pub(crate) fn numbers<T: FromStr>(self, name: &str) -> Result<Vec<T>, StructureError> {
+        let line = self.line;
+        self.fields
+            .map(|field| {
+                field.parse().map_err(|_| StructureError::Syntax {
+                    line,
+                    message: format!("invalid {} `{}`", name, field),
+                })
+            })
+            .collect()
+    }
+
+    pub(crate) fn next(&mut self, name: &str) -> Result<&'a str, StructureError> {
+        self.fields.next().ok_or_else(|| StructureError::Syntax {
+            line: self.line,
+            message: format!("missing {}", name),
+        })
+    }
+
+    pub(crate) fn number(&mut self, name: &str) -> Result<f32, StructureError> {
+        let field = self.next(name)?;
+        field.parse().map_err(|_| StructureError::Syntax {
+            line: self.line,
+            message: format!("invalid {} `{}`", name, field),
+        })
+    }
+}
+
+/// the element symbol of a species given by name or atomic number
+pub(crate) fn species(name: &str) -> String {
+    match name.parse().ok().and_then(elements::by_number) {
+        Some(element) => element.symbol.into(),
+        None => elements::parse_symbol(name),
+    }
+}
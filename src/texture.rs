@@ -0,0 +1,375 @@
+use std::path::{Path, PathBuf};
+
+use image::{
+    ImageError, ImageResult, Rgb32FImage,
+    error::{ParameterError, ParameterErrorKind},
+};
+use nalgebra::{Point2, Vector2};
+use parry3d::math::{Point, Vector};
+
+use crate::objects::{Color, SurfacePoint};
+
+/// A texture that can replace the constant color of an object.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
+pub enum Texture {
+    Image(ImageTexture),
+    /// alternating squares in uv space, `scale` is the number of squares per unit of uv
+    Checkerboard {
+        even: Color,
+        odd: Color,
+        scale: f32,
+    },
+    /// a linear blend between `start` and `end` along one uv coordinate
+    Gradient {
+        start: Color,
+        end: Color,
+        #[cfg_attr(
+            feature = "serde",
+            serde(serialize_with = "crate::scene_file::variant_name")
+        )]
+        axis: UvAxis,
+    },
+    /// a solid noise evaluated at the position in object space
+    Noise {
+        noise: Noise,
+        low: Color,
+        high: Color,
+        scale: f32,
+    },
+    /// darkens the surface where it crosses the planes between the object axes, the principal
+    /// axis bands of thermal ellipsoids, `width` is in object space
+    PrincipalBands {
+        color: Color,
+        width: f32,
+    },
+    /// projects a texture along the three object axes and blends by the normal,
+    /// useful for shapes without good uvs
+    Triplanar {
+        texture: Box<Texture>,
+        scale: f32,
+        sharpness: f32,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UvAxis {
+    U,
+    V,
+}
+
+impl Texture {
+    pub fn color_at(&self, surface: &SurfacePoint) -> Color {
+        match self {
+            Texture::Image(image) => image.sample(surface.uv),
+            Texture::Checkerboard { even, odd, scale } => {
+                let x = (surface.uv.x * scale).floor() as i64;
+                let y = (surface.uv.y * scale).floor() as i64;
+                if (x + y) % 2 == 0 { *even } else { *odd }
+            }
+            Texture::Gradient { start, end, axis } => {
+                let t = match axis {
+                    UvAxis::U => surface.uv.x,
+                    UvAxis::V => surface.uv.y,
+                }
+                .clamp(0.0, 1.0);
+                start.lerp(end, t)
+            }
+            Texture::Noise {
+                noise,
+                low,
+                high,
+                scale,
+            } => low.lerp(high, noise.evaluate(surface.point * *scale)),
+            Texture::PrincipalBands { color, width } => {
+                let normal = surface.normal.normalize();
+                // faces lying in one of the planes are only darkened along their edges
+                let on_band = (0..3)
+                    .any(|axis| surface.point[axis].abs() < *width && normal[axis].abs() < 0.99);
+                if on_band {
+                    *color
+                } else {
+                    Color(1.0, 1.0, 1.0)
+                }
+            }
+            Texture::Triplanar {
+                texture,
+                scale,
+                sharpness,
+            } => {
+                let weights = surface.normal.map(|x| x.abs().powf(*sharpness));
+                let weights = weights / weights.sum();
+                let p = surface.point * *scale;
+                let mut color = Color(0.0, 0.0, 0.0);
+                for (axis, (a, b)) in [(p.y, p.z), (p.x, p.z), (p.x, p.y)].into_iter().enumerate() {
+                    if weights[axis] > 0.0 {
+                        let projected = SurfacePoint {
+                            uv: [a, b].into(),
+                            ..*surface
+                        };
+                        color += texture.color_at(&projected).scale(weights[axis]);
+                    }
+                }
+                color
+            }
+        }
+    }
+}
+
+/// Perturbs the shading normal of a surface.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
+pub enum NormalMap {
+    /// a tangent space normal map, the color channels encode the x, y and z components in `[0, 1]`
+    Tangent { image: ImageTexture, strength: f32 },
+    /// a greyscale height map, the normal follows the gradient of the height in uv space
+    Bump { height: Texture, strength: f32 },
+}
+
+/// the step in uv and object space used to differentiate bump maps
+const BUMP_DELTA: f32 = 1e-3;
+
+impl NormalMap {
+    /// the perturbed normal in the local space of the shape
+    pub fn perturb(&self, surface: &SurfacePoint) -> Vector<f32> {
+        let normal = surface.normal.normalize();
+        let tangent = surface.tangent;
+        let bitangent = normal.cross(&tangent);
+        let perturbed = match self {
+            NormalMap::Tangent { image, strength } => {
+                let Color(x, y, z) = image.sample(surface.uv);
+                let local = Vector::new(
+                    (2.0 * x - 1.0) * strength,
+                    (2.0 * y - 1.0) * strength,
+                    2.0 * z - 1.0,
+                );
+                tangent * local.x + bitangent * local.y + normal * local.z
+            }
+            NormalMap::Bump { height, strength } => {
+                let height_at = |du: f32, dv: f32| {
+                    height
+                        .color_at(&SurfacePoint {
+                            uv: surface.uv + Vector2::new(du, dv),
+                            point: surface.point + tangent * du + bitangent * dv,
+                            ..*surface
+                        })
+                        .luminance()
+                };
+                let center = height_at(0.0, 0.0);
+                let d_u = (height_at(BUMP_DELTA, 0.0) - center) / BUMP_DELTA;
+                let d_v = (height_at(0.0, BUMP_DELTA) - center) / BUMP_DELTA;
+                normal - (tangent * d_u + bitangent * d_v) * *strength
+            }
+        };
+        perturbed.try_normalize(1e-6).unwrap_or(normal)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WrapMode {
+    #[default]
+    Repeat,
+    MirroredRepeat,
+    Clamp,
+}
+
+/// An image sampled with bilinear filtering, `v = 0` is the bottom row of the image.
+pub struct ImageTexture {
+    image: Rgb32FImage,
+    pub wrap: WrapMode,
+    path: Option<PathBuf>,
+}
+
+impl ImageTexture {
+    /// panics for an image without pixels, which cannot be sampled
+    pub fn new(image: Rgb32FImage, wrap: WrapMode) -> Self {
+        assert!(
+            image.width() > 0 && image.height() > 0,
+            "image textures need at least one pixel"
+        );
+        Self {
+            image,
+            wrap,
+            path: None,
+        }
+    }
+
+    pub fn open(path: impl AsRef<Path>, wrap: WrapMode) -> ImageResult<Self> {
+        Ok(Self {
+            path: Some(path.as_ref().to_path_buf()),
+            ..Self::new(open_image(path)?, wrap)
+        })
+    }
+
+    /// the file the texture was opened from
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn sample(&self, uv: Point2<f32>) -> Color {
+        let width = self.image.width() as f32;
+        let height = self.image.height() as f32;
+        let x = uv.x * width - 0.5;
+        let y = (1.0 - uv.y) * height - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let texel = |dx: f32, dy: f32| {
+            let px = self.wrap.apply(x0 + dx, width);
+            let py = self.wrap.apply(y0 + dy, height);
+            let [r, g, b] = self.image.get_pixel(px, py).0;
+            Color(r, g, b)
+        };
+        texel(0.0, 0.0)
+            .lerp(&texel(1.0, 0.0), fx)
+            .lerp(&texel(0.0, 1.0).lerp(&texel(1.0, 1.0), fx), fy)
+    }
+}
+
+/// opens an image with floating point channels, images without pixels are an error since they
+/// cannot be sampled
+pub(crate) fn open_image(path: impl AsRef<Path>) -> ImageResult<Rgb32FImage> {
+    let image = image::open(path)?.into_rgb32f();
+    if image.width() == 0 || image.height() == 0 {
+        return Err(ImageError::Parameter(ParameterError::from_kind(
+            ParameterErrorKind::Generic("the image has no pixels".into()),
+        )));
+    }
+    Ok(image)
+}
+
+impl WrapMode {
+    fn apply(&self, coord: f32, size: f32) -> u32 {
+        let coord = match self {
+            WrapMode::Repeat => coord.rem_euclid(size),
+            WrapMode::MirroredRepeat => {
+                let period = coord.rem_euclid(2.0 * size);
+                if period < size {
+                    period
+                } else {
+                    2.0 * size - 1.0 - period
+                }
+            }
+            WrapMode::Clamp => coord.clamp(0.0, size - 1.0),
+        };
+        (coord as u32).min(size as u32 - 1)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NoiseKind {
+    Perlin,
+    /// distance to the closest feature point
+    Worley,
+}
+
+/// Seeded solid noise with fractal octaves, values are in `[0, 1]`.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Noise {
+    pub kind: NoiseKind,
+    pub seed: u32,
+    pub octaves: u32,
+}
+
+impl Noise {
+    pub fn evaluate(&self, point: Point<f32>) -> f32 {
+        let mut value = 0.0;
+        let mut amplitude = 1.0;
+        let mut total = 0.0;
+        let mut frequency = 1.0;
+        for octave in 0..self.octaves.max(1) {
+            let p = point * frequency;
+            let seed = self.seed.wrapping_add(octave);
+            value += amplitude
+                * match self.kind {
+                    NoiseKind::Perlin => 0.5 + 0.5 * perlin(p, seed),
+                    NoiseKind::Worley => worley(p, seed),
+                };
+            total += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        (value / total).clamp(0.0, 1.0)
+    }
+}
+
+fn hash(x: i32, y: i32, z: i32, seed: u32) -> u32 {
+    let mut h = seed
+        ^ (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ (z as u32).wrapping_mul(0xcb1a_b31f);
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846c_a68b);
+    h ^ (h >> 16)
+}
+
+fn perlin(point: Point<f32>, seed: u32) -> f32 {
+    const GRADIENTS: [[f32; 3]; 12] = [
+        [1.0, 1.0, 0.0],
+        [-1.0, 1.0, 0.0],
+        [1.0, -1.0, 0.0],
+        [-1.0, -1.0, 0.0],
+        [1.0, 0.0, 1.0],
+        [-1.0, 0.0, 1.0],
+        [1.0, 0.0, -1.0],
+        [-1.0, 0.0, -1.0],
+        [0.0, 1.0, 1.0],
+        [0.0, -1.0, 1.0],
+        [0.0, 1.0, -1.0],
+        [0.0, -1.0, -1.0],
+    ];
+    let cell = point.map(f32::floor);
+    let local = point - cell;
+    let fade = local.map(|t| t * t * t * (t * (t * 6.0 - 15.0) + 10.0));
+    let mut value = 0.0;
+    for corner in 0..8 {
+        let offset = Vector::new(
+            (corner & 1) as f32,
+            ((corner >> 1) & 1) as f32,
+            ((corner >> 2) & 1) as f32,
+        );
+        let lattice = cell + offset;
+        let gradient: Vector<f32> =
+            GRADIENTS[hash(lattice.x as i32, lattice.y as i32, lattice.z as i32, seed) as usize
+                % GRADIENTS.len()]
+            .into();
+        let weight = (0..3)
+            .map(|i| {
+                if offset[i] == 1.0 {
+                    fade[i]
+                } else {
+                    1.0 - fade[i]
+                }
+            })
+            .product::<f32>();
+        value += weight * gradient.dot(&(local - offset));
+    }
+    value
+}
+
+fn worley(point: Point<f32>, seed: u32) -> f32 {
+    let cell = point.map(f32::floor);
+    let mut closest = f32::MAX;
+    for dx in -1..=1 {
+        for dy in -1..=1 {
+            for dz in -1..=1 {
+                let x = cell.x as i32 + dx;
+                let y = cell.y as i32 + dy;
+                let z = cell.z as i32 + dz;
+                let h = hash(x, y, z, seed);
+                let feature = Point::new(
+                    x as f32 + (h & 0x3ff) as f32 / 1024.0,
+                    y as f32 + ((h >> 10) & 0x3ff) as f32 / 1024.0,
+                    z as f32 + ((h >> 20) & 0x3ff) as f32 / 1024.0,
+                );
+                closest = closest.min((feature - point).norm());
+            }
+        }
+    }
+    closest
+}
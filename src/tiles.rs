@@ -0,0 +1,224 @@
+//! Splitting images into tiles that are rendered one after another.
+//!
+//! Every tile is rendered on one thread into its own buffer, with its own random numbers when
+//! the render is seeded, and written into the image when it is finished. Rayon hands the tiles
+//! to the threads in the order of the tiling, so the first tiles of the order are finished
+//! first.
+
+use std::f32::consts::PI;
+
+/// A rectangle of pixels of an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Tile {
+    pub fn pixel_count(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    /// the pixels of the tile row by row in image coordinates
+    pub fn pixels(&self) -> impl Iterator<Item = (u32, u32)> {
+        let Tile {
+            x,
+            y,
+            width,
+            height,
+        } = *self;
+        (y..y + height).flat_map(move |y| (x..x + width).map(move |x| (x, y)))
+    }
+
+    /// the seed of the random numbers of the tile derived from the seed of the render,
+    /// independent of the order in which the tiles are rendered
+    pub fn seed(&self, seed: u64) -> u64 {
+        let position = (self.y as u64) << 32 | self.x as u64;
+        seed ^ position.wrapping_add(1).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+    }
+}
+
+/// The order in which the tiles are rendered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TileOrder {
+    /// row by row from the top left
+    Scanline,
+    /// around the centre of the image outwards, where the subject usually is
+    #[default]
+    Spiral,
+    /// along a Hilbert curve, neighbouring tiles are rendered close in time
+    Hilbert,
+}
+
+/// The size and order of the tiles of an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Tiling {
+    /// the width and height of the tiles in pixels, the tiles at the right and bottom edges
+    /// are cut off
+    pub size: u32,
+    pub order: TileOrder,
+}
+
+impl Default for Tiling {
+    fn default() -> Self {
+        Self {
+            size: 32,
+            order: TileOrder::default(),
+        }
+    }
+}
+
+impl Tiling {
+    pub fn new(size: u32, order: TileOrder) -> Self {
+        Self { size, order }
+    }
+
+    /// the tiles covering an image in the order they are rendered
+    pub fn tiles(&self, width: u32, height: u32) -> Vec<Tile> {
+        let size = self.size.max(1);
+        let columns = width.div_ceil(size);
+        let rows = height.div_ceil(size);
+        let mut cells: Vec<(u32, u32)> = (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .collect();
+        match self.order {
+            TileOrder::Scanline => {}
+            TileOrder::Spiral => {
+                let centre = (columns as f32 / 2.0 - 0.5, rows as f32 / 2.0 - 0.5);
+                let key = |&(column, row): &(u32, u32)| {
+                    let (dx, dy) = (column as f32 - centre.0, row as f32 - centre.1);
+                    // rings of tiles around the centre, each one clockwise from the top
+                    let ring = dx.abs().max(dy.abs()).round();
+                    let angle = dx.atan2(-dy).rem_euclid(2.0 * PI);
+                    (ring, angle)
+                };
+                cells.sort_by(|a, b| {
+                    let (a, b) = (key(a), key(b));
+                    a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1))
+                });
+            }
+            TileOrder::Hilbert => {
+                let side = columns.max(rows).next_power_of_two();
+                cells.sort_by_key(|&(column, row)| hilbert_index(side, column, row));
+            }
+        }
+        cells
+            .into_iter()
+            .map(|(column, row)| {
+                let (x, y) = (column * size, row * size);
+                Tile {
+                    x,
+                    y,
+                    width: size.min(width - x),
+                    height: size.min(height - y),
+                }
+            })
+            .collect()
+    }
+}
+
+/// the distance along the Hilbert curve through a square grid with a side of a power of two
+fn hilbert_index(side: u32, mut x: u32, mut y: u32) -> u64 {
+    let mut index = 0;
+    let mut s = side / 2;
+    while s > 0 {
+        let rx = (x & s > 0) as u32;
+        let ry = (y & s > 0) as u32;
+        index += s as u64 * s as u64 * ((3 * rx) ^ ry) as u64;
+        // rotates the quadrant so the curve continues where the last one ended
+        if ry == 0 {
+            if rx == 1 {
+                x = side - 1 - x;
+                y = side - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use super::*;
+
+    const ORDERS: [TileOrder; 3] = [TileOrder::Scanline, TileOrder::Spiral, TileOrder::Hilbert];
+
+    #[test]
+    fn every_order_covers_each_pixel_once() {
+        let images = [
+            (64, 64, 16),
+            (100, 37, 16),
+            (33, 65, 32),
+            (50, 50, 7),
+            (7, 5, 1),
+            (1, 1, 8),
+        ];
+        for order in ORDERS {
+            for (width, height, size) in images {
+                let mut counts = vec![0; width as usize * height as usize];
+                for tile in Tiling::new(size, order).tiles(width, height) {
+                    assert!(tile.width > 0 && tile.height > 0, "{:?}", tile);
+                    for (x, y) in tile.pixels() {
+                        assert!(x < width && y < height, "{:?}", tile);
+                        counts[(y * width + x) as usize] += 1;
+                    }
+                }
+                assert!(
+                    counts.iter().all(|&count| count == 1),
+                    "{:?} on {}×{} with tiles of {}",
+                    order,
+                    width,
+                    height,
+                    size
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn hilbert_tiles_follow_their_neighbours() {
+        let tiles = Tiling::new(4, TileOrder::Hilbert).tiles(32, 32);
+        for pair in tiles.windows(2) {
+            let distance = pair[0].x.abs_diff(pair[1].x) + pair[0].y.abs_diff(pair[1].y);
+            assert_eq!(distance, 4, "{:?}", pair);
+        }
+    }
+
+    #[test]
+    fn seeds_depend_only_on_the_position() {
+        let seeds = |order| -> HashMap<(u32, u32), u64> {
+            Tiling::new(16, order)
+                .tiles(70, 45)
+                .iter()
+                .map(|tile| ((tile.x, tile.y), tile.seed(42)))
+                .collect()
+        };
+        let scanline = seeds(TileOrder::Scanline);
+        for order in ORDERS {
+            assert_eq!(seeds(order), scanline);
+        }
+        let distinct: HashSet<u64> = scanline.values().copied().collect();
+        assert_eq!(distinct.len(), scanline.len());
+        let tile = Tile {
+            x: 16,
+            y: 32,
+            width: 16,
+            height: 16,
+        };
+        let cut_off = Tile {
+            width: 6,
+            height: 13,
+            ..tile
+        };
+        assert_eq!(tile.seed(42), cut_off.seed(42));
+        assert_ne!(tile.seed(42), tile.seed(43));
+    }
+}
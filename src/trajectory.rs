@@ -0,0 +1,543 @@
+//! Rendering trajectories from multi-frame XYZ files and LAMMPS dumps as numbered images.
+//!
+//! The scene is built once from the first frame and later frames only move the objects of the
+//! atoms and bonds, keeping their materials and every other object of the scene. Bonds are
+//! detected in the first frame and drawn towards the closest periodic image.
+
+use std::{
+    f32::consts::TAU,
+    fs,
+    iter::Peekable,
+    path::{Path, PathBuf},
+};
+
+use image::ImageResult;
+use indicatif::ProgressBar;
+use nalgebra::{Translation, Unit, UnitQuaternion};
+use parry3d::math::{Isometry, Point, Vector};
+
+use crate::{
+    camera::CameraSettings,
+    elements::PeriodicTable,
+    lammps,
+    objects::{Color, Scene, Shape},
+    structure::{self, Part, Representation, Structure, StructureError},
+    xyz,
+};
+
+type Lines<'a> = Peekable<Box<dyn Iterator<Item = (usize, &'a str)> + 'a>>;
+
+/// The frames of a trajectory, every frame holds the same atoms in the same order.
+#[derive(Debug, Clone, Default)]
+pub struct Trajectory {
+    pub frames: Vec<Structure>,
+}
+
+impl Trajectory {
+    /// reads a trajectory, choosing the format like `Structure::open`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StructureError> {
+        let path = path.as_ref();
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        let source = || fs::read_to_string(path).map_err(StructureError::Io);
+        match extension.as_str() {
+            "xyz" | "extxyz" => Self::from_xyz(&source()?),
+            "lammpstrj" => Self::from_lammps_dump(&source()?),
+            _ if name.starts_with("dump.") => Self::from_lammps_dump(&source()?),
+            _ => Err(StructureError::UnknownFormat),
+        }
+    }
+
+    pub fn from_xyz(source: &str) -> Result<Self, StructureError> {
+        Self::read(source, xyz::read_frame)
+    }
+
+    /// reads a LAMMPS dump, the species are the atom types unless the dump has an `element`
+    /// column, see `with_type_elements`
+    pub fn from_lammps_dump(source: &str) -> Result<Self, StructureError> {
+        Self::read(source, lammps::read_frame)
+    }
+
+    fn read<'a>(
+        source: &'a str,
+        read_frame: impl Fn(&mut Lines<'a>) -> Result<Option<Structure>, StructureError>,
+    ) -> Result<Self, StructureError> {
+        let lines: Box<dyn Iterator<Item = (usize, &str)>> =
+            Box::new(source.lines().enumerate().map(|(i, line)| (i + 1, line)));
+        let mut lines = lines.peekable();
+        let mut frames: Vec<Structure> = Vec::new();
+        loop {
+            let line = lines.peek().map_or(0, |(number, _)| *number);
+            let Some(frame) = read_frame(&mut lines)? else {
+                break;
+            };
+            if let Some(first) = frames.first() {
+                if frame.len() != first.len() {
+                    return Err(StructureError::Syntax {
+                        line,
+                        message: format!(
+                            "the frame has {} atoms, the first one has {}",
+                            frame.len(),
+                            first.len()
+                        ),
+                    });
+                }
+            }
+            frames.push(frame);
+        }
+        Ok(Self { frames })
+    }
+
+    /// names the elements of the numbered atom types of LAMMPS dumps, starting with type 1
+    pub fn with_type_elements(mut self, elements: &[&str]) -> Self {
+        for species in self.frames.iter_mut().flat_map(|frame| &mut frame.species) {
+            let element = species
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| elements.get(index.checked_sub(1)?));
+            if let Some(element) = element {
+                *species = structure::species(element);
+            }
+        }
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// moves every atom to the periodic image closest to its position in the previous frame,
+    /// so atoms leave the cell instead of jumping to its other side
+    pub fn unwrap_periodic(&mut self) {
+        for i in 1..self.frames.len() {
+            let (previous, current) = self.frames.split_at_mut(i);
+            let (previous, current) = (&previous[i - 1], &mut current[0]);
+            let Some(lattice) = current.lattice else {
+                continue;
+            };
+            for (position, last) in current.positions.iter_mut().zip(&previous.positions) {
+                *position = last + lattice.minimum_image(&(*position - last));
+            }
+        }
+    }
+
+    /// the positions at `time` counted in frames, interpolating linearly along the shortest
+    /// periodic displacement between two frames
+    pub fn positions_at(&self, time: f32) -> Vec<Point<f32>> {
+        let Some(last) = self.frames.len().checked_sub(1) else {
+            return Vec::new();
+        };
+        let time = time.clamp(0.0, last as f32);
+        let index = (time.floor() as usize).min(last);
+        let (from, t) = (&self.frames[index], time - index as f32);
+        let Some(to) = self.frames.get(index + 1).filter(|_| t > 0.0) else {
+            return from.positions.clone();
+        };
+        from.positions
+            .iter()
+            .zip(&to.positions)
+            .map(|(a, b)| {
+                let d = b - a;
+                let d = from.lattice.map_or(d, |lattice| lattice.minimum_image(&d));
+                a + d * t
+            })
+            .collect()
+    }
+}
+
+/// How the camera moves while the trajectory plays.
+#[derive(Debug, Clone, Default)]
+pub enum CameraPath {
+    /// the camera of the scene
+    #[default]
+    Fixed,
+    /// circles the camera of the scene around the axis through its target
+    Orbit { axis: Vector<f32>, turns: f32 },
+    /// passes through the cameras at even intervals, interpolating their placement and lens
+    /// linearly, the image size and shutter are taken from the first one
+    Keyframes(Vec<CameraSettings>),
+}
+
+impl CameraPath {
+    /// the camera at `progress`, which goes from 0 at the first to 1 at the last image
+    pub fn camera(&self, scene: &CameraSettings, progress: f32) -> CameraSettings {
+        match self {
+            CameraPath::Fixed => *scene,
+            CameraPath::Orbit { axis, turns } => {
+                let rotation = UnitQuaternion::from_axis_angle(
+                    &Unit::new_normalize(*axis),
+                    turns * TAU * progress,
+                );
+                CameraSettings {
+                    eye: scene.target + rotation * (scene.eye - scene.target),
+                    ..*scene
+                }
+            }
+            CameraPath::Keyframes(cameras) => {
+                let Some(first) = cameras.first() else {
+                    return *scene;
+                };
+                let position = progress.clamp(0.0, 1.0) * (cameras.len() - 1) as f32;
+                let index = (position as usize).min(cameras.len().saturating_sub(2));
+                let (a, b) = (&cameras[index], cameras.get(index + 1).unwrap_or(first));
+                let t = position - index as f32;
+                CameraSettings {
+                    eye: a.eye.coords.lerp(&b.eye.coords, t).into(),
+                    target: a.target.coords.lerp(&b.target.coords, t).into(),
+                    fov: a.fov + (b.fov - a.fov) * t,
+                    aperture: a.aperture + (b.aperture - a.aperture) * t,
+                    focus_distance: a
+                        .focus_distance
+                        .zip(b.focus_distance)
+                        .map(|(a, b)| a + (b - a) * t),
+                    ..*first
+                }
+            }
+        }
+    }
+}
+
+/// The file format of the rendered images.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FrameFormat {
+    #[default]
+    Png,
+    /// OpenEXR with the linear radiance, for tonemapping and compositing afterwards
+    Exr,
+}
+
+impl FrameFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            FrameFormat::Png => "png",
+            FrameFormat::Exr => "exr",
+        }
+    }
+}
+
+/// Renders a trajectory into numbered images named `frame_00000.png` and so on.
+pub struct TrajectoryRenderer {
+    trajectory: Trajectory,
+    scene: Scene,
+    /// what the first objects of the scene depict
+    parts: Vec<Part>,
+    /// images per frame of the trajectory
+    steps: usize,
+    camera_path: CameraPath,
+    format: FrameFormat,
+    first_image: usize,
+}
+
+impl TrajectoryRenderer {
+    /// a scene with the first frame in front of a white background, like `Structure::scene`
+    pub fn new(
+        trajectory: Trajectory,
+        representation: &Representation,
+        table: &PeriodicTable,
+    ) -> Self {
+        let first = trajectory.frames.first().cloned().unwrap_or_default();
+        let (parts, objects) = first.parts(representation, table).into_iter().unzip();
+        Self {
+            trajectory,
+            scene: Scene::new(Color(1.0, 1.0, 1.0))
+                .add_objects(objects)
+                .with_camera(first.camera()),
+            parts,
+            steps: 1,
+            camera_path: CameraPath::default(),
+            format: FrameFormat::default(),
+            first_image: 0,
+        }
+    }
+
+    /// changes the scene, for example to add lights or other objects, the objects of the
+    /// structure have to stay at the start of the scene
+    pub fn with_scene(mut self, f: impl FnOnce(Scene) -> Scene) -> Self {
+        self.scene = f(self.scene);
+        self
+    }
+
+    /// renders `steps` images per frame of the trajectory, interpolating the positions
+    pub fn with_interpolation(mut self, steps: usize) -> Self {
+        self.steps = steps.max(1);
+        self
+    }
+
+    /// see `Trajectory::unwrap_periodic`
+    pub fn with_unwrapping(mut self) -> Self {
+        self.trajectory.unwrap_periodic();
+        self
+    }
+
+    pub fn with_camera_path(mut self, camera_path: CameraPath) -> Self {
+        self.camera_path = camera_path;
+        self
+    }
+
+    pub fn with_format(mut self, format: FrameFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// starts rendering at the given image, to resume an interrupted render
+    pub fn with_first_image(mut self, image: usize) -> Self {
+        self.first_image = image;
+        self
+    }
+
+    pub fn scene(&self) -> &Scene {
+        &self.scene
+    }
+
+    pub fn image_count(&self) -> usize {
+        match self.trajectory.len() {
+            0 => 0,
+            frames => (frames - 1) * self.steps + 1,
+        }
+    }
+
+    /// the file name of an image, like `frame_00042.png`
+    pub fn file_name(&self, image: usize) -> String {
+        format!("frame_{:05}.{}", image, self.format.extension())
+    }
+
+    /// the first image without a file in `directory`, where an interrupted render resumes
+    pub fn first_missing(&self, directory: impl AsRef<Path>) -> usize {
+        let directory = directory.as_ref();
+        (0..self.image_count())
+            .find(|&image| !directory.join(self.file_name(image)).exists())
+            .unwrap_or(self.image_count())
+    }
+
+    /// moves the atoms and bonds to their positions in an image and returns its camera,
+    /// if its shutter is open they keep moving towards the next image while it is
+    pub fn set_image(&mut self, image: usize) -> CameraSettings {
+        let progress = match self.image_count() {
+            0 | 1 => 0.0,
+            count => image as f32 / (count - 1) as f32,
+        };
+        let camera = self
+            .camera_path
+            .camera(self.scene.camera_settings(), progress);
+        let mut ends = Vec::new();
+        if !camera.shutter.is_instant() && image + 1 < self.image_count() {
+            self.move_parts(image + 1, &[]);
+            ends = self
+                .scene
+                .objects()
+                .iter()
+                .take(self.parts.len())
+                .map(|object| Some(object.isometry))
+                .collect();
+        }
+        self.move_parts(image, &ends);
+        camera
+    }
+
+    /// `ends` are where the parts are at the end of the shutter interval
+    fn move_parts(&mut self, image: usize, ends: &[Option<Isometry<f32>>]) {
+        let time = image as f32 / self.steps as f32;
+        let positions = self.trajectory.positions_at(time);
+        let frame = (time as usize).min(self.trajectory.len().saturating_sub(1));
+        let lattice = self
+            .trajectory
+            .frames
+            .get(frame)
+            .and_then(|frame| frame.lattice);
+        let parts = &self.parts;
+        self.scene.move_objects(|objects| {
+            for (index, (object, part)) in objects.iter_mut().zip(parts).enumerate() {
+                object.end_isometry = ends.get(index).copied().flatten();
+                match *part {
+                    Part::Atom(i) => {
+                        object.isometry.translation = Translation::from(positions[i].coords);
+                    }
+                    Part::HalfBond { from, to } => {
+                        let Shape::Cylinder(cylinder) = &object.shape else {
+                            continue;
+                        };
+                        let d = positions[to] - positions[from];
+                        let d = lattice.map_or(d, |lattice| lattice.minimum_image(&d));
+                        let (start, middle) = (positions[from], positions[from] + d / 2.0);
+                        if let Some(moved) =
+                            structure::cylinder(start, middle, cylinder.radius, object.color)
+                        {
+                            object.shape = moved.shape;
+                            object.isometry = moved.isometry;
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    /// renders the images from the first one into `directory`, `progress` counts the images
+    pub fn render(
+        &mut self,
+        directory: impl AsRef<Path>,
+        progress: ProgressBar,
+    ) -> ImageResult<Vec<PathBuf>> {
+        let directory = directory.as_ref();
+        fs::create_dir_all(directory)?;
+        let images = self.first_image..self.image_count();
+        progress.set_length(images.len() as u64);
+        let mut paths = Vec::new();
+        for image in images {
+            let mut camera = self.set_image(image).build();
+            let settings = *self.scene.render_settings();
+            self.scene
+                .render_with_settings(&mut camera, &settings, ProgressBar::hidden());
+            let path = directory.join(self.file_name(image));
+            camera.save_img(&path)?;
+            paths.push(path);
+            progress.inc(1);
+        }
+        progress.finish();
+        Ok(paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parry3d::shape::Cuboid;
+
+    use super::*;
+    use crate::objects::{Object, RenderSettings};
+
+    /// an atom leaving the cell through its side at x = 10
+    const CROSSING: &str = "\
+1
+Lattice=\"10 0 0 0 10 0 0 0 10\"
+Na 9.5 1 1
+1
+Lattice=\"10 0 0 0 10 0 0 0 10\"
+Na 0.5 1 1
+1
+Lattice=\"10 0 0 0 10 0 0 0 10\"
+Na 1.5 1 1
+";
+
+    fn renderer() -> TrajectoryRenderer {
+        let trajectory = Trajectory::from_xyz(CROSSING).unwrap();
+        TrajectoryRenderer::new(
+            trajectory,
+            &Representation::default(),
+            &PeriodicTable::new(),
+        )
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("ray-trajectory-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn unwraps_to_the_closest_image() {
+        let mut trajectory = Trajectory::from_xyz(CROSSING).unwrap();
+        assert!((trajectory.positions_at(0.5)[0].x - 10.0).abs() < 1e-5);
+        trajectory.unwrap_periodic();
+        let x: Vec<f32> = trajectory
+            .frames
+            .iter()
+            .map(|frame| frame.positions[0].x)
+            .collect();
+        assert_eq!(x, [9.5, 10.5, 11.5]);
+        assert_eq!(trajectory.positions_at(1.5)[0].x, 11.0);
+    }
+
+    #[test]
+    fn resumes_after_existing_frames() {
+        let renderer = renderer().with_interpolation(2);
+        assert_eq!(renderer.image_count(), 5);
+        let directory = temp_dir("resume");
+        for image in 0..2 {
+            fs::write(directory.join(renderer.file_name(image)), []).unwrap();
+        }
+        let missing = renderer.first_missing(&directory);
+        let exr = renderer.with_format(FrameFormat::Exr);
+        let missing_exr = exr.first_missing(&directory);
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(missing, 2);
+        assert_eq!(exr.file_name(2), "frame_00002.exr");
+        assert_eq!(missing_exr, 0);
+    }
+
+    #[test]
+    fn interpolates_keyframes() {
+        let camera = |x: f32, fov: f32, width: u32| CameraSettings {
+            eye: Point::new(x, 0.0, 0.0),
+            fov,
+            width,
+            aperture: fov / 10.0,
+            focus_distance: Some(x),
+            ..Default::default()
+        };
+        let path = CameraPath::Keyframes(vec![
+            camera(0.0, 1.0, 20),
+            camera(10.0, 2.0, 30),
+            camera(30.0, 1.0, 40),
+        ]);
+        let scene = CameraSettings::default();
+        let quarter = path.camera(&scene, 0.25);
+        assert_eq!(quarter.eye, Point::new(5.0, 0.0, 0.0));
+        assert_eq!(quarter.fov, 1.5);
+        assert_eq!(quarter.aperture, 0.15);
+        assert_eq!(quarter.focus_distance, Some(5.0));
+        assert_eq!(quarter.width, 20);
+        assert_eq!(path.camera(&scene, 1.0).eye, Point::new(30.0, 0.0, 0.0));
+        assert_eq!(
+            CameraPath::Keyframes(Vec::new()).camera(&scene, 0.5).eye,
+            scene.eye
+        );
+    }
+
+    #[test]
+    fn exr_frames_keep_the_linear_radiance() {
+        let mut renderer = renderer()
+            .with_format(FrameFormat::Exr)
+            .with_scene(|scene| {
+                // a bright wall filling the view, away from the atom
+                scene
+                    .add_object(
+                        Object::new(
+                            Shape::Cuboid(Cuboid::new(Vector::new(100.0, 100.0, 1.0))),
+                            Isometry::translation(0.0, 0.0, -150.0),
+                            Color(4.0, 3.0, 2.0),
+                        )
+                        .with_light_source(),
+                    )
+                    .with_camera(CameraSettings {
+                        eye: Point::new(0.0, 0.0, -100.0),
+                        target: Point::new(1.0, 2.0, -200.0),
+                        width: 4,
+                        height: 4,
+                        ..Default::default()
+                    })
+                    .with_render_settings(RenderSettings {
+                        rays: 1,
+                        max_reflections: 1,
+                        ..Default::default()
+                    })
+            });
+        let directory = temp_dir("exr");
+        let paths = renderer.render(&directory, ProgressBar::hidden());
+        let image = paths.map(|paths| image::open(&paths[0]));
+        fs::remove_dir_all(&directory).unwrap();
+        let radiance = image.unwrap().unwrap().into_rgb32f();
+        assert_eq!(radiance.get_pixel(2, 2).0, [4.0, 3.0, 2.0]);
+    }
+}
@@ -1,9 +1,72 @@
-use std::f32::consts::PI;
+use std::{cell::RefCell, f32::consts::PI, marker::PhantomData};
 
+use nalgebra::{Matrix3, Rotation3};
 use parry3d::math::Vector;
+use rand::{Rng, SeedableRng, distributions::Standard, prelude::Distribution, rngs::SmallRng};
+
+thread_local! {
+    static RNG: RefCell<SmallRng> = RefCell::new(SmallRng::from_entropy());
+}
+
+/// a random value from the random number generator of the current thread
+pub fn random<T>() -> T
+where
+    Standard: Distribution<T>,
+{
+    RNG.with(|rng| rng.borrow_mut().gen())
+}
+
+/// Gives the random number generator of the thread back its state from before `seed_rng` when
+/// dropped, so later unseeded renders on the thread do not repeat the seeded numbers.
+pub struct SeededRng {
+    previous: Option<SmallRng>,
+    /// the generator belongs to the thread the guard was created on
+    thread: PhantomData<*const ()>,
+}
+
+impl Drop for SeededRng {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            RNG.with(|rng| *rng.borrow_mut() = previous);
+        }
+    }
+}
+
+/// reseeds the random number generator of the current thread until the guard is dropped
+#[must_use = "the generator is restored when the guard is dropped"]
+pub fn seed_rng(seed: u64) -> SeededRng {
+    let previous = RNG.with(|rng| rng.replace(SmallRng::seed_from_u64(seed)));
+    SeededRng {
+        previous: Some(previous),
+        thread: PhantomData,
+    }
+}
 
 pub fn rand_unit_vec_pos_z() -> Vector<f32> {
-    let theta = 2.0 * PI * rand::random::<f32>();
-    let phi = (rand::random::<f32>()).acos();
+    let theta = 2.0 * PI * random::<f32>();
+    let phi = (random::<f32>()).acos();
     Vector::new(theta.cos() * phi.sin(), theta.sin() * phi.sin(), phi.cos())
 }
+
+/// a rotation mapping the z axis onto `direction`, which has to be normalized
+pub fn local_frame(direction: Vector<f32>) -> Rotation3<f32> {
+    // Duff et al. 2017, "Building an Orthonormal Basis, Revisited"
+    let sign = 1.0_f32.copysign(direction.z);
+    let a = -1.0 / (sign + direction.z);
+    let b = direction.x * direction.y * a;
+    let tangent = Vector::new(
+        1.0 + sign * direction.x * direction.x * a,
+        sign * b,
+        -sign * direction.x,
+    );
+    let bitangent = Vector::new(b, sign + direction.y * direction.y * a, -direction.y);
+    Rotation3::from_matrix_unchecked(Matrix3::from_columns(&[tangent, bitangent, direction]))
+}
+
+/// the multiple importance sampling weight of a sample drawn with density `pdf`
+/// against another strategy with density `other`
+pub fn power_heuristic(pdf: f32, other: f32) -> f32 {
+    let pdf2 = pdf * pdf;
+    let sum = pdf2 + other * other;
+    if sum == 0.0 { 0.0 } else { pdf2 / sum }
+}
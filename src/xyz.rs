@@ -0,0 +1,234 @@
+//! Reading XYZ files, including the lattice and column layout of extended XYZ files.
+
+use std::collections::HashMap;
+
+use parry3d::math::{Point, Vector};
+
+use crate::{
+    crystal::Lattice,
+    structure::{self, Structure, StructureError},
+};
+
+impl Structure {
+    /// reads the first frame of an XYZ file
+    pub fn from_xyz(source: &str) -> Result<Self, StructureError> {
+        let mut lines = source.lines().enumerate().map(|(i, line)| (i + 1, line));
+        read_frame(&mut lines)?.ok_or(StructureError::Syntax {
+            line: 1,
+            message: "missing the number of atoms".into(),
+        })
+    }
+}
+
+/// reads the next frame, `None` at the end of the file
+pub(crate) fn read_frame<'a>(
+    lines: &mut impl Iterator<Item = (usize, &'a str)>,
+) -> Result<Option<Structure>, StructureError> {
+    let Some((number, count)) = lines.find(|(_, line)| !line.trim().is_empty()) else {
+        return Ok(None);
+    };
+    let count: usize = count.trim().parse().map_err(|_| StructureError::Syntax {
+        line: number,
+        message: format!("invalid number of atoms `{}`", count.trim()),
+    })?;
+    let (number, comment) = lines.next().ok_or(StructureError::Syntax {
+        line: number + 1,
+        message: "missing the comment line".into(),
+    })?;
+    let properties = key_values(comment);
+
+    let lattice = match properties.get("lattice") {
+        Some(lattice) => {
+            let values: Vec<f32> = lattice
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .ok()
+                .filter(|values: &Vec<f32>| values.len() == 9)
+                .ok_or_else(|| StructureError::Syntax {
+                    line: number,
+                    message: format!("invalid lattice `{}`", lattice),
+                })?;
+            let vector = |i: usize| Vector::new(values[i], values[i + 1], values[i + 2]);
+            Some(Lattice::new(vector(0), vector(3), vector(6)))
+        }
+        None => None,
+    };
+    let (species_column, pos_column) = match properties.get("properties") {
+        Some(properties) => columns(properties).ok_or_else(|| StructureError::Syntax {
+            line: number,
+            message: format!("invalid properties `{}`", properties),
+        })?,
+        None => (0, 1),
+    };
+
+    let mut structure = Structure {
+        lattice,
+        ..Default::default()
+    };
+    let mut last = number;
+    for _ in 0..count {
+        let (number, line) = lines.next().ok_or(StructureError::Syntax {
+            line: last + 1,
+            message: format!("expected {} atoms", count),
+        })?;
+        last = number;
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let field = |column: usize, name: &str| {
+            fields
+                .get(column)
+                .copied()
+                .ok_or_else(|| StructureError::Syntax {
+                    line: number,
+                    message: format!("missing {}", name),
+                })
+        };
+        let coordinate = |i: usize, name: &str| {
+            let value = field(pos_column + i, name)?;
+            value.parse().map_err(|_| StructureError::Syntax {
+                line: number,
+                message: format!("invalid {} `{}`", name, value),
+            })
+        };
+        structure
+            .species
+            .push(structure::species(field(species_column, "species")?));
+        structure.positions.push(Point::new(
+            coordinate(0, "x")?,
+            coordinate(1, "y")?,
+            coordinate(2, "z")?,
+        ));
+    }
+    Ok(Some(structure))
+}
+
+/// parses the `key=value` pairs of an extended XYZ comment line, keys are lowercase
+fn key_values(comment: &str) -> HashMap<String, String> {
+    let mut values = HashMap::new();
+    let mut rest = comment.trim();
+    while let Some(equals) = rest.find('=') {
+        let key = rest[..equals].trim();
+        let key = key.rsplit(char::is_whitespace).next().unwrap_or(key);
+        let value = rest[equals + 1..].trim_start();
+        let (value, remainder) = match value.strip_prefix('"') {
+            Some(quoted) => match quoted.find('"') {
+                Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                None => (quoted, ""),
+            },
+            None => {
+                let end = value.find(char::is_whitespace).unwrap_or(value.len());
+                (&value[..end], &value[end..])
+            }
+        };
+        values.insert(key.to_lowercase(), value.into());
+        rest = remainder;
+    }
+    values
+}
+
+/// the columns of the species and the first position component in a `Properties` value
+/// like `species:S:1:pos:R:3`
+fn columns(properties: &str) -> Option<(usize, usize)> {
+    let fields: Vec<&str> = properties.split(':').collect();
+    let (mut species, mut pos) = (None, None);
+    let mut column = 0;
+    for property in fields.chunks(3) {
+        let [name, _, count] = property else {
+            return None;
+        };
+        match name.to_lowercase().as_str() {
+            "species" => species = Some(column),
+            "pos" => pos = Some(column),
+            _ => (),
+        }
+        column += count.parse::<usize>().ok()?;
+    }
+    Some((species?, pos?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trajectory::Trajectory;
+
+    const WATER: &str = "\
+3
+water
+O 0.0 0.0 0.117
+H 0.0 0.757 -0.467
+h 0.0 -0.757 -0.467
+";
+
+    #[test]
+    fn reads_plain_frame() {
+        let structure = Structure::from_xyz(WATER).unwrap();
+        assert_eq!(structure.species, ["O", "H", "H"]);
+        assert_eq!(structure.positions[1], Point::new(0.0, 0.757, -0.467));
+        assert!(structure.lattice.is_none());
+    }
+
+    #[test]
+    fn reads_extended_frame() {
+        let source = "\
+2
+Lattice=\"4 0 0 0 4 0 0 0 5\" Properties=id:I:1:species:S:1:pos:R:3 pbc=\"T T T\"
+1 Na 0 0 0
+2 Cl 2 2 2.5
+";
+        let structure = Structure::from_xyz(source).unwrap();
+        assert_eq!(structure.species, ["Na", "Cl"]);
+        assert_eq!(structure.positions[1], Point::new(2.0, 2.0, 2.5));
+        let lattice = structure.lattice.unwrap();
+        assert_eq!(lattice.vectors[2], Vector::new(0.0, 0.0, 5.0));
+    }
+
+    #[test]
+    fn reads_comment_key_values() {
+        let values = key_values("Time=1.5 lattice=\"1 0 0\" note=\"a b\" plain words");
+        assert_eq!(values["time"], "1.5");
+        assert_eq!(values["lattice"], "1 0 0");
+        assert_eq!(values["note"], "a b");
+        assert_eq!(columns("species:S:1:pos:R:3"), Some((0, 1)));
+        assert_eq!(columns("species:S:1:vel:R:3"), None);
+    }
+
+    #[test]
+    fn reports_missing_atoms() {
+        let source = "4\nshort frame\nO 0 0 0\nH 0 0 1\n";
+        let Err(StructureError::Syntax { line, message }) = Structure::from_xyz(source) else {
+            panic!("read a frame with missing atoms");
+        };
+        assert_eq!(line, 5);
+        assert_eq!(message, "expected 4 atoms");
+    }
+
+    #[test]
+    fn reports_invalid_fields() {
+        let Err(StructureError::Syntax { line, .. }) = Structure::from_xyz("three\n\n") else {
+            panic!("read an invalid number of atoms");
+        };
+        assert_eq!(line, 1);
+        let Err(StructureError::Syntax { line, message }) = Structure::from_xyz("1\n\nO 0 x 0\n")
+        else {
+            panic!("read an invalid coordinate");
+        };
+        assert_eq!((line, message.as_str()), (3, "invalid y `x`"));
+    }
+
+    #[test]
+    fn reads_every_frame() {
+        let source = format!("{}{}", WATER, WATER);
+        let trajectory = Trajectory::from_xyz(&source).unwrap();
+        assert_eq!(trajectory.len(), 2);
+    }
+
+    #[test]
+    fn rejects_frames_with_other_atom_counts() {
+        let source = format!("{}2\nsecond\nO 0 0 0\nH 0 0 1\n", WATER);
+        let Err(StructureError::Syntax { line, message }) = Trajectory::from_xyz(&source) else {
+            panic!("read frames with different atom counts");
+        };
+        assert_eq!(line, 6);
+        assert_eq!(message, "the frame has 2 atoms, the first one has 3");
+    }
+}